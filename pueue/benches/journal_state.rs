@@ -0,0 +1,45 @@
+use std::{collections::HashMap, env::vars, path::PathBuf};
+
+use chrono::Local;
+use criterion::{Criterion, criterion_group, criterion_main};
+use pueue::daemon::internal_state::state::InternalState;
+use pueue_lib::{Settings, Task, TaskStatus, state::PUEUE_DEFAULT_GROUP};
+
+/// Create a large state with a few hundred tasks and save it once.
+/// Every following save only appends the changed task to the state journal.
+pub fn state(crit: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut settings = Settings::default();
+    settings.shared.pueue_directory = Some(dir.path().to_owned());
+
+    let mut state = InternalState::new();
+
+    for _ in 0..400 {
+        let task = Task::new(
+            "ls".into(),
+            PathBuf::from("/tmp"),
+            HashMap::from_iter(vars()),
+            PUEUE_DEFAULT_GROUP.to_owned(),
+            TaskStatus::Queued {
+                enqueued_at: Local::now(),
+            },
+            Vec::new(),
+            0,
+            None,
+        );
+
+        state.add_task(task);
+    }
+    state.save(&settings).unwrap();
+
+    crit.bench_function("Save single task change to journal", |b| {
+        b.iter(|| {
+            let task = state.task_mut(0).unwrap();
+            task.priority += 1;
+            state.save(&settings).unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, state);
+criterion_main!(benches);
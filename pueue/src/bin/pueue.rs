@@ -10,13 +10,13 @@ use color_eyre::{
     eyre::{WrapErr, bail},
 };
 use pueue::client::{
+    all_contexts_state,
     cli::{CliArguments, ColorChoice, Shell, SubCommand},
+    contexts::{apply_context, connect, context_command},
     handle_command,
     style::OutputStyle,
 };
-use pueue_lib::{
-    Client, network::socket::ConnectionSettings, secret::read_shared_secret, settings::Settings,
-};
+use pueue_lib::settings::Settings;
 
 /// This is the main entry point of the client.
 ///
@@ -26,7 +26,8 @@ use pueue_lib::{
 /// - Read the config
 /// - Default to `status` subcommand if no subcommand was specified
 /// - Determine the current
-/// - Initialize the [`Client`]
+/// - Apply the given or current context
+/// - Initialize the [`Client`](pueue_lib::Client)
 /// - Handle the command
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
@@ -67,6 +68,10 @@ async fn main() -> Result<()> {
         json: false,
         group: None,
         query: Vec::new(),
+        watch: None,
+        views: false,
+        format: None,
+        all_contexts: false,
     });
 
     // Determine whether we should color/style our output or not.
@@ -78,20 +83,38 @@ async fn main() -> Result<()> {
     };
     let style = OutputStyle::new(&settings, style_enabled);
 
-    // Only show version incompatibility warnings if we aren't supposed to output json.
-    let show_version_warning = match subcommand {
-        SubCommand::Status { json, .. } => !json,
-        SubCommand::Log { json, .. } => !json,
-        SubCommand::Group { json, .. } => !json,
+    // Contexts are managed locally, no daemon is involved.
+    if let SubCommand::Context { cmd } = subcommand {
+        return context_command(&settings, &style, cmd);
+    }
+
+    // The status of all contexts requires a connection to each of their daemons.
+    // Each context is applied to the settings of the configuration file, not the current context.
+    if let SubCommand::Status {
+        all_contexts: true,
+        query,
+        json,
+        group,
+        ..
+    } = subcommand
+    {
+        return all_contexts_state(&settings, &style, query, json, group).await;
+    }
+
+    // Connect to the daemon of the given or the current context, if there's one.
+    apply_context(&mut settings, opt.context.as_deref())?;
+
+    // Only show version incompatibility warnings if we aren't supposed to output json or any
+    // other machine-readable format.
+    let show_version_warning = match &subcommand {
+        SubCommand::Status { json, format, .. } => !*json && format.is_none(),
+        SubCommand::Log { json, format, .. } => !*json && format.is_none(),
+        SubCommand::Group { json, .. } => !*json,
         _ => true,
     };
 
     // Create client to talk with the daemon and connect.
-    let connection_settings = ConnectionSettings::try_from(settings.shared.clone())?;
-    let secret = read_shared_secret(&settings.shared.shared_secret_path())?;
-    let mut client = Client::new(connection_settings, &secret, show_version_warning)
-        .await
-        .context("Failed to initialize client.")?;
+    let mut client = connect(&settings, show_version_warning).await?;
 
     handle_command(&mut client, settings, &style, subcommand).await?;
 
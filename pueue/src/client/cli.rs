@@ -1,11 +1,11 @@
-use std::path::PathBuf;
+use std::{path::PathBuf, str::FromStr};
 
 use chrono::{TimeDelta, prelude::*};
 use clap::{ArgAction, Parser, ValueEnum, ValueHint};
 use interim::*;
-use pueue_lib::message::Signal;
+use pueue_lib::{message::Signal, task::DependencyCondition};
 
-use crate::client::commands::WaitTargetStatus;
+use crate::client::{commands::WaitTargetStatus, output_format::OutputFormat};
 
 #[derive(Parser, Debug, Clone)]
 pub enum SubCommand {
@@ -22,9 +22,23 @@ pub enum SubCommand {
     #[command(trailing_var_arg = true)]
     Add {
         /// The command to be added.
-        #[arg(required = true, num_args(1..), value_hint = ValueHint::CommandWithArguments)]
+        ///
+        /// If a template is used, these are the template's parameters in the form `KEY=VALUE`.
+        #[arg(
+            required_unless_present = "template",
+            num_args(1..),
+            value_hint = ValueHint::CommandWithArguments
+        )]
         command: Vec<String>,
 
+        /// Create the task from a template of the `client.templates` setting.
+        ///
+        /// Templates may define a command with `{{parameters}}`, a group, a priority, a label,
+        /// environment variables, a working directory and a timeout.
+        /// Explicitly specified options take precedence over those of the template.
+        #[arg(short, long, conflicts_with = "escape")]
+        template: Option<String>,
+
         /// Specify current working directory.
         #[arg(name = "working-directory", short = 'w', long, value_hint = ValueHint::DirPath)]
         working_directory: Option<PathBuf>,
@@ -71,8 +85,15 @@ pub enum SubCommand {
         /// Start the task once all specified tasks have successfully finished.
         ///
         /// As soon as one of the dependencies fails, this task will fail as well.
-        #[arg(name = "after", short, long, num_args(1..))]
-        dependencies: Vec<usize>,
+        ///
+        /// Dependencies may have a condition in the form of `ID:CONDITION`:
+        /// - `success`: The dependency must finish successfully. This is the default.
+        /// - `failure`: The dependency must fail, e.g. for cleanup or rollback tasks.
+        /// - `completion`: The dependency must finish, regardless of its result.
+        ///
+        /// If a condition can no longer be met, this task will fail.
+        #[arg(name = "after", short, long, num_args(1..), value_parser = parse_dependency)]
+        dependencies: Vec<(usize, DependencyCondition)>,
 
         /// Start this task with a higher priority.
         ///
@@ -87,12 +108,50 @@ pub enum SubCommand {
         #[arg(short, long)]
         label: Option<String>,
 
+        /// Kill the task once it has been running for this many seconds.
+        /// Time the task spends paused doesn't count towards this.
+        #[arg(long)]
+        timeout: Option<u64>,
+
         /// Only return the task id instead of a text.
         ///
         /// This is useful when working with dependencies in scripts.
         #[arg(short, long)]
         print_task_id: bool,
     },
+
+    /// Add a whole workflow of tasks from a YAML file.
+    ///
+    /// Tasks are identified by local names, which can be used to declare dependencies between
+    /// them. Either all tasks are added or none of them, e.g. if the dependencies contain a cycle.
+    ///
+    /// Example:
+    ///
+    /// tasks:
+    ///   build:
+    ///     command: cargo build
+    ///   test:
+    ///     command: cargo test
+    ///     after: [build]
+    ///   notify:
+    ///     command: notify-send 'Tests failed'
+    ///     after: ["test:failure"]
+    #[command(verbatim_doc_comment)]
+    Submit {
+        /// The path to the workflow file.
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+    },
+
+    /// Add and manage task arrays, e.g. for parameter sweeps.
+    ///
+    /// A task array consists of one task per combination of parameter values.
+    /// Its members share a label and can be killed, restarted and inspected as a unit.
+    Array {
+        #[command(subcommand)]
+        cmd: ArrayCommand,
+    },
+
     /// Remove tasks from the list.
     /// Running or paused tasks need to be killed first.
     #[command(alias("rm"))]
@@ -336,17 +395,23 @@ pub enum SubCommand {
 or limit the amount of tasks listed.
 
 Syntax:
-   [column_selection]? [filter]* [order_by]? [limit]?
+   [column_selection]? [filters]? [column_selection]? [order_by]? [limit]?
 
 where:
   - column_selection := `columns=[column]([column],)*`
-  - column := `id | status | command | label | path | enqueue_at | dependencies | start | end`
+  - column := `id | status | priority | command | label | path | enqueue_at | dependencies | start | end`
+  - filters := `[filter] ([and | or]? [filters])*`, `not [filters]` or `([filters])`
+    (`not` binds the strongest, followed by `and` and then `or`.
+     Filters without an operator in between are combined via `and`.)
   - filter := `[filter_column] [filter_op] [filter_value]`
     (note: not all columns support all operators, see \"Filter columns\" below.)
-  - filter_column := `status | command | label | start | end | enqueue_at`
-  - filter_op := `= | != | < | > | %=`
+  - filter_column := `id | status | priority | command | label | path | group | exit_code |
+                      dependencies | duration | env.[name] | start | end | enqueue_at`
+  - filter_op := `= | != | < | > | %= | ~`
     (`%=` means 'contains', as in the test value is a substring of the column value)
-  - order_by := `order_by [column] [order_direction]`
+    (`~` means the column value matches the test value as a regular expression)
+  - order_by := `order_by [order_column] [order_direction]`
+  - order_column := `[column] | group | exit_code | duration`
   - order_direction := `asc | desc`
   - limit := `[limit_type]? [limit_count]`
   - limit_type := `first | last`
@@ -356,9 +421,12 @@ Filter columns:
   - `status` supports the operators `=`, `!=`
     against test values that are:
       - strings like `queued`, `stashed`, `paused`, `running`, `success`, `failed`
-  - `command`, `label` support the operators `=`, `!=`, `%=`
+  - `command`, `label`, `path`, `group`, `env.[name]` support the operators `=`, `!=`, `%=`, `~`
     against test values that are:
       - strings like `some text`
+      - quoted strings like `\"some (text) and more\"`, if the text contains a `)`, a keyword
+        or something that looks like another filter.
+        Double quotes and backslashes inside quoted strings are escaped via a backslash.
   - `start`, `end`, `enqueue_at` contain a datetime
     which support the operators `=`, `!=`, `<`, `>`
     against test values that are:
@@ -366,11 +434,26 @@ Filter columns:
       - time like `HH:mm:ss` or `HH:mm`
       - datetime like `YYYY-MM-DDHH:mm:ss`
         (note there is currently no separator between the date and the time)
+  - `id`, `priority`, `exit_code` support the operators `=`, `!=`, `<`, `>`
+    against integers. Only finished tasks with an exit code match `exit_code` filters.
+  - `dependencies` supports the operators `=`, `!=`
+    against a task id, i.e. whether the task depends on that task.
+  - `duration` supports the operators `=`, `!=`, `<`, `>`
+    against durations like `90s`, `2h` or `1d12h`.
+    Tasks that haven't been started don't match.
+
+Views:
+  Named queries can be defined in the `client.views` setting and used via `@name`,
+  e.g. `pueue status @failing first 5`. Use `pueue status --views` to list them.
 
 Examples:
   - `status=running`
   - `command%=echo`
   - `label=mytask`
+  - `status=failed or (status=running and start<10:00)`
+  - `not status=success label%=build`
+  - `group=build duration>2h exit_code!=0`
+  - `command~\"^cargo (build|test)\" env.RUST_LOG=debug order_by duration desc`
   - `columns=id,status,command status=running start > 2023-05-2112:03:17 order_by command first 5`
 
 The formal syntax is defined here:
@@ -384,12 +467,119 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         /// Print the current state as json to stdout.
         /// This does not include the output of tasks.
         /// Use `log -j` if you want everything.
-        #[arg(short, long)]
+        #[arg(short, long, conflicts_with = "watch")]
         json: bool,
 
         #[arg(short, long)]
         /// Only show tasks of a specific group
         group: Option<String>,
+
+        /// Redraw the status every few seconds until Ctrl+C is pressed.
+        /// The interval defaults to 2 seconds.
+        ///
+        /// Tasks whose status changed since the last refresh are highlighted.
+        /// A different interval can be set via `--watch=SECONDS`.
+        #[arg(
+            short,
+            long,
+            value_name = "SECONDS",
+            num_args = 0..=1,
+            require_equals = true,
+            default_missing_value = "2"
+        )]
+        watch: Option<f64>,
+
+        /// Show the tasks of the daemons of all contexts in a single table.
+        #[arg(long, conflicts_with_all = ["watch", "views", "format"])]
+        all_contexts: bool,
+
+        /// List all views of the `client.views` setting.
+        ///
+        /// Views are named queries, which can be used via `pueue status @name`.
+        #[arg(long, conflicts_with_all = ["query", "json", "watch"])]
+        views: bool,
+
+        /// Print each task via a handlebars template or one of the `csv`, `tsv` and `yaml` presets.
+        ///
+        /// Templates are rendered once per task and have access to the fields `id`, `status`,
+        /// `result`, `exit_code`, `group`, `priority`, `label`, `command`, `original_command`,
+        /// `path`, `dependencies`, `created_at`, `enqueued_at`, `start`, `end`, `duration` (in
+        /// seconds), `timeout`, `array` and `envs`.
+        /// The query is applied as usual, but the column selection is ignored.
+        /// E.g. `--format '{{id}} {{status}} {{command}}'`.
+        #[arg(short = 'F', long, conflicts_with_all = ["json", "watch", "views"])]
+        format: Option<OutputFormat>,
+    },
+
+    /// Display tasks from the archive.
+    ///
+    /// Finished tasks are archived when they're cleaned or removed, if the `daemon.archive`
+    /// setting is enabled. Supports the same query syntax as `status`.
+    History {
+        /// A query in the syntax of `pueue status`, e.g. `status=failed first 10`.
+        query: Vec<String>,
+
+        /// Print the archived tasks as json.
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Export tasks including their groups, e.g. to move them to another machine.
+    ///
+    /// By default, the tasks of the default group are exported as JSON to stdout.
+    /// Use `pueue import` to recreate the tasks on another daemon.
+    Export {
+        /// Export these specific tasks.
+        task_ids: Vec<usize>,
+
+        /// Export all tasks of a group.
+        #[arg(short, long, conflicts_with = "all")]
+        group: Option<String>,
+
+        /// Export all tasks across all groups.
+        #[arg(short, long)]
+        all: bool,
+
+        /// Include the log output of the tasks.
+        #[arg(short, long)]
+        logs: bool,
+
+        /// Write the export to this file instead of stdout.
+        #[arg(short, long, value_hint = ValueHint::FilePath)]
+        output: Option<PathBuf>,
+    },
+
+    /// Recreate tasks that have been exported via `pueue export`.
+    ///
+    /// The tasks get new ids and their dependencies are remapped accordingly.
+    /// Unfinished tasks are stashed, unless `--enqueue` is given.
+    /// Missing groups are created with the configuration they had on the exporting daemon.
+    Import {
+        /// The exported file. Use `-` to read from stdin.
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+
+        /// Enqueue unfinished tasks right away instead of stashing them.
+        #[arg(short, long)]
+        enqueue: bool,
+    },
+
+    /// Display the dependency graph of tasks.
+    ///
+    /// Nodes are colored by the status of their task.
+    /// By default, all tasks are shown. If task ids are specified, only these tasks, their
+    /// dependencies and their dependants are shown.
+    Graph {
+        /// Only show the graph around these tasks.
+        task_ids: Vec<usize>,
+
+        /// Only show tasks of a specific group and their dependencies.
+        #[arg(short, long, conflicts_with = "task_ids")]
+        group: Option<String>,
+
+        /// The output format of the graph.
+        #[arg(short, long, value_enum, default_value = "tree")]
+        format: GraphFormat,
     },
 
     /// Display the log output of finished tasks.
@@ -425,6 +615,17 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         /// Show the whole output.
         #[arg(short, long)]
         full: bool,
+
+        /// Print each task via a handlebars template or one of the `csv`, `tsv` and `yaml` presets.
+        ///
+        /// Templates are rendered once per task and have access to the fields `id`, `status`,
+        /// `result`, `exit_code`, `group`, `priority`, `label`, `command`, `original_command`,
+        /// `path`, `dependencies`, `created_at`, `enqueued_at`, `start`, `end`, `duration` (in
+        /// seconds), `timeout`, `array` and `envs`.
+        /// The output of the task is available as `output`.
+        /// E.g. `--format '{{id}} {{status}} {{command}}'`.
+        #[arg(short = 'F', long, conflicts_with_all = ["json"])]
+        format: Option<OutputFormat>,
     },
 
     /// Follow the output of a currently running task.
@@ -442,6 +643,13 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         lines: Option<usize>,
     },
 
+    /// Open an interactive full-screen interface.
+    ///
+    /// It shows all groups, the tasks of the selected group and the output of the selected task.
+    /// Tasks can be started, paused, killed, restarted, edited and more via keybindings.
+    /// Press `?` inside the interface to see all keybindings.
+    Tui,
+
     /// Wait until tasks are finished.
     ///
     /// By default, this will wait for all tasks in the default group to finish.
@@ -496,6 +704,44 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
     /// manager.
     Shutdown,
 
+    /// Make the daemon reload its configuration file.
+    ///
+    /// Changes to the `shared` section of the configuration only take effect after a restart of
+    /// the daemon.
+    Reload,
+
+    /// Show the most recent runs of callbacks and hooks.
+    ///
+    /// This includes their exit code, their duration and their captured output.
+    Callbacks {
+        /// Print the list of callback runs as json.
+        #[arg(short, long)]
+        json: bool,
+
+        /// Print the captured output of each run.
+        #[arg(short, long)]
+        output: bool,
+    },
+
+    /// List or restore snapshots of tasks.
+    ///
+    /// The daemon takes a snapshot of all affected tasks before `reset`, `clean`, `remove` and
+    /// the removal of a group. By default, all snapshots are listed.
+    Snapshot {
+        /// Print the list of snapshots as json.
+        #[arg(short, long)]
+        json: bool,
+
+        #[command(subcommand)]
+        cmd: Option<SnapshotCommand>,
+    },
+
+    /// Restore the tasks of the most recent snapshot as stashed tasks.
+    ///
+    /// This reverts the last `reset`, `clean`, `remove` or group removal.
+    /// Calling it repeatedly restores older snapshots.
+    Undo,
+
     /// Set the amount of allowed parallel tasks
     ///
     /// By default, adjusts the amount of the default group.
@@ -513,6 +759,15 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
         group: Option<String>,
     },
 
+    /// Manage named contexts, which store the connection details of different daemons.
+    ///
+    /// The current context is used by all commands, unless another one is selected via
+    /// `--context`. By default, all contexts are listed.
+    Context {
+        #[command(subcommand)]
+        cmd: Option<ContextCommand>,
+    },
+
     /// Generates shell completion files.
     ///
     /// This can be ignored during normal operations.
@@ -526,6 +781,52 @@ https://github.com/Nukesor/pueue/issues/350#issue-1359083118"
     },
 }
 
+#[derive(Parser, Debug, Clone)]
+pub enum ContextCommand {
+    /// Add a context or replace an existing one with the same name.
+    ///
+    /// Details that aren't given are taken from the configuration file.
+    Add {
+        /// The name of the context.
+        name: String,
+
+        /// The hostname or ip address of the daemon.
+        #[arg(long, conflicts_with = "unix_socket")]
+        host: Option<String>,
+
+        /// The port of the daemon.
+        #[arg(long, conflicts_with = "unix_socket")]
+        port: Option<String>,
+
+        /// The TLS certificate of the daemon, which is used to verify its identity.
+        #[arg(long, value_hint = ValueHint::FilePath, conflicts_with = "unix_socket")]
+        cert: Option<PathBuf>,
+
+        /// Connect to the daemon via this unix socket instead of TCP.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        unix_socket: Option<PathBuf>,
+
+        /// The file containing the shared secret of the daemon.
+        #[arg(long, value_hint = ValueHint::FilePath)]
+        secret: Option<PathBuf>,
+
+        /// Use the context right away.
+        #[arg(long = "use")]
+        use_context: bool,
+    },
+
+    /// Use a context for all following commands.
+    ///
+    /// Omit the name to go back to the connection details of the configuration file.
+    Use { name: Option<String> },
+
+    /// List all contexts. The current one is marked with a `*`.
+    List,
+
+    /// Remove a context.
+    Remove { name: String },
+}
+
 #[derive(Parser, Debug, Clone)]
 pub enum EnvCommand {
     /// Set a variable for a specific task's environment.
@@ -550,6 +851,16 @@ pub enum EnvCommand {
     },
 }
 
+#[derive(Parser, Debug, Clone)]
+pub enum SnapshotCommand {
+    /// List all snapshots.
+    List,
+
+    /// Restore the tasks of a snapshot as stashed tasks.
+    /// The tasks get new ids and their dependencies are remapped accordingly.
+    Restore { id: usize },
+}
+
 #[derive(Parser, Debug, Clone)]
 pub enum GroupCommand {
     /// Add a group by name.
@@ -566,6 +877,128 @@ pub enum GroupCommand {
     /// Remove a group by name.
     /// This will move all tasks in this group to the default group!
     Remove { name: String },
+
+    /// Change the defaults that're applied to all tasks of a group.
+    ///
+    /// Groups that're declared in the configuration file are reset to their declaration
+    /// when the daemon's configuration is reloaded, but only if that declaration has changed.
+    Edit {
+        name: String,
+
+        /// Set an environment variable for all tasks of this group, e.g. `--env KEY=VALUE`.
+        /// Can be passed multiple times.
+        #[arg(short, long, value_parser = parse_env_var)]
+        env: Vec<(String, String)>,
+
+        /// Remove an environment variable from this group.
+        /// Can be passed multiple times.
+        #[arg(long)]
+        unset_env: Vec<String>,
+
+        /// The working directory of new tasks in this group,
+        /// if no working directory is specified when adding them.
+        #[arg(short, long, value_hint = ValueHint::DirPath, conflicts_with = "clear_working_directory")]
+        working_directory: Option<PathBuf>,
+
+        /// Remove the default working directory of this group.
+        #[arg(long)]
+        clear_working_directory: bool,
+
+        /// The shell command that should be used to run tasks of this group.
+        /// The task's command is inserted via the `{{ pueue_command_string }}` placeholder, e.g.
+        ///
+        /// pueue group edit gpu --shell-command bash -c '{{ pueue_command_string }}'
+        #[arg(
+            long,
+            num_args(1..),
+            allow_hyphen_values = true,
+            conflicts_with = "clear_shell_command"
+        )]
+        shell_command: Option<Vec<String>>,
+
+        /// Use the daemon's shell command for tasks of this group again.
+        #[arg(long)]
+        clear_shell_command: bool,
+    },
+}
+
+#[derive(Parser, Debug, Clone)]
+pub enum ArrayCommand {
+    /// Add one task for each combination of parameter values.
+    ///
+    /// The parameters are inserted into the command via `{{name}}` placeholders.
+    /// Each task gets its index inside the array via the `PUEUE_ARRAY_INDEX` environment variable.
+    ///
+    /// Example:
+    ///
+    /// pueue array add -p lr=0.1,0.01 -p seed=1..3 -- python train.py --lr {{lr}} --seed {{seed}}
+    #[command(trailing_var_arg = true, verbatim_doc_comment)]
+    Add {
+        /// The command template of the array's tasks.
+        #[arg(required = true, num_args(1..), value_hint = ValueHint::CommandWithArguments)]
+        command: Vec<String>,
+
+        /// A parameter in the form `NAME=VALUES`. Can be passed multiple times, in which case
+        /// a task is created for every combination of values.
+        ///
+        /// VALUES can be:
+        /// - a list: `a,b,c`
+        /// - an inclusive range of integers with an optional step: `1..10` or `0..100:10`
+        /// - a file with one value per line: `@values.txt`
+        #[arg(short, long = "param", required = true, verbatim_doc_comment, value_parser = parse_env_var)]
+        parameters: Vec<(String, String)>,
+
+        /// The name of the array, which is also used as the label of its tasks.
+        #[arg(short, long)]
+        name: Option<String>,
+
+        /// Specify current working directory.
+        #[arg(name = "working-directory", short = 'w', long, value_hint = ValueHint::DirPath)]
+        working_directory: Option<PathBuf>,
+
+        /// Assign the tasks to a group.
+        #[arg(short, long)]
+        group: Option<String>,
+
+        /// Create the tasks in Stashed state.
+        #[arg(short, long)]
+        stashed: bool,
+
+        /// Start the tasks with a higher priority.
+        #[arg(short = 'o', long)]
+        priority: Option<i32>,
+
+        /// Kill each task once it has been running for this many seconds.
+        #[arg(long)]
+        timeout: Option<u64>,
+    },
+
+    /// Show the progress of task arrays.
+    Status {
+        /// Only show this array.
+        name: Option<String>,
+
+        /// Print the list of arrays as json.
+        #[arg(short, long)]
+        json: bool,
+    },
+
+    /// Kill all running tasks of an array.
+    /// Queued tasks of the array are stashed, so they won't be started either.
+    Kill { name: String },
+
+    /// Restart all failed tasks of an array in place.
+    Restart { name: String },
+}
+
+#[derive(Parser, ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    /// A tree in the terminal, starting at tasks without dependencies.
+    Tree,
+    /// A Graphviz DOT graph.
+    Dot,
+    /// A Mermaid flowchart.
+    Mermaid,
 }
 
 #[derive(Parser, ValueEnum, Debug, Clone, PartialEq, Eq)]
@@ -615,10 +1048,41 @@ pub struct CliArguments {
     #[arg(short, long)]
     pub profile: Option<String>,
 
+    /// The name of the context, i.e. the daemon, this command should be sent to.
+    ///
+    /// This overrides the current context, which is set via `pueue context use`.
+    #[arg(long)]
+    pub context: Option<String>,
+
     #[command(subcommand)]
     pub cmd: Option<SubCommand>,
 }
 
+fn parse_env_var(src: &str) -> Result<(String, String), String> {
+    match src.split_once('=') {
+        Some((key, value)) if !key.is_empty() => Ok((key.to_string(), value.to_string())),
+        _ => Err(format!("expected KEY=VALUE, got '{src}'")),
+    }
+}
+
+fn parse_dependency(src: &str) -> Result<(usize, DependencyCondition), String> {
+    let (id, condition) = match src.split_once(':') {
+        Some((id, condition)) => {
+            let condition = DependencyCondition::from_str(condition).map_err(|_| {
+                format!("unknown condition '{condition}', expected success, failure or completion")
+            })?;
+            (id, condition)
+        }
+        None => (src, DependencyCondition::default()),
+    };
+
+    let id = id
+        .parse()
+        .map_err(|_| format!("expected a task id, got '{id}'"))?;
+
+    Ok((id, condition))
+}
+
 fn parse_delay_until(src: &str) -> Result<DateTime<Local>, String> {
     if let Ok(seconds) = src.parse::<i64>() {
         let delay_until = Local::now()
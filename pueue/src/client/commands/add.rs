@@ -9,10 +9,15 @@ use chrono::{DateTime, Local};
 use pueue_lib::{
     Client, Request, Response, Settings,
     message::{AddRequest, AddedTaskResponse},
+    task::DependencyCondition,
 };
 
 use super::{follow as follow_cmd, group_or_default, handle_response};
-use crate::{client::style::OutputStyle, format::format_datetime, internal_prelude::*};
+use crate::{
+    client::{style::OutputStyle, template::instantiate},
+    format::format_datetime,
+    internal_prelude::*,
+};
 
 #[allow(clippy::too_many_arguments)]
 pub async fn add_task(
@@ -20,25 +25,49 @@ pub async fn add_task(
     settings: Settings,
     style: &OutputStyle,
     mut command: Vec<String>,
-    working_directory: Option<PathBuf>,
+    template: Option<String>,
+    mut working_directory: Option<PathBuf>,
     escape: bool,
     start_immediately: bool,
     stashed: bool,
-    group: Option<String>,
+    mut group: Option<String>,
     delay_until: Option<DateTime<Local>>,
-    dependencies: Vec<usize>,
-    priority: Option<i32>,
-    label: Option<String>,
+    dependencies: Vec<(usize, DependencyCondition)>,
+    mut priority: Option<i32>,
+    mut label: Option<String>,
+    mut timeout: Option<u64>,
     print_task_id: bool,
     follow: bool,
 ) -> Result<()> {
+    // Catch the current environment for later injection into the task's process.
+    let mut envs = HashMap::from_iter(vars());
+
+    // When using a template, the positional arguments are the template's parameters.
+    // Explicitly specified options take precedence over the template's values.
+    if let Some(name) = &template {
+        let Some(definition) = settings.client.templates.get(name) else {
+            bail!("There's no template with the name '{name}'");
+        };
+        let instance = instantiate(name, definition, &command)?;
+
+        command = vec![instance.command];
+        working_directory = working_directory.or(instance.working_directory);
+        group = group.or(instance.group);
+        priority = priority.or(instance.priority);
+        label = label.or(instance.label);
+        timeout = timeout.or(instance.timeout);
+        envs.extend(instance.env);
+    }
+
     // Either take the user-specified path or default to the current working directory.
+    // The daemon prefers the group's default working directory over the latter.
     // This will give errors if connecting over TCP/TLS to a remote host that doesn't
     // have the same directory structure as the client
-    let path = working_directory
-        .as_ref()
-        .map(|path| Ok(path.clone()))
-        .unwrap_or_else(current_dir)?;
+    let path_is_fallback = working_directory.is_none();
+    let path = match working_directory {
+        Some(path) => path,
+        None => current_dir()?,
+    };
 
     // The user can request to escape any special shell characters in all parameter
     // strings before we concatenated them to a single string.
@@ -53,15 +82,17 @@ pub async fn add_task(
     let message = Request::Add(AddRequest {
         command: command.join(" "),
         path,
-        // Catch the current environment for later injection into the task's process.
-        envs: HashMap::from_iter(vars()),
+        path_is_fallback,
+        envs,
         start_immediately,
         stashed,
         group: group_or_default(&group),
         enqueue_at: delay_until,
-        dependencies,
+        dependencies: Vec::new(),
+        dependency_conditions: dependencies.into_iter().collect(),
         priority,
         label,
+        timeout,
     });
     client.send_request(message).await?;
 
@@ -0,0 +1,347 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env::{current_dir, vars},
+};
+
+use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_HORIZONTAL_ONLY};
+use crossterm::style::Color;
+use handlebars::Handlebars;
+use pueue_lib::{
+    Client, Response, Settings, State,
+    message::*,
+    task::{Task, TaskResult, TaskStatus},
+};
+use serde::Serialize;
+
+use super::{get_state, group_or_default, handle_response, handle_user_confirmation};
+use crate::{
+    client::{cli::ArrayCommand, style::OutputStyle},
+    internal_prelude::*,
+};
+
+/// Add a task array or control an existing one.
+pub async fn array(
+    client: &mut Client,
+    settings: Settings,
+    style: &OutputStyle,
+    cmd: ArrayCommand,
+) -> Result<()> {
+    match cmd {
+        ArrayCommand::Add {
+            command,
+            parameters,
+            name,
+            working_directory,
+            group,
+            stashed,
+            priority,
+            timeout,
+        } => {
+            let group = group_or_default(&group);
+            let path_is_fallback = working_directory.is_none();
+            let path = match working_directory {
+                Some(path) => path,
+                None => current_dir()?,
+            };
+
+            let commands = expand_commands(&command.join(" "), &parameters)?;
+            let request = AddArrayRequest {
+                name,
+                commands,
+                path,
+                path_is_fallback,
+                // Catch the current environment for later injection into the tasks' processes.
+                envs: HashMap::from_iter(vars()),
+                group,
+                stashed,
+                priority,
+                timeout,
+            };
+            client.send_request(request).await?;
+
+            let response = client.receive_response().await?;
+            let Response::AddedArray(response) = response else {
+                return handle_response(style, response);
+            };
+
+            let first = response.task_ids.first().copied().unwrap_or_default();
+            let last = response.task_ids.last().copied().unwrap_or_default();
+            println!(
+                "New array '{}' added with {} tasks (ids {first}-{last}).",
+                response.name,
+                response.task_ids.len()
+            );
+            Ok(())
+        }
+        ArrayCommand::Status { name, json } => {
+            let state = get_state(client).await?;
+            let mut summaries = summarize(&state);
+            if let Some(name) = name {
+                summaries.retain(|summary| summary.name == name);
+                if summaries.is_empty() {
+                    bail!("There's no array with the name '{name}'");
+                }
+            }
+
+            if json {
+                println!("{}", serde_json::to_string(&summaries)?);
+            } else if summaries.is_empty() {
+                println!("There're no task arrays.");
+            } else {
+                println!("{}", build_table(&summaries, style));
+            }
+            Ok(())
+        }
+        ArrayCommand::Kill { name } => {
+            if settings.client.show_confirmation_questions {
+                let state = get_state(client).await?;
+                let ids: Vec<usize> = array_members(&state, &name)?
+                    .iter()
+                    .map(|task| task.id)
+                    .collect();
+                handle_user_confirmation("kill", &ids)?;
+            }
+
+            // The daemon stashes the queued members and kills the running ones in one go.
+            client.send_request(Request::KillArray(name)).await?;
+            handle_response(style, client.receive_response().await?)
+        }
+        ArrayCommand::Restart { name } => {
+            // Members are always restarted in place, so they stay part of the array.
+            client.send_request(Request::RestartArray(name)).await?;
+            handle_response(style, client.receive_response().await?)
+        }
+    }
+}
+
+/// Render one command for every combination of parameter values.
+///
+/// The first parameter varies the slowest.
+fn expand_commands(template: &str, parameters: &[(String, String)]) -> Result<Vec<String>> {
+    let mut combinations = vec![BTreeMap::new()];
+    for (name, spec) in parameters {
+        let values = parse_values(spec)
+            .with_context(|| format!("Failed to read the values of parameter '{name}'"))?;
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.insert(name.clone(), value.clone());
+                    combination
+                })
+            })
+            .collect();
+    }
+
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_escape_fn(handlebars::no_escape);
+
+    combinations
+        .iter()
+        .map(|combination| {
+            handlebars
+                .render_template(template, combination)
+                .context("Failed to insert the parameters into the command")
+        })
+        .collect()
+}
+
+/// Parse the values of a single parameter.
+/// Values are either read from a file, an inclusive range of integers or a comma separated list.
+fn parse_values(spec: &str) -> Result<Vec<String>> {
+    if let Some(path) = spec.strip_prefix('@') {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read values from file {path:?}"))?;
+        let values: Vec<String> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        if values.is_empty() {
+            bail!("The file {path:?} doesn't contain any values");
+        }
+        return Ok(values);
+    }
+
+    if let Some(range) = parse_range(spec)? {
+        return Ok(range);
+    }
+
+    Ok(spec.split(',').map(ToString::to_string).collect())
+}
+
+/// Parse an inclusive range in the form of `START..END` or `START..END:STEP`.
+/// Returns `None` if the spec isn't a range.
+fn parse_range(spec: &str) -> Result<Option<Vec<String>>> {
+    let (range, step) = match spec.rsplit_once(':') {
+        Some((range, step)) => (range, step),
+        None => (spec, "1"),
+    };
+    let Some((start, end)) = range.split_once("..") else {
+        return Ok(None);
+    };
+    let (Ok(start), Ok(end), Ok(step)) = (
+        start.parse::<i64>(),
+        end.parse::<i64>(),
+        step.parse::<usize>(),
+    ) else {
+        return Ok(None);
+    };
+
+    if step == 0 {
+        bail!("The step of range '{spec}' has to be positive");
+    }
+    if start > end {
+        bail!("The range '{spec}' is empty");
+    }
+
+    Ok(Some(
+        (start..=end)
+            .step_by(step)
+            .map(|value| value.to_string())
+            .collect(),
+    ))
+}
+
+/// Get all tasks of an array.
+fn array_members<'a>(state: &'a State, name: &str) -> Result<Vec<&'a Task>> {
+    let members: Vec<&Task> = state
+        .tasks
+        .values()
+        .filter(|task| task.array.as_deref() == Some(name))
+        .collect();
+    if members.is_empty() {
+        bail!("There's no array with the name '{name}'");
+    }
+
+    Ok(members)
+}
+
+/// The roll-up of the statuses of an array's members.
+#[derive(Debug, Default, Serialize)]
+struct ArraySummary {
+    name: String,
+    group: String,
+    total: usize,
+    /// Queued, stashed or locked members.
+    pending: usize,
+    /// Running or paused members.
+    running: usize,
+    succeeded: usize,
+    failed: usize,
+}
+
+fn summarize(state: &State) -> Vec<ArraySummary> {
+    let mut summaries: BTreeMap<&str, ArraySummary> = BTreeMap::new();
+    for task in state.tasks.values() {
+        let Some(name) = &task.array else {
+            continue;
+        };
+        let summary = summaries.entry(name).or_insert_with(|| ArraySummary {
+            name: name.clone(),
+            group: task.group.clone(),
+            ..Default::default()
+        });
+
+        summary.total += 1;
+        match &task.status {
+            TaskStatus::Running { .. } | TaskStatus::Paused { .. } => summary.running += 1,
+            TaskStatus::Done {
+                result: TaskResult::Success,
+                ..
+            } => summary.succeeded += 1,
+            TaskStatus::Done { .. } => summary.failed += 1,
+            _ => summary.pending += 1,
+        }
+    }
+
+    summaries.into_values().collect()
+}
+
+fn build_table(summaries: &[ArraySummary], style: &OutputStyle) -> Table {
+    let header = [
+        "Array", "Group", "Progress", "Pending", "Running", "Success", "Failed",
+    ]
+    .into_iter()
+    .map(|name| style.styled_cell(name, None, Some(comfy_table::Attribute::Bold)));
+
+    let mut table = Table::new();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(header);
+
+    let count_cell = |count: usize, color: Color| {
+        if count == 0 {
+            Cell::new(count)
+        } else {
+            style.styled_cell(count, Some(color), None)
+        }
+    };
+
+    for summary in summaries {
+        let done = summary.succeeded + summary.failed;
+        table.add_row(vec![
+            Cell::new(&summary.name),
+            Cell::new(&summary.group),
+            Cell::new(format!("{done}/{}", summary.total)),
+            count_cell(summary.pending, Color::Yellow),
+            count_cell(summary.running, Color::Green),
+            count_cell(summary.succeeded, Color::Green),
+            count_cell(summary.failed, Color::Red),
+        ]);
+    }
+
+    // Explicitly force styling, in case we aren't on a tty, but `--color=always` is set.
+    if style.enabled {
+        table.enforce_styling();
+    }
+
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn values_of_ranges_and_lists() -> Result<()> {
+        assert_eq!(parse_values("1..3")?, vec!["1", "2", "3"]);
+        assert_eq!(parse_values("0..10:5")?, vec!["0", "5", "10"]);
+        assert_eq!(parse_values("-1..1")?, vec!["-1", "0", "1"]);
+        assert_eq!(parse_values("a,b")?, vec!["a", "b"]);
+        assert_eq!(parse_values("0.1")?, vec!["0.1"]);
+        assert!(parse_values("3..1").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn cartesian_product() -> Result<()> {
+        let parameters = vec![
+            ("lr".to_string(), "0.1,0.01".to_string()),
+            ("seed".to_string(), "1..2".to_string()),
+        ];
+        let commands = expand_commands("train --lr {{lr}} --seed {{ seed }}", &parameters)?;
+
+        assert_eq!(
+            commands,
+            vec![
+                "train --lr 0.1 --seed 1",
+                "train --lr 0.1 --seed 2",
+                "train --lr 0.01 --seed 1",
+                "train --lr 0.01 --seed 2",
+            ]
+        );
+
+        // Placeholders without a parameter are rejected.
+        assert!(expand_commands("train {{missing}}", &parameters).is_err());
+
+        Ok(())
+    }
+}
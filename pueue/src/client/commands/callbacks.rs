@@ -0,0 +1,97 @@
+use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_HORIZONTAL_ONLY};
+use crossterm::style::{Attribute, Color};
+use pueue_lib::{Client, Settings, message::*};
+
+use super::handle_response;
+use crate::{client::style::OutputStyle, internal_prelude::*};
+
+/// Show the most recent runs of callbacks and hooks.
+pub async fn callbacks(
+    client: &mut Client,
+    settings: &Settings,
+    style: &OutputStyle,
+    json: bool,
+    output: bool,
+) -> Result<()> {
+    client.send_request(Request::Callbacks).await?;
+
+    let response = client.receive_response().await?;
+
+    let Response::Callbacks(response) = response else {
+        return handle_response(style, response);
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&response.runs).unwrap());
+        return Ok(());
+    }
+
+    if response.runs.is_empty() {
+        println!("No callbacks have been run yet.");
+        return Ok(());
+    }
+
+    println!("{}", build_table(&response.runs, settings, style));
+
+    if output {
+        for (index, run) in response.runs.iter().enumerate() {
+            if run.output.is_empty() {
+                continue;
+            }
+
+            let header = format!("Output of run {index} ({}):", run.trigger);
+            println!(
+                "\n{}",
+                style.style_text(header, Some(Color::Green), Some(Attribute::Bold))
+            );
+            print!("{}", run.output);
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a table with one row per callback run.
+fn build_table(runs: &[CallbackRun], settings: &Settings, style: &OutputStyle) -> Table {
+    let header = [
+        "Run", "Task", "Trigger", "Result", "Start", "Duration", "Command",
+    ]
+    .into_iter()
+    .map(|name| style.styled_cell(name, None, Some(comfy_table::Attribute::Bold)));
+
+    let mut table = Table::new();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(header);
+
+    for (index, run) in runs.iter().enumerate() {
+        let result = match (run.timed_out, run.exit_code) {
+            (true, _) => style.styled_cell("Timed out", Some(Color::Red), None),
+            (false, Some(0)) => style.styled_cell("Success", Some(Color::Green), None),
+            (false, Some(code)) => {
+                style.styled_cell(format!("Failed ({code})"), Some(Color::Red), None)
+            }
+            (false, None) => style.styled_cell("Killed", Some(Color::Red), None),
+        };
+        let task = run.task_id.map(|id| id.to_string()).unwrap_or_default();
+        let duration = (run.end - run.start).num_milliseconds() as f64 / 1000.0;
+
+        table.add_row(vec![
+            Cell::new(index),
+            Cell::new(task),
+            Cell::new(&run.trigger),
+            result,
+            Cell::new(run.start.format(&settings.client.status_datetime_format)),
+            Cell::new(format!("{duration:.1}s")),
+            Cell::new(&run.command),
+        ]);
+    }
+
+    // Explicitly force styling, in case we aren't on a tty, but `--color=always` is set.
+    if style.enabled {
+        table.enforce_styling();
+    }
+
+    table
+}
@@ -0,0 +1,92 @@
+use std::{
+    io::{Read, stdin},
+    path::PathBuf,
+};
+
+use pueue_lib::{
+    Client, Response,
+    message::{ExportRequest, ImportRequest, ImportedResponse, TaskExport},
+};
+
+use super::{handle_response, selection_from_params};
+use crate::{client::style::OutputStyle, internal_prelude::*};
+
+/// Export tasks, their groups and optionally their logs as JSON.
+pub async fn export(
+    client: &mut Client,
+    style: &OutputStyle,
+    task_ids: Vec<usize>,
+    group: Option<String>,
+    all: bool,
+    logs: bool,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let tasks = selection_from_params(all, group, task_ids);
+    client.send_request(ExportRequest { tasks, logs }).await?;
+
+    let response = client.receive_response().await?;
+    let Response::Export(export) = response else {
+        return handle_response(style, response);
+    };
+
+    let serialized = serde_json::to_string_pretty(&export)?;
+    match output {
+        Some(path) => {
+            std::fs::write(&path, serialized)
+                .with_context(|| format!("Failed to write export to {path:?}"))?;
+            println!("Exported {} tasks to {path:?}", export.tasks.len());
+        }
+        None => println!("{serialized}"),
+    }
+
+    Ok(())
+}
+
+/// Recreate exported tasks on the daemon.
+pub async fn import(
+    client: &mut Client,
+    style: &OutputStyle,
+    file: PathBuf,
+    enqueue: bool,
+) -> Result<()> {
+    let content = if file.as_os_str() == "-" {
+        let mut content = String::new();
+        stdin().read_to_string(&mut content)?;
+        content
+    } else {
+        std::fs::read_to_string(&file)
+            .with_context(|| format!("Failed to read export file {file:?}"))?
+    };
+    let export: TaskExport = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse export file {file:?}"))?;
+
+    client
+        .send_request(ImportRequest { export, enqueue })
+        .await?;
+
+    let response = client.receive_response().await?;
+    let Response::Imported(response) = response else {
+        return handle_response(style, response);
+    };
+
+    println!("Imported {} tasks:", response.task_ids.len());
+    print_imported_tasks(&response);
+
+    Ok(())
+}
+
+/// Print the new id of each imported task and the dependencies that had to be dropped.
+pub fn print_imported_tasks(response: &ImportedResponse) {
+    for (old_id, new_id) in &response.task_ids {
+        println!("  {old_id} -> {new_id}");
+    }
+
+    for (old_id, dropped) in &response.dropped_dependencies {
+        let dropped = dropped
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        println!("Dropped dependencies of task {old_id} on tasks that weren't exported: {dropped}");
+    }
+}
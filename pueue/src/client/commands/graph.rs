@@ -0,0 +1,294 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use crossterm::style::Attribute;
+use pueue_lib::{
+    Client,
+    task::{DependencyCondition, Task, TaskResult, TaskStatus},
+};
+
+use super::get_state;
+use crate::{
+    client::{cli::GraphFormat, display_helper::status_text_and_color, style::OutputStyle},
+    internal_prelude::*,
+};
+
+/// Commands are cut off after this many characters, to keep the nodes readable.
+const MAX_COMMAND_LENGTH: usize = 40;
+
+/// Print the dependency graph of a selection of tasks.
+pub async fn graph(
+    client: &mut Client,
+    style: &OutputStyle,
+    task_ids: Vec<usize>,
+    group: Option<String>,
+    format: GraphFormat,
+) -> Result<()> {
+    let state = get_state(client).await?;
+    let tasks = select_tasks(state.tasks, &task_ids, group.as_deref());
+
+    if tasks.is_empty() {
+        println!("No tasks found.");
+        return Ok(());
+    }
+
+    let output = match format {
+        GraphFormat::Tree => render_tree(&tasks, style),
+        GraphFormat::Dot => render_dot(&tasks),
+        GraphFormat::Mermaid => render_mermaid(&tasks),
+    };
+    print!("{output}");
+
+    Ok(())
+}
+
+/// Determine the tasks that should be part of the graph.
+///
+/// - Without any selection, all tasks are shown.
+/// - If a group is specified, its tasks and all of their (transitive) dependencies are shown.
+/// - If task ids are specified, these tasks and all of their (transitive) dependencies and
+///   dependants are shown.
+fn select_tasks(
+    mut tasks: BTreeMap<usize, Task>,
+    task_ids: &[usize],
+    group: Option<&str>,
+) -> BTreeMap<usize, Task> {
+    let mut selected: BTreeSet<usize> = if !task_ids.is_empty() {
+        task_ids
+            .iter()
+            .copied()
+            .filter(|id| tasks.contains_key(id))
+            .collect()
+    } else if let Some(group) = group {
+        tasks
+            .values()
+            .filter(|task| task.group == group)
+            .map(|task| task.id)
+            .collect()
+    } else {
+        return tasks;
+    };
+
+    // Walk up the graph and add all dependencies.
+    let mut pending: Vec<usize> = selected.iter().copied().collect();
+    while let Some(id) = pending.pop() {
+        for dependency in &tasks[&id].dependencies {
+            if tasks.contains_key(dependency) && selected.insert(*dependency) {
+                pending.push(*dependency);
+            }
+        }
+    }
+
+    // Walk down the graph and add all dependants of explicitly selected tasks.
+    let mut pending = task_ids.to_vec();
+    while let Some(id) = pending.pop() {
+        for task in tasks.values() {
+            if task.dependencies.contains(&id) && selected.insert(task.id) {
+                pending.push(task.id);
+            }
+        }
+    }
+
+    tasks.retain(|id, _| selected.contains(id));
+    tasks
+}
+
+/// Get the ids of all shown tasks that depend on the given task.
+fn dependants(tasks: &BTreeMap<usize, Task>, id: usize) -> Vec<usize> {
+    tasks
+        .values()
+        .filter(|task| task.dependencies.contains(&id))
+        .map(|task| task.id)
+        .collect()
+}
+
+/// The text that's used to describe a task inside a node.
+/// The label is preferred, as it's usually much shorter than the command.
+fn node_name(task: &Task) -> String {
+    if let Some(label) = &task.label {
+        return label.clone();
+    }
+
+    let command = task.command.trim();
+    if command.chars().count() > MAX_COMMAND_LENGTH {
+        let shortened: String = command.chars().take(MAX_COMMAND_LENGTH - 3).collect();
+        format!("{shortened}...")
+    } else {
+        command.to_string()
+    }
+}
+
+/// Only non-default conditions are shown on edges, to keep the graph uncluttered.
+fn edge_label(task: &Task, dependency: usize) -> Option<String> {
+    let condition = task.dependency_condition(dependency);
+    (condition != DependencyCondition::default()).then(|| condition.to_string())
+}
+
+/// Render the graph as a tree, starting at the tasks without any shown dependencies.
+///
+/// Tasks with multiple dependencies show up below each of them.
+/// Their dependants are only expanded on the first occurrence.
+fn render_tree(tasks: &BTreeMap<usize, Task>, style: &OutputStyle) -> String {
+    let mut output = String::new();
+    let mut expanded = BTreeSet::new();
+
+    let roots = tasks.values().filter(|task| {
+        !task
+            .dependencies
+            .iter()
+            .any(|dependency| tasks.contains_key(dependency))
+    });
+    for root in roots {
+        render_tree_node(tasks, style, root, None, "", "", &mut expanded, &mut output);
+    }
+
+    output
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_tree_node(
+    tasks: &BTreeMap<usize, Task>,
+    style: &OutputStyle,
+    task: &Task,
+    parent: Option<usize>,
+    prefix: &str,
+    child_prefix: &str,
+    expanded: &mut BTreeSet<usize>,
+    output: &mut String,
+) {
+    let (status, color) = status_text_and_color(&task.status);
+    let condition = parent
+        .and_then(|parent| edge_label(task, parent))
+        .map(|condition| format!(" (on {condition})"))
+        .unwrap_or_default();
+    let already_expanded = expanded.contains(&task.id);
+    let see_above = if already_expanded { " (see above)" } else { "" };
+
+    output.push_str(&format!(
+        "{prefix}{} {} [{}]{condition}{see_above}\n",
+        style.style_text(task.id, None, Some(Attribute::Bold)),
+        node_name(task),
+        style.style_text(status, Some(color), None),
+    ));
+
+    if already_expanded {
+        return;
+    }
+    expanded.insert(task.id);
+
+    let children = dependants(tasks, task.id);
+    for (index, child) in children.iter().enumerate() {
+        let last = index == children.len() - 1;
+        let (branch, indent) = if last {
+            ("└─ ", "   ")
+        } else {
+            ("├─ ", "│  ")
+        };
+        render_tree_node(
+            tasks,
+            style,
+            &tasks[child],
+            Some(task.id),
+            &format!("{child_prefix}{branch}"),
+            &format!("{child_prefix}{indent}"),
+            expanded,
+            output,
+        );
+    }
+}
+
+/// The kind of status of a task, which determines the color of its node.
+fn node_class(status: &TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Running { .. } => "running",
+        TaskStatus::Paused { .. } | TaskStatus::Locked { .. } => "paused",
+        TaskStatus::Done {
+            result: TaskResult::Success,
+            ..
+        } => "success",
+        TaskStatus::Done { .. } => "failed",
+        _ => "pending",
+    }
+}
+
+/// The fill color of each node class.
+fn class_color(class: &str) -> &'static str {
+    match class {
+        "running" => "#8fd3fe",
+        "paused" => "#d9d9d9",
+        "success" => "#a6e3a1",
+        "failed" => "#f38ba8",
+        _ => "#f9e2af",
+    }
+}
+
+/// Render the graph in the Graphviz DOT format.
+fn render_dot(tasks: &BTreeMap<usize, Task>) -> String {
+    let escape = |text: String| text.replace('\\', "\\\\").replace('"', "\\\"");
+
+    let mut output = String::from("digraph pueue {\n");
+    output.push_str("    rankdir=LR;\n");
+    output.push_str("    node [shape=box, style=\"rounded,filled\"];\n");
+
+    for task in tasks.values() {
+        let (status, _) = status_text_and_color(&task.status);
+        output.push_str(&format!(
+            "    {} [label=\"{}: {}\\n{}\", fillcolor=\"{}\"];\n",
+            task.id,
+            task.id,
+            escape(node_name(task)),
+            escape(status),
+            class_color(node_class(&task.status)),
+        ));
+    }
+
+    for task in tasks.values() {
+        for dependency in task.dependencies.iter().filter(|id| tasks.contains_key(id)) {
+            let label = edge_label(task, *dependency)
+                .map(|label| format!(" [label=\"{label}\"]"))
+                .unwrap_or_default();
+            output.push_str(&format!("    {dependency} -> {}{label};\n", task.id));
+        }
+    }
+
+    output.push_str("}\n");
+    output
+}
+
+/// Render the graph as a Mermaid flowchart.
+fn render_mermaid(tasks: &BTreeMap<usize, Task>) -> String {
+    let escape = |text: String| text.replace('"', "#quot;");
+
+    let mut output = String::from("flowchart LR\n");
+    for task in tasks.values() {
+        let (status, _) = status_text_and_color(&task.status);
+        output.push_str(&format!(
+            "    task{}[\"{}: {}<br/>{}\"]:::{}\n",
+            task.id,
+            task.id,
+            escape(node_name(task)),
+            escape(status),
+            node_class(&task.status),
+        ));
+    }
+
+    for task in tasks.values() {
+        for dependency in task.dependencies.iter().filter(|id| tasks.contains_key(id)) {
+            let label = edge_label(task, *dependency)
+                .map(|label| format!("|{label}|"))
+                .unwrap_or_default();
+            output.push_str(&format!(
+                "    task{dependency} -->{label} task{}\n",
+                task.id
+            ));
+        }
+    }
+
+    for class in ["pending", "running", "paused", "success", "failed"] {
+        output.push_str(&format!(
+            "    classDef {class} fill:{}\n",
+            class_color(class)
+        ));
+    }
+
+    output
+}
@@ -1,4 +1,4 @@
-use pueue_lib::{Client, message::*};
+use pueue_lib::{Client, Group, message::*};
 
 use super::handle_response;
 use crate::{
@@ -19,6 +19,23 @@ pub async fn group(
             parallel_tasks: parallel.to_owned(),
         },
         Some(GroupCommand::Remove { name }) => GroupRequest::Remove(name.to_owned()),
+        Some(GroupCommand::Edit {
+            name,
+            env,
+            unset_env,
+            working_directory,
+            clear_working_directory,
+            shell_command,
+            clear_shell_command,
+        }) => GroupRequest::Edit(GroupEditRequest {
+            name,
+            set_envs: env.into_iter().collect(),
+            unset_envs: unset_env,
+            working_directory,
+            clear_working_directory,
+            shell_command,
+            clear_shell_command,
+        }),
         None => GroupRequest::List,
     };
 
@@ -48,6 +65,7 @@ pub fn format_groups(message: GroupResponse, style: &OutputStyle, json: bool) ->
         let styled = get_group_headline(name, group, style);
 
         text.push_str(&styled);
+        text.push_str(&format_group_defaults(group));
         if group_iter.peek().is_some() {
             text.push('\n');
         }
@@ -55,3 +73,26 @@ pub fn format_groups(message: GroupResponse, style: &OutputStyle, json: bool) ->
 
     text
 }
+
+/// List the task defaults of a group, one per line.
+/// Groups without any defaults result in an empty string.
+fn format_group_defaults(group: &Group) -> String {
+    let mut text = String::new();
+    if let Some(working_directory) = &group.working_directory {
+        text.push_str(&format!(
+            "\n  working directory: {}",
+            working_directory.display()
+        ));
+    }
+    if let Some(shell_command) = &group.shell_command {
+        text.push_str(&format!("\n  shell command: {}", shell_command.join(" ")));
+    }
+
+    let mut envs: Vec<_> = group.envs.iter().collect();
+    envs.sort();
+    for (key, value) in envs {
+        text.push_str(&format!("\n  env: {key}={value}"));
+    }
+
+    text
+}
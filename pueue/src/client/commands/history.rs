@@ -0,0 +1,27 @@
+use pueue_lib::{Client, Request, Response, Settings};
+
+use super::{handle_response, state::format_task_list};
+use crate::{client::style::OutputStyle, internal_prelude::*};
+
+/// Show the tasks of the archive, filtered by an optional query.
+pub async fn history(
+    client: &mut Client,
+    settings: &Settings,
+    style: &OutputStyle,
+    query: Vec<String>,
+    json: bool,
+) -> Result<()> {
+    client.send_request(Request::History).await?;
+
+    let response = client.receive_response().await?;
+    let Response::History(response) = response else {
+        return handle_response(style, response);
+    };
+
+    println!(
+        "{}",
+        format_task_list(response.tasks, style, settings, json, query)?
+    );
+
+    Ok(())
+}
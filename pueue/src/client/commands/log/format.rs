@@ -0,0 +1,37 @@
+use std::collections::BTreeMap;
+
+use pueue_lib::{message::TaskLogResponse, settings::Settings};
+
+use super::json::{get_local_log, get_remote_log};
+use crate::{
+    client::output_format::{OutputFormat, TaskRecord, format_records},
+    internal_prelude::*,
+};
+
+/// Print the tasks and their output in the given format.
+///
+/// Just like for json, the output is either read from the local log files or taken from the
+/// compressed logs of the daemon's response.
+pub fn print_log_format(
+    task_log_messages: BTreeMap<usize, TaskLogResponse>,
+    settings: &Settings,
+    lines: Option<usize>,
+    format: &OutputFormat,
+) -> Result<()> {
+    let mut records = Vec::new();
+    for (id, message) in task_log_messages {
+        let output = if settings.client.read_local_logs {
+            get_local_log(settings, id, lines)
+        } else {
+            get_remote_log(message.output)
+        };
+
+        let mut record = TaskRecord::from(&message.task);
+        record.output = Some(output);
+        records.push(record);
+    }
+
+    print!("{}", format_records(format, &records)?);
+
+    Ok(())
+}
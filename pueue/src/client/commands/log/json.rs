@@ -56,7 +56,7 @@ pub fn print_log_json(
 }
 
 /// Read logs directly from local files for a specific task.
-fn get_local_log(settings: &Settings, id: usize, lines: Option<usize>) -> String {
+pub(super) fn get_local_log(settings: &Settings, id: usize, lines: Option<usize>) -> String {
     let mut file = match get_log_file_handle(id, &settings.shared.pueue_directory()) {
         Ok(file) => file,
         Err(err) => {
@@ -80,7 +80,7 @@ fn get_local_log(settings: &Settings, id: usize, lines: Option<usize>) -> String
 
 /// Read logs from from compressed remote logs.
 /// If logs don't exist, an empty string will be returned.
-fn get_remote_log(output_bytes: Option<Vec<u8>>) -> String {
+pub(super) fn get_remote_log(output_bytes: Option<Vec<u8>>) -> String {
     let Some(bytes) = output_bytes else {
         return String::new();
     };
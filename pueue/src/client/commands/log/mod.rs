@@ -8,12 +8,14 @@ use pueue_lib::{
 };
 
 use super::{OutputStyle, handle_response, selection_from_params};
-use crate::internal_prelude::*;
+use crate::{client::output_format::OutputFormat, internal_prelude::*};
 
+mod format;
 mod json;
 mod local;
 mod remote;
 
+use format::print_log_format;
 use json::*;
 use local::*;
 use remote::*;
@@ -31,6 +33,7 @@ pub async fn print_logs(
     json: bool,
     lines: Option<usize>,
     full: bool,
+    format: Option<OutputFormat>,
 ) -> Result<()> {
     let lines = determine_log_line_amount(full, &lines);
     let selection = selection_from_params(all, group.clone(), task_ids.clone());
@@ -56,6 +59,10 @@ pub async fn print_logs(
         return Ok(());
     }
 
+    if let Some(format) = format {
+        return print_log_format(task_logs, &settings, lines, &format);
+    }
+
     if task_logs.is_empty() {
         match selection {
             TaskSelection::TaskIds(_) => {
@@ -17,47 +17,66 @@ use pueue_lib::{
 use crate::internal_prelude::*;
 
 mod add;
+mod array;
+mod callbacks;
 mod clean;
 mod edit;
 mod enqueue;
 mod env;
+mod export;
 mod follow;
+mod graph;
 mod group;
+mod history;
 mod kill;
 mod log;
 mod parallel;
 mod pause;
+mod reload;
 mod remove;
 mod reset;
 mod restart;
 mod send;
 mod shutdown;
+mod snapshot;
 mod start;
 mod stash;
 mod state;
+mod submit;
 mod switch;
+mod tui;
 mod wait;
 
 use add::add_task;
+use array::array;
+use callbacks::callbacks;
 use clean::clean;
 use edit::edit;
 use enqueue::enqueue;
 use env::env;
+use export::{export, import};
 use follow::follow;
+use graph::graph;
 use group::group;
+use history::history;
 use kill::kill;
 use log::print_logs;
 use parallel::parallel;
 use pause::pause;
+use reload::reload;
 use remove::remove;
 use reset::reset;
 use restart::restart;
 use send::send;
 use shutdown::shutdown;
+use snapshot::{snapshot, undo};
 use start::start;
 use stash::stash;
-use state::state;
+pub use state::all_contexts_state;
+use state::{print_views, state};
+use submit::submit;
 use switch::switch;
+use tui::tui;
 pub use wait::WaitTargetStatus;
 use wait::wait;
 
@@ -161,6 +180,7 @@ pub async fn handle_command(
     match subcommand {
         SubCommand::Add {
             command,
+            template,
             working_directory,
             escape,
             start_immediately,
@@ -170,6 +190,7 @@ pub async fn handle_command(
             dependencies,
             priority,
             label,
+            timeout,
             print_task_id,
             follow,
         } => {
@@ -178,6 +199,7 @@ pub async fn handle_command(
                 settings,
                 style,
                 command,
+                template,
                 working_directory,
                 escape,
                 start_immediately,
@@ -187,11 +209,14 @@ pub async fn handle_command(
                 dependencies,
                 priority,
                 label,
+                timeout,
                 print_task_id,
                 follow,
             )
             .await
         }
+        SubCommand::Array { cmd } => array(client, settings, style, cmd).await,
+        SubCommand::Submit { file } => submit(client, style, file).await,
         SubCommand::Clean {
             successful_only,
             group,
@@ -207,7 +232,23 @@ pub async fn handle_command(
         SubCommand::Follow { task_id, lines } => {
             follow(client, settings, style, task_id, lines).await
         }
+        SubCommand::Graph {
+            task_ids,
+            group,
+            format,
+        } => graph(client, style, task_ids, group, format).await,
+        SubCommand::History { query, json } => history(client, &settings, style, query, json).await,
+        SubCommand::Export {
+            task_ids,
+            group,
+            all,
+            logs,
+            output,
+        } => export(client, style, task_ids, group, all, logs, output).await,
+        SubCommand::Import { file, enqueue } => import(client, style, file, enqueue).await,
         SubCommand::Group { cmd, json } => group(client, style, cmd, json).await,
+        SubCommand::Snapshot { cmd, json } => snapshot(client, &settings, style, cmd, json).await,
+        SubCommand::Undo => undo(client, style).await,
         SubCommand::Kill {
             task_ids,
             group,
@@ -221,9 +262,10 @@ pub async fn handle_command(
             json,
             lines,
             full,
+            format,
         } => {
             print_logs(
-                client, settings, style, task_ids, group, all, json, lines, full,
+                client, settings, style, task_ids, group, all, json, lines, full, format,
             )
             .await
         }
@@ -237,6 +279,10 @@ pub async fn handle_command(
             all,
             wait,
         } => pause(client, style, task_ids, group, all, wait).await,
+        SubCommand::Reload => reload(client, style).await,
+        SubCommand::Callbacks { json, output } => {
+            callbacks(client, &settings, style, json, output).await
+        }
         SubCommand::Remove { task_ids } => remove(client, settings, style, task_ids).await,
         SubCommand::Reset { force, groups } => reset(client, style, force, groups).await,
         SubCommand::Restart {
@@ -276,13 +322,23 @@ pub async fn handle_command(
             group,
             all,
         } => start(client, style, task_ids, group, all).await,
-        SubCommand::Status { query, json, group } => {
-            state(client, settings, style, query, json, group).await
+        SubCommand::Status { views: true, .. } => {
+            print_views(&settings, style);
+            Ok(())
         }
+        SubCommand::Status {
+            query,
+            json,
+            group,
+            watch,
+            format,
+            ..
+        } => state(client, settings, style, query, json, group, watch, format).await,
         SubCommand::Switch {
             task_id_1,
             task_id_2,
         } => switch(client, style, task_id_1, task_id_2).await,
+        SubCommand::Tui => tui(client, settings).await,
         SubCommand::Wait {
             task_ids,
             group,
@@ -0,0 +1,52 @@
+use crossterm::style::{Attribute, Color};
+use pueue_lib::{Client, message::*};
+
+use super::handle_response;
+use crate::{client::style::OutputStyle, internal_prelude::*};
+
+/// Make the daemon reload its configuration and show which settings changed.
+pub async fn reload(client: &mut Client, style: &OutputStyle) -> Result<()> {
+    client.send_request(Request::ReloadConfig).await?;
+
+    let response = client.receive_response().await?;
+
+    let Response::ConfigReloaded(report) = response else {
+        return handle_response(style, response);
+    };
+
+    if report.applied.is_empty() && report.requires_restart.is_empty() {
+        println!("Configuration reloaded, nothing changed.");
+        return Ok(());
+    }
+
+    println!("Configuration reloaded.");
+    if !report.applied.is_empty() {
+        println!(
+            "\n{}",
+            style.style_text(
+                "Applied changes:",
+                Some(Color::Green),
+                Some(Attribute::Bold)
+            )
+        );
+        for key in report.applied {
+            println!("  {key}");
+        }
+    }
+
+    if !report.requires_restart.is_empty() {
+        println!(
+            "\n{}",
+            style.style_text(
+                "Changes that require a daemon restart:",
+                Some(Color::Yellow),
+                Some(Attribute::Bold)
+            )
+        );
+        for key in report.requires_restart {
+            println!("  {key}");
+        }
+    }
+
+    Ok(())
+}
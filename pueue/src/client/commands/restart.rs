@@ -146,14 +146,17 @@ pub async fn restart(
         let add_task_message = AddRequest {
             command: task.original_command,
             path: task.path,
+            path_is_fallback: false,
             envs: task.envs.clone(),
             start_immediately,
             stashed,
             group: task.group.clone(),
             enqueue_at: None,
             dependencies: Vec::new(),
+            dependency_conditions: BTreeMap::new(),
             priority: Some(task.priority),
             label: task.label,
+            timeout: task.timeout,
         };
 
         // Send the cloned task to the daemon and abort on any failure messages.
@@ -0,0 +1,111 @@
+use comfy_table::{Cell, ContentArrangement, Table, presets::UTF8_HORIZONTAL_ONLY};
+use pueue_lib::{Client, Response, Settings, message::*};
+
+use super::{export::print_imported_tasks, handle_response};
+use crate::{
+    client::{cli::SnapshotCommand, style::OutputStyle},
+    internal_prelude::*,
+};
+
+/// List all snapshots or restore one of them.
+pub async fn snapshot(
+    client: &mut Client,
+    settings: &Settings,
+    style: &OutputStyle,
+    cmd: Option<SnapshotCommand>,
+    json: bool,
+) -> Result<()> {
+    let id = match cmd {
+        Some(SnapshotCommand::Restore { id }) => id,
+        Some(SnapshotCommand::List) | None => {
+            return list_snapshots(client, settings, style, json).await;
+        }
+    };
+
+    restore(client, style, Some(id)).await
+}
+
+/// Restore the most recent snapshot.
+pub async fn undo(client: &mut Client, style: &OutputStyle) -> Result<()> {
+    restore(client, style, None).await
+}
+
+async fn restore(client: &mut Client, style: &OutputStyle, id: Option<usize>) -> Result<()> {
+    client.send_request(SnapshotRequest::Restore(id)).await?;
+
+    let response = client.receive_response().await?;
+    let Response::Imported(response) = response else {
+        return handle_response(style, response);
+    };
+
+    if response.task_ids.is_empty() {
+        println!("Snapshot restored.");
+        return Ok(());
+    }
+
+    println!(
+        "Restored {} tasks as stashed tasks:",
+        response.task_ids.len()
+    );
+    print_imported_tasks(&response);
+
+    Ok(())
+}
+
+async fn list_snapshots(
+    client: &mut Client,
+    settings: &Settings,
+    style: &OutputStyle,
+    json: bool,
+) -> Result<()> {
+    client.send_request(SnapshotRequest::List).await?;
+
+    let response = client.receive_response().await?;
+    let Response::Snapshots(response) = response else {
+        return handle_response(style, response);
+    };
+
+    if json {
+        println!("{}", serde_json::to_string(&response.snapshots).unwrap());
+        return Ok(());
+    }
+
+    if response.snapshots.is_empty() {
+        println!("No snapshots have been taken yet.");
+        return Ok(());
+    }
+
+    let header = ["Id", "Operation", "Taken at", "Tasks", "Groups"]
+        .into_iter()
+        .map(|name| style.styled_cell(name, None, Some(comfy_table::Attribute::Bold)));
+
+    let mut table = Table::new();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_header(header);
+
+    for snapshot in response.snapshots {
+        let task_ids: Vec<String> = snapshot.task_ids.iter().map(|id| id.to_string()).collect();
+        table.add_row(vec![
+            Cell::new(snapshot.id),
+            Cell::new(&snapshot.operation),
+            Cell::new(
+                snapshot
+                    .created_at
+                    .format(&settings.client.status_datetime_format),
+            ),
+            Cell::new(task_ids.join(", ")),
+            Cell::new(snapshot.groups.join(", ")),
+        ]);
+    }
+
+    // Explicitly force styling, in case we aren't on a tty, but `--color=always` is set.
+    if style.enabled {
+        table.enforce_styling();
+    }
+
+    println!("{table}");
+
+    Ok(())
+}
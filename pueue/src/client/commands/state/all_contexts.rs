@@ -0,0 +1,122 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use crossterm::style::Color;
+use pueue_lib::{settings::Settings, state::State};
+use tokio::{task::JoinSet, time::timeout};
+
+use super::{TableBuilder, apply_query, expand_views};
+use crate::{
+    client::{
+        commands::get_state,
+        contexts::{Contexts, connect},
+        style::OutputStyle,
+    },
+    internal_prelude::*,
+};
+
+/// How long to wait for the state of a single daemon, before it's reported as unreachable.
+const CONTEXT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Print the tasks of the daemons of all contexts in a single table, which has an additional
+/// column for the name of each task's context.
+///
+/// All daemons are contacted at the same time. Daemons that cannot be reached in time are
+/// reported, but don't prevent the tasks of the other daemons from being shown.
+/// The query is applied to the tasks of each daemon separately.
+/// In json mode, the states are printed as a single object by context name.
+pub async fn all_contexts_state(
+    settings: &Settings,
+    style: &OutputStyle,
+    query: Vec<String>,
+    json: bool,
+    group: Option<String>,
+) -> Result<()> {
+    let contexts = Contexts::read(settings)?;
+    if contexts.contexts.is_empty() {
+        bail!("No contexts defined. Add one via `pueue context add`.");
+    }
+
+    // Make sure the query is valid, before connecting to any daemon.
+    let query = expand_views(&query, &settings.client.views)?;
+    let query_result = apply_query(&query.join(" "), &group)?;
+
+    let mut requests = JoinSet::new();
+    for (name, context) in contexts.contexts {
+        let mut context_settings = settings.clone();
+        context.apply(&mut context_settings);
+        requests.spawn(async move {
+            let state = timeout(CONTEXT_TIMEOUT, context_state(&context_settings, !json))
+                .await
+                .unwrap_or_else(|_| {
+                    bail!(
+                        "The daemon didn't respond within {} seconds",
+                        CONTEXT_TIMEOUT.as_secs()
+                    )
+                });
+            (name, state)
+        });
+    }
+
+    let mut states = BTreeMap::new();
+    while let Some(result) = requests.join_next().await {
+        let (name, state) = result.context("Failed to get the status of a context")?;
+        states.insert(name, state);
+    }
+
+    let mut errors = Vec::new();
+    let mut json_states = BTreeMap::new();
+    let mut tasks = Vec::new();
+    for (name, state) in states {
+        let mut state = match state {
+            Ok(state) => state,
+            Err(err) => {
+                errors.push(format!(
+                    "Failed to get the status of context '{name}': {err:#}"
+                ));
+                continue;
+            }
+        };
+
+        let context_tasks = state.tasks.values().cloned().collect();
+        let context_tasks = query_result.apply_filters(context_tasks);
+        let context_tasks = query_result.order_tasks(context_tasks);
+        let context_tasks = query_result.limit_tasks(context_tasks);
+
+        if json {
+            state.tasks = context_tasks
+                .into_iter()
+                .map(|task| (task.id, task))
+                .collect();
+            json_states.insert(name, state);
+        } else {
+            tasks.extend(context_tasks.into_iter().map(|task| (name.clone(), task)));
+        }
+    }
+
+    if json {
+        for error in errors {
+            eprintln!("{error}");
+        }
+        println!("{}", serde_json::to_string(&json_states)?);
+        return Ok(());
+    }
+
+    if tasks.is_empty() {
+        println!("No tasks found.");
+    } else {
+        let mut table_builder = TableBuilder::new(settings, style);
+        table_builder.set_visibility_by_rules(&query_result.selected_columns);
+        println!("{}", table_builder.build_with_contexts(&tasks));
+    }
+    for error in errors {
+        println!("{}", style.style_text(error, Some(Color::Red), None));
+    }
+
+    Ok(())
+}
+
+/// Connect to the daemon of a single context and get its state.
+async fn context_state(settings: &Settings, show_version_warning: bool) -> Result<State> {
+    let mut client = connect(settings, show_version_warning).await?;
+    get_state(&mut client).await
+}
@@ -1,4 +1,4 @@
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 
 use chrono::{DateTime, Local, LocalResult};
 use pueue_lib::{
@@ -9,17 +9,32 @@ use pueue_lib::{
 };
 
 use crate::{
-    client::{commands::get_state, display_helper::get_group_headline, style::OutputStyle},
+    client::{
+        commands::get_state,
+        display_helper::get_group_headline,
+        output_format::{OutputFormat, TaskRecord, format_records},
+        style::OutputStyle,
+    },
     internal_prelude::*,
 };
 
+mod all_contexts;
 mod query;
 mod table_builder;
+mod views;
+mod watch;
 
+pub use all_contexts::all_contexts_state;
 use query::apply_query;
-use table_builder::TableBuilder;
+pub(super) use table_builder::TableBuilder;
+use views::expand_views;
+pub use views::print_views;
+use watch::watch_state;
 
 /// Simply request and print the state.
+/// If `watch` is set, the state is redrawn in the given interval in seconds instead.
+/// If `format` is set, the tasks are printed in that format instead of a table.
+#[allow(clippy::too_many_arguments)]
 pub async fn state(
     client: &mut Client,
     settings: Settings,
@@ -27,11 +42,38 @@ pub async fn state(
     query: Vec<String>,
     json: bool,
     group: Option<String>,
+    watch: Option<f64>,
+    format: Option<OutputFormat>,
 ) -> Result<()> {
+    if let Some(interval) = watch {
+        return watch_state(client, &settings, style, query, group, interval).await;
+    }
+
     let state = get_state(client).await?;
     let tasks = state.tasks.values().cloned().collect();
 
-    let output = print_state(state, tasks, style, &settings, json, group, Some(query))?;
+    if let Some(format) = format {
+        let query = expand_views(&query, &settings.client.views)?;
+        let query_result = apply_query(&query.join(" "), &group)?;
+        let tasks = query_result.apply_filters(tasks);
+        let tasks = query_result.order_tasks(tasks);
+        let tasks = query_result.limit_tasks(tasks);
+
+        let records: Vec<TaskRecord> = tasks.iter().map(TaskRecord::from).collect();
+        print!("{}", format_records(&format, &records)?);
+        return Ok(());
+    }
+
+    let output = print_state(
+        state,
+        tasks,
+        style,
+        &settings,
+        json,
+        group,
+        Some(query),
+        &BTreeSet::new(),
+    )?;
     println!("{output}");
 
     Ok(())
@@ -42,6 +84,9 @@ pub async fn state(
 ///
 /// We pass the tasks as a separate parameter and as a list.
 /// This allows us to print the tasks in the order passed to the `format-status` subcommand.
+///
+/// The rows of all `highlighted_tasks` are highlighted, e.g. tasks that changed in watch mode.
+#[allow(clippy::too_many_arguments)]
 fn print_state(
     mut state: State,
     mut tasks: Vec<Task>,
@@ -50,12 +95,17 @@ fn print_state(
     json: bool,
     group: Option<String>,
     query: Option<Vec<String>>,
+    highlighted_tasks: &BTreeSet<usize>,
 ) -> Result<String> {
     let mut output = String::new();
 
     let mut table_builder = TableBuilder::new(settings, style);
+    for task_id in highlighted_tasks {
+        table_builder.highlight_task(*task_id);
+    }
 
     if let Some(query) = &query {
+        let query = expand_views(query, &settings.client.views)?;
         let query_result = apply_query(&query.join(" "), &group)?;
         table_builder.set_visibility_by_rules(&query_result.selected_columns);
         tasks = query_result.apply_filters(tasks);
@@ -82,6 +132,36 @@ fn print_state(
     Ok(output)
 }
 
+/// Format a list of tasks as a single table, e.g. for `pueue history`.
+///
+/// The query is applied in the same way as for `pueue status`.
+pub(super) fn format_task_list(
+    mut tasks: Vec<Task>,
+    style: &OutputStyle,
+    settings: &Settings,
+    json: bool,
+    query: Vec<String>,
+) -> Result<String> {
+    let mut table_builder = TableBuilder::new(settings, style);
+
+    let query = expand_views(&query, &settings.client.views)?;
+    let query_result = apply_query(&query.join(" "), &None)?;
+    table_builder.set_visibility_by_rules(&query_result.selected_columns);
+    tasks = query_result.apply_filters(tasks);
+    tasks = query_result.order_tasks(tasks);
+    tasks = query_result.limit_tasks(tasks);
+
+    if json {
+        return Ok(serde_json::to_string(&tasks)?);
+    }
+
+    if tasks.is_empty() {
+        return Ok("No tasks found.".to_string());
+    }
+
+    Ok(table_builder.build(&tasks).to_string())
+}
+
 /// The user requested only a single group to be displayed.
 ///
 /// Print this group or show an error if this group doesn't exist.
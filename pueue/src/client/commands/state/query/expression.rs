@@ -0,0 +1,106 @@
+use pest::iterators::Pair;
+use pueue_lib::task::Task;
+
+use super::{FilterFunction, Rule, filters};
+use crate::internal_prelude::*;
+
+/// A tree of filters that are combined via boolean logic.
+///
+/// E.g. `status=failed or (status=running and start<10:00)` results in
+/// `Or([Filter, And([Filter, Filter])])`.
+pub enum FilterExpression {
+    Filter(Box<FilterFunction>),
+    Not(Box<FilterExpression>),
+    And(Vec<FilterExpression>),
+    Or(Vec<FilterExpression>),
+}
+
+impl FilterExpression {
+    /// Check whether a task matches this expression.
+    pub fn matches(&self, task: &Task) -> bool {
+        match self {
+            FilterExpression::Filter(filter) => filter(task),
+            FilterExpression::Not(expression) => !expression.matches(task),
+            FilterExpression::And(expressions) => expressions
+                .iter()
+                .all(|expression| expression.matches(task)),
+            FilterExpression::Or(expressions) => expressions
+                .iter()
+                .any(|expression| expression.matches(task)),
+        }
+    }
+}
+
+/// Build the expression tree from a parsed `expression`.
+///
+/// The grammar already takes care of the precedence of the operators:
+/// - An `expression` is a list of `term`s that are combined via `or`.
+/// - A `term` is a list of `factor`s that are combined via `and`.
+/// - A `factor` is a single filter or a parenthesized `expression`, which may be negated.
+pub fn expression(section: Pair<'_, Rule>) -> Result<FilterExpression> {
+    let expression = match section.as_rule() {
+        Rule::expression => {
+            let terms = operands(section, Rule::or)?;
+            combine(terms, FilterExpression::Or)
+        }
+        Rule::term => {
+            let factors = operands(section, Rule::and)?;
+            combine(factors, FilterExpression::And)
+        }
+        Rule::factor => {
+            let mut negations = 0;
+            let mut inner = None;
+            for pair in section.into_inner() {
+                match pair.as_rule() {
+                    Rule::not => negations += 1,
+                    _ => inner = Some(expression(pair)?),
+                }
+            }
+            let Some(inner) = inner else {
+                bail!("Expected a filter or an expression in parentheses");
+            };
+
+            // Double negations cancel each other out.
+            if negations % 2 == 1 {
+                FilterExpression::Not(Box::new(inner))
+            } else {
+                inner
+            }
+        }
+        Rule::datetime_filter => FilterExpression::Filter(filters::datetime(section)?),
+        Rule::label_filter => FilterExpression::Filter(filters::label(section)?),
+        Rule::command_filter => FilterExpression::Filter(filters::command(section)?),
+        Rule::status_filter => FilterExpression::Filter(filters::status(section)?),
+        Rule::path_filter => FilterExpression::Filter(filters::path(section)?),
+        Rule::group_filter => FilterExpression::Filter(filters::group(section)?),
+        Rule::env_filter => FilterExpression::Filter(filters::env(section)?),
+        Rule::numeric_filter => FilterExpression::Filter(filters::numeric(section)?),
+        Rule::dependencies_filter => FilterExpression::Filter(filters::dependencies(section)?),
+        Rule::duration_filter => FilterExpression::Filter(filters::duration(section)?),
+        rule => bail!("Unexpected {rule:?} in filter expression"),
+    };
+
+    Ok(expression)
+}
+
+/// Build the expressions of all operands of an `expression` or `term`, skipping their operator.
+fn operands(section: Pair<'_, Rule>, operator: Rule) -> Result<Vec<FilterExpression>> {
+    section
+        .into_inner()
+        .filter(|pair| pair.as_rule() != operator)
+        .map(expression)
+        .collect()
+}
+
+/// Combine multiple operands via the given operator.
+/// A single operand doesn't need to be wrapped.
+fn combine(
+    mut operands: Vec<FilterExpression>,
+    operator: fn(Vec<FilterExpression>) -> FilterExpression,
+) -> FilterExpression {
+    if operands.len() == 1 {
+        return operands.remove(0);
+    }
+
+    operator(operands)
+}
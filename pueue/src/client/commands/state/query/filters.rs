@@ -1,9 +1,12 @@
 #![allow(bindings_with_variant_name)]
+use std::borrow::Cow;
+
 use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta};
 use pest::iterators::Pair;
 use pueue_lib::task::{Task, TaskResult, TaskStatus};
+use regex::Regex;
 
-use super::{QueryResult, Rule};
+use super::{FilterFunction, Rule};
 use crate::internal_prelude::*;
 
 enum DateOrDateTime {
@@ -65,7 +68,7 @@ enum DateOrDateTime {
 ///         },
 ///     ],
 /// }
-pub fn datetime(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result<()> {
+pub fn datetime(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
     let mut filter = section.into_inner();
     // Get the column this filter should be applied to.
     // Either of [Rule::column_enqueue_at | Rule::column_start | Rule::column_end]
@@ -80,21 +83,25 @@ pub fn datetime(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Resu
     // This can be either a Date or a DateTime.
     let operand = filter.next().unwrap();
     let operand_rule = operand.as_rule();
+    // The span may include whitespace that's followed by another part of the query.
+    let operand_str = operand.as_str().trim();
     let operand = match operand_rule {
         Rule::time => {
-            let time = NaiveTime::parse_from_str(operand.as_str(), "%X")
-                .context("Expected hh:mm:ss time format")?;
+            let time = NaiveTime::parse_from_str(operand_str, "%X")
+                .or_else(|_| NaiveTime::parse_from_str(operand_str, "%R"))
+                .context("Expected hh:mm:ss or hh:mm time format")?;
             let today = Local::now().date_naive();
             let datetime = today.and_time(time).and_local_timezone(Local).unwrap();
             DateOrDateTime::DateTime(datetime)
         }
         Rule::datetime => {
-            let datetime = NaiveDateTime::parse_from_str(operand.as_str(), "%F %X")
+            let datetime = NaiveDateTime::parse_from_str(operand_str, "%F %X")
+                .or_else(|_| NaiveDateTime::parse_from_str(operand_str, "%F %R"))
                 .context("Expected YYYY-MM-SS hh:mm:ss date time format")?;
             DateOrDateTime::DateTime(datetime.and_local_timezone(Local).unwrap())
         }
         Rule::date => {
-            let date = NaiveDate::parse_from_str(operand.as_str(), "%F")
+            let date = NaiveDate::parse_from_str(operand_str, "%F")
                 .context("Expected YYYY-MM-SS date format")?;
             DateOrDateTime::Date(date)
         }
@@ -177,15 +184,15 @@ pub fn datetime(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Resu
             }
         }
     });
-    query_result.filters.push(filter_function);
-
-    Ok(())
+    Ok(filter_function)
 }
 
 /// Parse a filter for the label field.
 ///
 /// This filter syntax looks like this:
-/// `label [=|!=] string`
+/// `label [=|!=|%=|~] string` or `label [=|!=|%=|~] "string"`
+///
+/// `~` matches the label against a regular expression.
 ///
 /// The data structure looks something like this:
 ///  Pair {
@@ -221,69 +228,155 @@ pub fn datetime(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Resu
 ///                 start: 6,
 ///                 end: 10,
 ///             },
-///             inner: [],
+///             inner: [
+///                 Pair {
+///                     rule: unquoted_text,
+///                     span: Span {
+///                         str: "test",
+///                         start: 6,
+///                         end: 10,
+///                     },
+///                     inner: [],
+///                 },
+///             ],
 ///         },
 ///     ],
 /// }
-pub fn label(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result<()> {
+pub fn label(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
+    text(section, |task| task.label.as_deref().map(Cow::Borrowed))
+}
+
+/// Parse a filter for the command field.
+///
+/// This filter syntax is exactly the same as the [label] filter.
+/// Only the keyword changed from `label` to `command`.
+pub fn command(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
+    text(section, |task| Some(Cow::Borrowed(&task.command)))
+}
+
+/// Parse a filter for the path field.
+///
+/// This filter syntax is exactly the same as the [label] filter.
+pub fn path(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
+    text(section, |task| Some(task.path.to_string_lossy()))
+}
+
+/// Parse a filter for the group field.
+///
+/// This filter syntax is exactly the same as the [label] filter.
+pub fn group(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
+    text(section, |task| Some(Cow::Borrowed(&task.group)))
+}
+
+/// Parse a filter for a single environment variable of a task.
+///
+/// This filter syntax looks like this:
+/// `env.[name] [=|!=|%=|~] string`
+///
+/// Tasks that don't have the variable only match the `!=` operator.
+pub fn env(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
     let mut filter = section.into_inner();
-    // The first word should be the `label` keyword.
-    let _label = filter.next().unwrap();
+    // The variable consists of the `env` keyword and the name of the variable.
+    let mut variable = filter.next().unwrap().into_inner();
+    let _env = variable.next().unwrap();
+    let name = variable.next().unwrap().as_str().to_string();
 
-    // Get the operator that should be applied in this filter.
-    // Can be either of [Rule::eq | Rule::neq].
     let operator = filter.next().unwrap().as_rule();
+    let matcher = TextMatcher::new(operator, text_operand(filter.next().unwrap()))?;
+
+    let filter_function = Box::new(move |task: &Task| -> bool {
+        match task.envs.get(&name) {
+            Some(value) => matcher.matches(value),
+            None => matcher.matches_missing(),
+        }
+    });
+    Ok(filter_function)
+}
 
-    // Get the name of the label we should filter for.
-    let operand = filter.next().unwrap().as_str().to_string();
+/// Parse a filter for a numeric field, i.e. `id`, `priority` or `exit_code`.
+///
+/// This filter syntax looks like this:
+/// `[id|priority|exit_code] [=|!=|<|>] number`
+///
+/// Only finished tasks with an actual exit code match `exit_code` filters.
+/// Tasks that e.g. have been killed or couldn't be spawned don't have an exit code.
+pub fn numeric(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
+    let mut filter = section.into_inner();
+    // Either of [Rule::column_id | Rule::column_priority | Rule::column_exit_code]
+    let column = filter.next().unwrap().as_rule();
+    let operator = filter.next().unwrap().as_rule();
+    let operand: i64 = filter
+        .next()
+        .unwrap()
+        .as_str()
+        .parse()
+        .context("Expected a number")?;
 
-    // Build the label filter function.
     let filter_function = Box::new(move |task: &Task| -> bool {
-        let Some(label) = &task.label else {
-            return operator == Rule::neq;
+        let field = match column {
+            Rule::column_id => task.id as i64,
+            Rule::column_priority => i64::from(task.priority),
+            Rule::column_exit_code => match exit_code(task) {
+                Some(code) => i64::from(code),
+                None => return false,
+            },
+            _ => return true,
         };
 
-        match operator {
-            Rule::eq => label == &operand,
-            Rule::neq => label != &operand,
-            Rule::contains => label.contains(&operand),
-            _ => false,
-        }
+        compare(field, operator, operand)
     });
-    query_result.filters.push(filter_function);
-
-    Ok(())
+    Ok(filter_function)
 }
 
-/// Parse a filter for the command field.
+/// Parse a filter for the dependencies of a task.
 ///
-/// This filter syntax is exactly the same as the [label] filter.
-/// Only the keyword changed from `label` to `command`.
-pub fn command(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result<()> {
+/// This filter syntax looks like this:
+/// `dependencies [=|!=] task_id`
+///
+/// `=` matches all tasks that depend on the given task, `!=` all tasks that don't.
+pub fn dependencies(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
     let mut filter = section.into_inner();
-    // The first word should be the `command` keyword.
-    let _command = filter.next().unwrap();
-
-    // Get the operator that should be applied in this filter.
-    // Can be either of [Rule::eq | Rule::neq].
+    let _dependencies = filter.next().unwrap();
     let operator = filter.next().unwrap().as_rule();
+    let operand: usize = filter
+        .next()
+        .unwrap()
+        .as_str()
+        .parse()
+        .context("Expected a task id")?;
 
-    // Get the name of the command we should filter for.
-    let operand = filter.next().unwrap().as_str().to_string();
-
-    // Build the command filter function.
     let filter_function = Box::new(move |task: &Task| -> bool {
-        let command = task.command.as_str();
+        let depends = task.dependencies.contains(&operand);
         match operator {
-            Rule::eq => command == operand,
-            Rule::neq => command != operand,
-            Rule::contains => command.contains(&operand),
+            Rule::eq => depends,
+            Rule::neq => !depends,
             _ => false,
         }
     });
-    query_result.filters.push(filter_function);
+    Ok(filter_function)
+}
+
+/// Parse a filter for the duration of a task.
+///
+/// This filter syntax looks like this:
+/// `duration [=|!=|<|>] duration`, where the duration looks like `1d`, `2h`, `1h30m` or `90s`.
+///
+/// Durations are compared with a precision of seconds.
+/// Tasks that haven't been started yet don't match any duration filter.
+pub fn duration(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
+    let mut filter = section.into_inner();
+    let _duration = filter.next().unwrap();
+    let operator = filter.next().unwrap().as_rule();
+    let operand = parse_duration(filter.next().unwrap().as_str())?.num_seconds();
 
-    Ok(())
+    let filter_function = Box::new(move |task: &Task| -> bool {
+        let Some(duration) = task_duration(task) else {
+            return false;
+        };
+
+        compare(duration.num_seconds(), operator, operand)
+    });
+    Ok(filter_function)
 }
 
 /// Parse a filter for the status field.
@@ -329,7 +422,7 @@ pub fn command(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Resul
 ///         },
 ///     ],
 /// }
-pub fn status(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result<()> {
+pub fn status(section: Pair<'_, Rule>) -> Result<Box<FilterFunction>> {
     let mut filter = section.into_inner();
     // The first word should be the `status` keyword.
     let _status = filter.next().unwrap();
@@ -375,7 +468,161 @@ pub fn status(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Result
             _ => false,
         }
     });
-    query_result.filters.push(filter_function);
+    Ok(filter_function)
+}
+
+/// Build a filter for a text field, i.e. the `label`, `command`, `path` or `group` field.
+///
+/// `field` returns the field's value of a task or `None`, if the task doesn't have such a value.
+/// Tasks without a value only match the `!=` operator.
+fn text(
+    section: Pair<'_, Rule>,
+    field: impl Fn(&Task) -> Option<Cow<'_, str>> + 'static,
+) -> Result<Box<FilterFunction>> {
+    let mut filter = section.into_inner();
+    // The first word is the keyword of the column.
+    let _column = filter.next().unwrap();
+
+    // Get the operator that should be applied in this filter.
+    // Can be either of [Rule::eq | Rule::neq | Rule::contains | Rule::regex].
+    let operator = filter.next().unwrap().as_rule();
+
+    // Get the value we should filter for.
+    let matcher = TextMatcher::new(operator, text_operand(filter.next().unwrap()))?;
+
+    let filter_function = Box::new(move |task: &Task| -> bool {
+        match field(task) {
+            Some(value) => matcher.matches(&value),
+            None => matcher.matches_missing(),
+        }
+    });
+    Ok(filter_function)
+}
+
+/// The operator and value of a text filter.
+enum TextMatcher {
+    Eq(String),
+    Neq(String),
+    Contains(String),
+    Regex(Regex),
+}
+
+impl TextMatcher {
+    fn new(operator: Rule, operand: String) -> Result<Self> {
+        let matcher = match operator {
+            Rule::eq => TextMatcher::Eq(operand),
+            Rule::neq => TextMatcher::Neq(operand),
+            Rule::contains => TextMatcher::Contains(operand),
+            Rule::regex => TextMatcher::Regex(
+                Regex::new(&operand).context(format!("Invalid regular expression: {operand}"))?,
+            ),
+            _ => bail!("Unexpected operator {operator:?} for a text filter"),
+        };
+
+        Ok(matcher)
+    }
+
+    fn matches(&self, value: &str) -> bool {
+        match self {
+            TextMatcher::Eq(operand) => value == operand,
+            TextMatcher::Neq(operand) => value != operand,
+            TextMatcher::Contains(operand) => value.contains(operand.as_str()),
+            TextMatcher::Regex(regex) => regex.is_match(value),
+        }
+    }
+
+    /// Whether a task without such a value matches.
+    fn matches_missing(&self) -> bool {
+        matches!(self, TextMatcher::Neq(_))
+    }
+}
+
+/// Apply a comparison operator to two numbers.
+fn compare(field: i64, operator: Rule, operand: i64) -> bool {
+    match operator {
+        Rule::eq => field == operand,
+        Rule::neq => field != operand,
+        Rule::lt => field < operand,
+        Rule::gt => field > operand,
+        _ => false,
+    }
+}
+
+/// Parse a duration like `1d`, `2h`, `1h30m` or `90s`.
+fn parse_duration(duration: &str) -> Result<TimeDelta> {
+    let mut total = TimeDelta::zero();
+    let mut amount = String::new();
+    for char in duration.chars() {
+        if char.is_ascii_digit() {
+            amount.push(char);
+            continue;
+        }
+
+        let amount: i64 = std::mem::take(&mut amount)
+            .parse()
+            .context(format!("Invalid duration: {duration}"))?;
+        let delta = match char {
+            'd' => TimeDelta::try_days(amount),
+            'h' => TimeDelta::try_hours(amount),
+            'm' => TimeDelta::try_minutes(amount),
+            's' => TimeDelta::try_seconds(amount),
+            _ => None,
+        };
+        let Some(delta) = delta.and_then(|delta| total.checked_add(&delta)) else {
+            bail!("Invalid duration: {duration}");
+        };
+        total = delta;
+    }
+
+    Ok(total)
+}
+
+/// The exit code of a finished task.
+/// Tasks that have been killed, couldn't be spawned or never ran don't have one.
+pub fn exit_code(task: &Task) -> Option<i32> {
+    match &task.status {
+        TaskStatus::Done {
+            result: TaskResult::Success,
+            ..
+        } => Some(0),
+        TaskStatus::Done {
+            result: TaskResult::Failed(code),
+            ..
+        } => Some(*code),
+        _ => None,
+    }
+}
+
+/// How long a task ran or, if it's still running, has been running so far.
+pub fn task_duration(task: &Task) -> Option<TimeDelta> {
+    match task.start_and_end() {
+        (Some(start), Some(end)) => Some(end - start),
+        (Some(start), None) => Some(Local::now() - start),
+        _ => None,
+    }
+}
+
+/// Get the value of a text filter, e.g. the `label` or `command` filter.
+///
+/// The value is either wrapped in double quotes or ends right before the next part of the query.
+/// The quotes aren't part of the value and escaped quotes and backslashes inside them are unescaped.
+fn text_operand(operand: Pair<'_, Rule>) -> String {
+    let Some(text) = operand.into_inner().next() else {
+        return String::new();
+    };
+    if text.as_rule() != Rule::quoted_text {
+        return text.as_str().to_string();
+    }
+
+    // The grammar ensures that every backslash is followed by the escaped character.
+    let mut value = String::new();
+    let mut chars = text.as_str().chars();
+    while let Some(char) = chars.next() {
+        match char {
+            '\\' => value.extend(chars.next()),
+            char => value.push(char),
+        }
+    }
 
-    Ok(())
+    value
 }
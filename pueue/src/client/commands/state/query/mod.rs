@@ -8,10 +8,12 @@ use pueue_lib::task::{Task, TaskResult, TaskStatus};
 use crate::internal_prelude::*;
 
 mod column_selection;
+mod expression;
 mod filters;
 mod limit;
 mod order_by;
 
+use expression::FilterExpression;
 use limit::Limit;
 use order_by::Direction;
 
@@ -32,8 +34,8 @@ pub struct QueryResult {
     /// The list of selected columns based.
     pub selected_columns: Vec<Rule>,
 
-    /// A list of filter functions that should be applied to the list of tasks.
-    filters: Vec<Box<FilterFunction>>,
+    /// The filters that should be applied to the list of tasks, combined via boolean logic.
+    filter: Option<FilterExpression>,
 
     /// A list of filter functions that should be applied to the list of tasks.
     order_by: Option<(Rule, Direction)>,
@@ -45,20 +47,16 @@ pub struct QueryResult {
 impl QueryResult {
     /// Take a list of tasks and apply all filters to it.
     pub fn apply_filters(&self, tasks: Vec<Task>) -> Vec<Task> {
-        let mut iter = tasks.into_iter();
-
-        // If requested, only look at tasks of a specific group.
-        if let Some(group) = &self.group {
-            iter = iter
-                .filter(|task| task.group == *group)
-                .collect::<Vec<Task>>()
-                .into_iter();
-        }
-
-        for filter in self.filters.iter() {
-            iter = iter.filter(filter).collect::<Vec<Task>>().into_iter();
-        }
-        iter.collect()
+        tasks
+            .into_iter()
+            // If requested, only look at tasks of a specific group.
+            .filter(|task| self.group.as_ref().is_none_or(|group| task.group == *group))
+            .filter(|task| {
+                self.filter
+                    .as_ref()
+                    .is_none_or(|filter| filter.matches(task))
+            })
+            .collect()
     }
 
     /// Take a list of tasks and apply all filters to it.
@@ -90,6 +88,7 @@ impl QueryResult {
 
                 rank_status(task1).cmp(&rank_status(task2))
             }
+            Rule::column_priority => task1.priority.cmp(&task2.priority),
             Rule::column_label => task1.label.cmp(&task2.label),
             Rule::column_command => task1.command.cmp(&task2.command),
             Rule::column_path => task1.path.cmp(&task2.path),
@@ -121,6 +120,12 @@ impl QueryResult {
                 let (_, end2) = task2.start_and_end();
                 end1.cmp(&end2)
             }
+            Rule::column_dependencies => task1.dependencies.cmp(&task2.dependencies),
+            Rule::column_group => task1.group.cmp(&task2.group),
+            Rule::column_exit_code => filters::exit_code(task1).cmp(&filters::exit_code(task2)),
+            Rule::column_duration => {
+                filters::task_duration(task1).cmp(&filters::task_duration(task2))
+            }
             _ => std::cmp::Ordering::Less,
         });
 
@@ -186,10 +191,7 @@ pub fn apply_query(query: &str, group: &Option<String>) -> Result<QueryResult> {
         // E.g. `columns=id,status,start,end`
         match section.as_rule() {
             Rule::column_selection => column_selection::apply(section, &mut query_result)?,
-            Rule::datetime_filter => filters::datetime(section, &mut query_result)?,
-            Rule::label_filter => filters::label(section, &mut query_result)?,
-            Rule::command_filter => filters::command(section, &mut query_result)?,
-            Rule::status_filter => filters::status(section, &mut query_result)?,
+            Rule::expression => query_result.filter = Some(expression::expression(section)?),
             Rule::order_by_condition => order_by::order_by(section, &mut query_result)?,
             Rule::limit_condition => limit::limit(section, &mut query_result)?,
             _ => (),
@@ -285,6 +287,9 @@ mod test {
             start: Local.with_ymd_and_hms(2022, 1, 2, 12, 0, 0).unwrap(),
         };
         running.id = 4;
+        running
+            .envs
+            .insert("PUEUE_TEST".to_string(), "yes".to_string());
         tasks.insert(running.id, running);
 
         // Add two queued tasks with different command
@@ -296,6 +301,7 @@ mod test {
         // Task 6 depends on task 5
         queued.id = 6;
         queued.dependencies.push(5);
+        queued.priority = 1;
         tasks.insert(queued.id, queued);
 
         tasks
@@ -324,6 +330,18 @@ mod test {
         Ok(())
     }
 
+    /// The column selection may also follow the filters.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn column_selection_after_filters() -> Result<()> {
+        let result = apply_query("label=test columns=id,label order_by id desc", &None)?;
+        assert_eq!(
+            result.selected_columns,
+            [Rule::column_id, Rule::column_label]
+        );
+
+        Ok(())
+    }
+
     /// Select the first few entries of the list
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn limit_first() -> Result<()> {
@@ -530,6 +548,121 @@ mod test {
         Ok(())
     }
 
+    /// Combine filters via `and`, `or`, `not` and parentheses.
+    #[rstest]
+    #[case("status=failed or (status=running and start<2022-01-05)", &[0, 4])]
+    // `and` binds stronger than `or`.
+    #[case("status=failed or status=running and start>2022-01-05", &[0])]
+    #[case("not status=queued and not (status=stashed or status=failed)", &[1, 4])]
+    #[case("not not status=running", &[4])]
+    // Filters without an operator are combined via `and`, quotes end text values.
+    #[case("command=\"echo Hello Pueue\" status=queued", &[5, 6])]
+    #[case("command%=echo or label=label-10-1", &[1, 5, 6])]
+    #[case("(label%=label) and not label=label-10-1 order_by id desc first 1", &[2])]
+    // Times can be specified without seconds.
+    #[case("start<10:00 and status!=running", &[0, 1])]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn boolean_logic(#[case] query: &'static str, #[case] expected: &[usize]) -> Result<()> {
+        let tasks = test_tasks_with_query(query, &None)?;
+
+        let ids: Vec<usize> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, expected, "Unexpected tasks for query: {query}");
+
+        Ok(())
+    }
+
+    /// Incomplete boolean expressions are rejected.
+    #[rstest]
+    #[case("status=failed or")]
+    #[case("(status=failed")]
+    #[case("not")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn invalid_boolean_logic(#[case] query: &'static str) -> Result<()> {
+        assert!(apply_query(query, &None).is_err());
+
+        Ok(())
+    }
+
+    /// Filter by group, priority, path, exit code, dependencies, duration and env variables.
+    #[rstest]
+    #[case("group=testgroup", &[3])]
+    #[case("group~^test", &[3])]
+    #[case("path%=tmp label=label-10-2", &[2])]
+    #[case("id<2 or id=6", &[0, 1, 6])]
+    #[case("priority>0", &[6])]
+    #[case("exit_code=255", &[0])]
+    // Only finished tasks have an exit code.
+    #[case("exit_code!=0", &[0])]
+    #[case("dependencies=5", &[6])]
+    #[case("duration>1h", &[4])]
+    #[case("duration=5m", &[0, 1])]
+    #[case("duration<4m59s", &[])]
+    #[case("command~\"^echo\" priority=0", &[5])]
+    #[case("label~\"-[02]$\"", &[0, 2])]
+    #[case("env.PUEUE_TEST=yes", &[4])]
+    // Tasks without the variable match `!=`.
+    #[case("env.PUEUE_TEST!=yes", &[0, 1, 2, 3, 5, 6])]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn filter_columns(#[case] query: &'static str, #[case] expected: &[usize]) -> Result<()> {
+        let tasks = test_tasks_with_query(query, &None)?;
+
+        let ids: Vec<usize> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, expected, "Unexpected tasks for query: {query}");
+
+        Ok(())
+    }
+
+    /// Quoted values can contain escaped double quotes and backslashes.
+    #[rstest]
+    #[case(r#"command="echo \"Hello\" \\ Pueue""#, &[7])]
+    #[case(r#"command%="\"Hello\"" or label=label-10-0"#, &[0, 7])]
+    #[case(r#"command%="\\""#, &[7])]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn escaped_quotes(#[case] query: &'static str, #[case] expected: &[usize]) -> Result<()> {
+        let mut task = build_task();
+        task.id = 7;
+        task.command = r#"echo "Hello" \ Pueue"#.to_string();
+        let mut tasks = test_tasks();
+        tasks.push(task);
+
+        let query_result = apply_query(query, &None)?;
+        let ids: Vec<usize> = query_result
+            .apply_filters(tasks)
+            .iter()
+            .map(|task| task.id)
+            .collect();
+        assert_eq!(ids, expected, "Unexpected tasks for query: {query}");
+
+        Ok(())
+    }
+
+    /// Invalid regular expressions are rejected.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn invalid_regex() -> Result<()> {
+        assert!(apply_query("command~\"(\"", &None).is_err());
+
+        Ok(())
+    }
+
+    /// Every column can be used for ordering.
+    #[rstest]
+    #[case("order_by priority desc", 6)]
+    #[case("order_by group desc", 3)]
+    #[case("order_by exit_code desc", 0)]
+    #[case("order_by duration desc", 4)]
+    #[case("order_by dependencies desc", 6)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn order_by_column(#[case] query: &'static str, #[case] first: usize) -> Result<()> {
+        let tasks = test_tasks_with_query(query, &None)?;
+
+        assert_eq!(
+            tasks[0].id, first,
+            "Unexpected first task for query: {query}"
+        );
+
+        Ok(())
+    }
+
     /// Filter tasks by label with the "eq" `=` "ne" `!=` and "contains" `%=`filter.
     #[rstest]
     #[case("%=", "label", 3)]
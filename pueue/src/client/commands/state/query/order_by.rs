@@ -33,7 +33,7 @@ pub enum Direction {
 ///             inner: [],
 ///         },
 ///         Pair {
-///             rule: column,
+///             rule: order_columns,
 ///             span: Span {
 ///                 str: "label",
 ///                 start: 9,
@@ -68,7 +68,7 @@ pub fn order_by(section: Pair<'_, Rule>, query_result: &mut QueryResult) -> Resu
     let _order_by = order_by_condition.next().unwrap();
 
     // Get the column we should order by.
-    // The column is wrapped by a `Rule::column` keyword.
+    // The column is wrapped by a `Rule::order_columns` keyword.
     let column_keyword = order_by_condition.next().unwrap();
     let column = column_keyword.into_inner().next().unwrap().as_rule();
 
@@ -1,12 +1,14 @@
+use std::collections::BTreeSet;
+
 use chrono::TimeDelta;
-use comfy_table::{Cell, ContentArrangement, Row, Table, presets::UTF8_HORIZONTAL_ONLY};
-use crossterm::style::Color;
+use comfy_table::{Attribute, Cell, ContentArrangement, Row, Table, presets::UTF8_HORIZONTAL_ONLY};
 use pueue_lib::{
     settings::Settings,
-    task::{Task, TaskResult, TaskStatus},
+    task::{Task, TaskStatus},
 };
 
 use super::{OutputStyle, formatted_start_end, query::Rule, start_of_today};
+use crate::client::display_helper::status_text_and_color;
 
 /// This builder is responsible for determining which table columns should be displayed and
 /// building a full [comfy_table] from a list of given [Task]s.
@@ -20,6 +22,9 @@ pub struct TableBuilder<'a> {
     /// not.
     selected_columns: bool,
 
+    /// The ids of tasks whose rows should be highlighted, e.g. the selected task in `pueue tui`.
+    highlighted_tasks: BTreeSet<usize>,
+
     /// This following fields represent which columns should be displayed when executing
     /// `pueue status`. `true` for any column means that it'll be shown in the table.
     id: bool,
@@ -40,6 +45,7 @@ impl<'a> TableBuilder<'a> {
             settings,
             style,
             selected_columns: false,
+            highlighted_tasks: BTreeSet::new(),
             id: true,
             status: true,
             priority: false,
@@ -71,6 +77,34 @@ impl<'a> TableBuilder<'a> {
         table
     }
 
+    /// Build a single table for the tasks of several daemons.
+    /// An additional column shows the name of the context each task belongs to.
+    pub fn build_with_contexts(mut self, tasks: &[(String, Task)]) -> Table {
+        let plain_tasks: Vec<Task> = tasks.iter().map(|(_, task)| task.clone()).collect();
+        self.determine_special_columns(&plain_tasks);
+
+        let mut header = vec![Cell::new("Context")];
+        header.extend(self.build_header());
+        let rows = tasks.iter().map(|(context, task)| {
+            let mut cells = vec![Cell::new(context)];
+            cells.extend(self.build_task_cells(task));
+            self.build_row(task, cells)
+        });
+
+        let mut table = Table::new();
+        table
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .load_preset(UTF8_HORIZONTAL_ONLY)
+            .set_header(header)
+            .add_rows(rows);
+
+        if self.style.enabled {
+            table.enforce_styling();
+        }
+
+        table
+    }
+
     /// By default, several columns aren't shown until there's at least one task with relevant data.
     /// This function determines whether any of those columns should be shown.
     fn determine_special_columns(&mut self, tasks: &[Task]) {
@@ -147,8 +181,13 @@ impl<'a> TableBuilder<'a> {
         }
     }
 
-    /// Build a header row based on the current selection of columns.
-    fn build_header(&self) -> Row {
+    /// Highlight the row of the task with the given id.
+    pub fn highlight_task(&mut self, task_id: usize) {
+        self.highlighted_tasks.insert(task_id);
+    }
+
+    /// Build the header cells based on the current selection of columns.
+    fn build_header(&self) -> Vec<Cell> {
         let mut header = Vec::new();
 
         // Create table header row
@@ -183,107 +222,111 @@ impl<'a> TableBuilder<'a> {
             header.push(Cell::new("End"));
         }
 
-        Row::from(header)
+        header
     }
 
     fn build_task_rows(&self, tasks: &[Task]) -> Vec<Row> {
-        let mut rows = Vec::new();
-        // Add rows one by one.
-        for task in tasks.iter() {
-            let mut row = Row::new();
-            // Users can set a max height per row.
-            if let Some(height) = self.settings.client.max_status_lines {
-                row.max_height(height);
-            }
+        tasks
+            .iter()
+            .map(|task| self.build_row(task, self.build_task_cells(task)))
+            .collect()
+    }
 
-            if self.id {
-                row.add_cell(Cell::new(task.id));
-            }
+    /// Build the cells of a single task based on the current selection of columns.
+    fn build_task_cells(&self, task: &Task) -> Vec<Cell> {
+        let mut cells = Vec::new();
 
-            if self.status {
-                // Determine the human readable task status representation and the respective color.
-                let status_string = task.status.to_string();
-                let (status_text, color) = match &task.status {
-                    TaskStatus::Running { .. } => (status_string, Color::Green),
-                    TaskStatus::Paused { .. } | TaskStatus::Locked { .. } => {
-                        (status_string, Color::White)
-                    }
-                    TaskStatus::Done { result, .. } => match result {
-                        TaskResult::Success => (TaskResult::Success.to_string(), Color::Green),
-                        TaskResult::DependencyFailed => {
-                            ("Dependency failed".to_string(), Color::Red)
-                        }
-                        TaskResult::FailedToSpawn(_) => ("Failed to spawn".to_string(), Color::Red),
-                        TaskResult::Failed(code) => (format!("Failed ({code})"), Color::Red),
-                        _ => (result.to_string(), Color::Red),
-                    },
-                    _ => (status_string, Color::Yellow),
-                };
-                row.add_cell(self.style.styled_cell(status_text, Some(color), None));
-            }
+        if self.id {
+            cells.push(Cell::new(task.id));
+        }
 
-            if self.priority {
-                row.add_cell(Cell::new(task.priority.to_string()));
-            }
+        if self.status {
+            // Determine the human readable task status representation and the respective color.
+            let (status_text, color) = status_text_and_color(&task.status);
+            cells.push(self.style.styled_cell(status_text, Some(color), None));
+        }
+
+        if self.priority {
+            cells.push(Cell::new(task.priority.to_string()));
+        }
 
-            if self.enqueue_at {
-                if let TaskStatus::Stashed {
-                    enqueue_at: Some(enqueue_at),
-                } = task.status
-                {
-                    // Only show the date if the task is not supposed to be enqueued today.
-                    let enqueue_today =
-                        enqueue_at <= start_of_today() + TimeDelta::try_days(1).unwrap();
-                    let formatted_enqueue_at = if enqueue_today {
-                        enqueue_at.format(&self.settings.client.status_time_format)
-                    } else {
-                        enqueue_at.format(&self.settings.client.status_datetime_format)
-                    };
-                    row.add_cell(Cell::new(formatted_enqueue_at));
+        if self.enqueue_at {
+            if let TaskStatus::Stashed {
+                enqueue_at: Some(enqueue_at),
+            } = task.status
+            {
+                // Only show the date if the task is not supposed to be enqueued today.
+                let enqueue_today =
+                    enqueue_at <= start_of_today() + TimeDelta::try_days(1).unwrap();
+                let formatted_enqueue_at = if enqueue_today {
+                    enqueue_at.format(&self.settings.client.status_time_format)
                 } else {
-                    row.add_cell(Cell::new(""));
-                }
+                    enqueue_at.format(&self.settings.client.status_datetime_format)
+                };
+                cells.push(Cell::new(formatted_enqueue_at));
+            } else {
+                cells.push(Cell::new(""));
             }
+        }
 
-            if self.dependencies {
-                let text = task
-                    .dependencies
-                    .iter()
-                    .map(|id| id.to_string())
-                    .collect::<Vec<String>>()
-                    .join(", ");
-                row.add_cell(Cell::new(text));
-            }
+        if self.dependencies {
+            // Dependencies with a non-default condition are shown as `ID:CONDITION`.
+            let text = task
+                .dependencies
+                .iter()
+                .map(|id| match task.dependency_conditions.get(id) {
+                    Some(condition) => format!("{id}:{condition}"),
+                    None => id.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            cells.push(Cell::new(text));
+        }
 
-            if self.label {
-                row.add_cell(Cell::new(task.label.as_deref().unwrap_or_default()));
-            }
+        if self.label {
+            cells.push(Cell::new(task.label.as_deref().unwrap_or_default()));
+        }
 
-            // Add command and path.
-            if self.command {
-                if self.settings.client.show_expanded_aliases {
-                    row.add_cell(Cell::new(&task.command));
-                } else {
-                    row.add_cell(Cell::new(&task.original_command));
-                }
+        // Add command and path.
+        if self.command {
+            if self.settings.client.show_expanded_aliases {
+                cells.push(Cell::new(&task.command));
+            } else {
+                cells.push(Cell::new(&task.original_command));
             }
+        }
 
-            if self.path {
-                row.add_cell(Cell::new(task.path.to_string_lossy()));
-            }
+        if self.path {
+            cells.push(Cell::new(task.path.to_string_lossy()));
+        }
 
-            // Add start and end info
-            let (start, end) = formatted_start_end(task, self.settings);
-            if self.start {
-                row.add_cell(Cell::new(start));
-            }
-            if self.end {
-                row.add_cell(Cell::new(end));
-            }
+        // Add start and end info
+        let (start, end) = formatted_start_end(task, self.settings);
+        if self.start {
+            cells.push(Cell::new(start));
+        }
+        if self.end {
+            cells.push(Cell::new(end));
+        }
+
+        cells
+    }
+
+    /// Turn the cells of a task into a row, which is highlighted if requested.
+    fn build_row(&self, task: &Task, mut cells: Vec<Cell>) -> Row {
+        if self.highlighted_tasks.contains(&task.id) {
+            cells = cells
+                .into_iter()
+                .map(|cell| cell.add_attribute(Attribute::Reverse))
+                .collect();
+        }
 
-            rows.push(row);
+        let mut row = Row::from(cells);
+        // Users can set a max height per row.
+        if let Some(height) = self.settings.client.max_status_lines {
+            row.max_height(height);
         }
 
-        rows
+        row
     }
 }
@@ -0,0 +1,78 @@
+//! Named queries from the `client.views` setting, which are used via `pueue status @name`.
+use std::collections::BTreeMap;
+
+use crossterm::style::Attribute;
+use pueue_lib::settings::Settings;
+
+use crate::{client::style::OutputStyle, internal_prelude::*};
+
+/// Replace all `@name` parts of a query with the query of the respective view.
+///
+/// Views are only expanded once, i.e. views cannot reference other views.
+pub fn expand_views(query: &[String], views: &BTreeMap<String, String>) -> Result<Vec<String>> {
+    query
+        .iter()
+        .map(|part| {
+            let Some(name) = part.strip_prefix('@') else {
+                return Ok(part.clone());
+            };
+
+            match views.get(name) {
+                Some(view) => Ok(view.clone()),
+                None if views.is_empty() => {
+                    bail!(
+                        "Unknown view @{name}. Views can be defined in the `client.views` setting."
+                    )
+                }
+                None => {
+                    let names: Vec<String> = views.keys().map(|name| format!("@{name}")).collect();
+                    bail!(
+                        "Unknown view @{name}. Available views: {}",
+                        names.join(", ")
+                    )
+                }
+            }
+        })
+        .collect()
+}
+
+/// Print all views with their query.
+pub fn print_views(settings: &Settings, style: &OutputStyle) {
+    if settings.client.views.is_empty() {
+        println!("No views defined. Views can be defined in the `client.views` setting.");
+        return;
+    }
+
+    for (name, query) in &settings.client.views {
+        let name = style.style_text(format!("@{name}"), None, Some(Attribute::Bold));
+        println!("{name}: {query}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn expand() -> Result<()> {
+        let views = BTreeMap::from([(
+            "failing".to_string(),
+            "status=failed columns=id,command,end order_by end desc".to_string(),
+        )]);
+        let query = ["@failing".to_string(), "first".to_string(), "5".to_string()];
+
+        assert_eq!(
+            expand_views(&query, &views)?,
+            [
+                "status=failed columns=id,command,end order_by end desc",
+                "first",
+                "5"
+            ]
+        );
+        assert!(expand_views(&["@unknown".to_string()], &views).is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,188 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::stdout,
+    time::Duration,
+};
+
+use chrono::Local;
+use crossterm::{
+    cursor::{Hide, Show},
+    execute,
+    style::Attribute,
+    terminal::{EnterAlternateScreen, LeaveAlternateScreen, size},
+};
+use pueue_lib::{Client, settings::Settings, state::State, task::TaskStatus};
+
+use super::print_state;
+use crate::{
+    client::{
+        commands::{get_state, tui::draw},
+        style::OutputStyle,
+    },
+    internal_prelude::*,
+};
+
+/// Redraw the status in place every `interval` seconds, until Ctrl+C is pressed.
+///
+/// The connection to the daemon is reused for all refreshes.
+pub async fn watch_state(
+    client: &mut Client,
+    settings: &Settings,
+    style: &OutputStyle,
+    query: Vec<String>,
+    group: Option<String>,
+    interval: f64,
+) -> Result<()> {
+    let Some(interval) = Duration::try_from_secs_f64(interval)
+        .ok()
+        .filter(|interval| !interval.is_zero())
+    else {
+        bail!("The watch interval must be a positive amount of seconds.");
+    };
+
+    let _screen = AlternateScreen::enter()?;
+    // The Ctrl+C handler is installed once and also covers the requests to the daemon.
+    tokio::select! {
+        result = watch_loop(client, settings, style, query, group, interval) => result,
+        result = tokio::signal::ctrl_c() => Ok(result?),
+    }
+}
+
+/// Shows an alternate screen without a cursor and restores the terminal once it's dropped,
+/// no matter how watching ends.
+struct AlternateScreen;
+
+impl AlternateScreen {
+    fn enter() -> Result<Self> {
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+        Ok(AlternateScreen)
+    }
+}
+
+impl Drop for AlternateScreen {
+    fn drop(&mut self) {
+        if let Err(err) = execute!(stdout(), Show, LeaveAlternateScreen) {
+            eprintln!("Failed to restore the terminal: {err}");
+        }
+    }
+}
+
+async fn watch_loop(
+    client: &mut Client,
+    settings: &Settings,
+    style: &OutputStyle,
+    query: Vec<String>,
+    group: Option<String>,
+    interval: Duration,
+) -> Result<()> {
+    let mut previous: Option<BTreeMap<usize, TaskStatus>> = None;
+    loop {
+        let state = get_state(client).await?;
+        let changed = changed_tasks(previous.as_ref(), &state);
+        previous = Some(
+            state
+                .tasks
+                .iter()
+                .map(|(id, task)| (*id, task.status.clone()))
+                .collect(),
+        );
+
+        let tasks = state.tasks.values().cloned().collect();
+        let output = print_state(
+            state,
+            tasks,
+            style,
+            settings,
+            false,
+            group.clone(),
+            Some(query.clone()),
+            &changed,
+        )?;
+
+        let header = format!(
+            "Every {}s: pueue status {}",
+            interval.as_secs_f64(),
+            query.join(" ")
+        );
+        let time = Local::now().format(&settings.client.status_time_format);
+        let mut lines = vec![
+            style.style_text(format!("{header} | {time}"), None, Some(Attribute::Dim)),
+            String::new(),
+        ];
+        lines.extend(output.lines().map(ToString::to_string));
+
+        // Anything that doesn't fit onto the screen is cut off, as it would scroll the screen.
+        let (_, height) = size()?;
+        lines.truncate(height as usize);
+        draw(&mut stdout(), &lines)?;
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Get the ids of all tasks whose status changed since the last refresh, including new tasks.
+/// Nothing is highlighted on the first refresh.
+fn changed_tasks(previous: Option<&BTreeMap<usize, TaskStatus>>, state: &State) -> BTreeSet<usize> {
+    let Some(previous) = previous else {
+        return BTreeSet::new();
+    };
+
+    state
+        .tasks
+        .values()
+        .filter(|task| previous.get(&task.id) != Some(&task.status))
+        .map(|task| task.id)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use pretty_assertions::assert_eq;
+    use pueue_lib::{state::PUEUE_DEFAULT_GROUP, task::Task};
+
+    use super::*;
+
+    fn state(statuses: &[TaskStatus]) -> State {
+        let mut state = State::new();
+        for (id, status) in statuses.iter().enumerate() {
+            let task = Task::new(
+                "ls".to_string(),
+                PathBuf::from("/tmp"),
+                Default::default(),
+                PUEUE_DEFAULT_GROUP.to_string(),
+                status.clone(),
+                Vec::new(),
+                0,
+                None,
+            );
+            state.tasks.insert(id, Task { id, ..task });
+        }
+
+        state
+    }
+
+    #[test]
+    fn detect_changed_tasks() {
+        let stashed = TaskStatus::Stashed { enqueue_at: None };
+        let queued = TaskStatus::Queued {
+            enqueued_at: Local::now(),
+        };
+
+        let first = state(&[stashed.clone(), stashed.clone()]);
+        assert_eq!(changed_tasks(None, &first), BTreeSet::new());
+
+        let previous = first
+            .tasks
+            .iter()
+            .map(|(id, task)| (*id, task.status.clone()))
+            .collect();
+        // The second task changed and the third task is new.
+        let second = state(&[stashed.clone(), queued, stashed]);
+        assert_eq!(
+            changed_tasks(Some(&previous), &second),
+            BTreeSet::from([1, 2])
+        );
+    }
+}
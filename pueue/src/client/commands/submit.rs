@@ -0,0 +1,128 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    env::{current_dir, vars},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use pueue_lib::{
+    Client, Response,
+    message::{SubmitRequest, SubmittedTask},
+    task::DependencyCondition,
+};
+use serde::Deserialize;
+
+use super::{group_or_default, handle_response};
+use crate::{client::style::OutputStyle, internal_prelude::*};
+
+/// The representation of a workflow file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Workflow {
+    tasks: BTreeMap<String, WorkflowTask>,
+}
+
+/// A single task inside a workflow file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkflowTask {
+    command: String,
+    /// Local names of the dependencies, optionally followed by a condition (`name:failure`).
+    #[serde(default)]
+    after: Vec<String>,
+    group: Option<String>,
+    priority: Option<i32>,
+    label: Option<String>,
+    /// Additional environment variables on top of the current environment.
+    #[serde(default)]
+    env: HashMap<String, String>,
+    working_directory: Option<PathBuf>,
+    #[serde(default)]
+    stashed: bool,
+}
+
+/// Read a workflow file and add all of its tasks in one go.
+pub async fn submit(client: &mut Client, style: &OutputStyle, file: PathBuf) -> Result<()> {
+    let content = std::fs::read_to_string(&file)
+        .with_context(|| format!("Failed to read workflow file {file:?}"))?;
+    let workflow: Workflow = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse workflow file {file:?}"))?;
+
+    // Relative working directories are interpreted relative to the workflow file.
+    let base_dir = match file.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent.canonicalize()?,
+        _ => current_dir()?,
+    };
+
+    // Catch the current environment for later injection into the tasks' processes.
+    let environment: HashMap<String, String> = HashMap::from_iter(vars());
+
+    let mut tasks = BTreeMap::new();
+    for (name, task) in workflow.tasks {
+        let group = group_or_default(&task.group);
+        let path_is_fallback = task.working_directory.is_none();
+        let path = match task.working_directory {
+            Some(path) => resolve_path(&base_dir, path),
+            None => current_dir()?,
+        };
+
+        let mut envs = environment.clone();
+        envs.extend(task.env);
+
+        let dependencies = task
+            .after
+            .iter()
+            .map(|dependency| parse_dependency(dependency))
+            .collect();
+
+        let task = SubmittedTask {
+            command: task.command,
+            path,
+            path_is_fallback,
+            envs,
+            group,
+            stashed: task.stashed,
+            priority: task.priority,
+            label: task.label,
+            dependencies,
+        };
+        tasks.insert(name, task);
+    }
+
+    client.send_request(SubmitRequest { tasks }).await?;
+
+    let response = client.receive_response().await?;
+    let Response::Submitted(response) = response else {
+        return handle_response(style, response);
+    };
+
+    println!("Workflow added ({} tasks):", response.task_ids.len());
+    let mut task_ids: Vec<_> = response.task_ids.into_iter().collect();
+    task_ids.sort_by_key(|(_, id)| *id);
+    for (name, id) in task_ids {
+        println!("  {id}: {name}");
+    }
+
+    Ok(())
+}
+
+fn resolve_path(base_dir: &Path, path: PathBuf) -> PathBuf {
+    if path.is_absolute() {
+        path
+    } else {
+        base_dir.join(path)
+    }
+}
+
+/// Parse a dependency in the form of `NAME[:CONDITION]`.
+///
+/// The suffix is only split off if it's a known condition, so task names may contain colons.
+fn parse_dependency(dependency: &str) -> (String, DependencyCondition) {
+    dependency
+        .rsplit_once(':')
+        .and_then(|(name, condition)| {
+            let condition = DependencyCondition::from_str(condition).ok()?;
+            Some((name.to_string(), condition))
+        })
+        .unwrap_or_else(|| (dependency.to_string(), DependencyCondition::default()))
+}
@@ -0,0 +1,352 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use pueue_lib::{
+    message::*,
+    state::{PUEUE_DEFAULT_GROUP, State},
+    task::Task,
+};
+
+/// Everything the user can trigger via a keybinding, which requires talking to the daemon or
+/// leaving the interface.
+#[derive(Debug, PartialEq)]
+pub enum Action {
+    Quit,
+    /// Send a request and show the daemon's response.
+    Request(Box<Request>),
+    /// Open the task in the user's editor.
+    Edit(usize),
+    /// Change the priority of a task by the given amount.
+    Priority(usize, i32),
+}
+
+impl Action {
+    fn request(request: impl Into<Request>) -> Self {
+        Action::Request(Box::new(request.into()))
+    }
+}
+
+/// The state of the interface, i.e. the latest state of the daemon and the user's selection.
+#[derive(Debug, Default)]
+pub struct App {
+    pub state: Option<State>,
+    /// The name of the selected group.
+    pub group: Option<String>,
+    /// The id of the selected task.
+    pub task_id: Option<usize>,
+    /// A task that has been marked to be switched with another task.
+    pub marked_task: Option<usize>,
+    /// The last lines of the selected task's output.
+    pub log: Vec<String>,
+    /// A message for the user and whether it's an error.
+    pub message: Option<(String, bool)>,
+    pub show_help: bool,
+}
+
+impl App {
+    /// Use the latest state of the daemon and make sure the selection is still valid.
+    pub fn update_state(&mut self, state: State) {
+        let group_exists = self
+            .group
+            .as_ref()
+            .is_some_and(|group| state.groups.contains_key(group));
+        if !group_exists {
+            self.group = if state.groups.contains_key(PUEUE_DEFAULT_GROUP) {
+                Some(PUEUE_DEFAULT_GROUP.to_string())
+            } else {
+                state.groups.keys().next().cloned()
+            };
+        }
+        self.state = Some(state);
+
+        // Fall back to the first task of the group, if the selected task is gone.
+        let task_ids: Vec<usize> = self.tasks().iter().map(|task| task.id).collect();
+        if !self.task_id.is_some_and(|id| task_ids.contains(&id)) {
+            self.task_id = task_ids.first().copied();
+        }
+        if !self.marked_task.is_some_and(|id| task_ids.contains(&id)) {
+            self.marked_task = None;
+        }
+    }
+
+    /// All group names in the order they're displayed in.
+    pub fn groups(&self) -> Vec<String> {
+        let Some(state) = &self.state else {
+            return Vec::new();
+        };
+        state.groups.keys().cloned().collect()
+    }
+
+    /// All tasks of the selected group, ordered by their id.
+    pub fn tasks(&self) -> Vec<&Task> {
+        let (Some(state), Some(group)) = (&self.state, &self.group) else {
+            return Vec::new();
+        };
+        state
+            .tasks
+            .values()
+            .filter(|task| &task.group == group)
+            .collect()
+    }
+
+    pub fn selected_task(&self) -> Option<&Task> {
+        self.state.as_ref()?.tasks.get(&self.task_id?)
+    }
+
+    pub fn set_message(&mut self, message: impl ToString, error: bool) {
+        self.message = Some((message.to_string(), error));
+    }
+
+    /// Handle a key press.
+    /// Navigation is handled right away, everything else is returned as an [`Action`].
+    pub fn handle_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Some(Action::Quit);
+        }
+
+        // Messages are only shown until the next key press.
+        self.message = None;
+
+        // Any key closes the help.
+        if self.show_help {
+            self.show_help = false;
+            return None;
+        }
+
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Some(Action::Quit),
+            KeyCode::Char('?') => self.show_help = true,
+            KeyCode::Up | KeyCode::Char('k') => self.move_selection(-1),
+            KeyCode::Down | KeyCode::Char('j') => self.move_selection(1),
+            KeyCode::Home | KeyCode::Char('g') => self.move_selection(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.move_selection(isize::MAX),
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::BackTab => self.switch_group(-1),
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Tab => self.switch_group(1),
+            KeyCode::Char('S') => return self.group_action(|group| StartRequest { tasks: group }),
+            KeyCode::Char('P') => {
+                return self.group_action(|group| PauseRequest {
+                    tasks: group,
+                    wait: false,
+                });
+            }
+            KeyCode::Char('w') => return self.switch_tasks(),
+            KeyCode::Char(char) => return self.task_action(char),
+            _ => (),
+        }
+
+        None
+    }
+
+    /// Actions that target the selected task.
+    fn task_action(&mut self, key: char) -> Option<Action> {
+        let task = self.selected_task()?.clone();
+        let tasks = TaskSelection::TaskIds(vec![task.id]);
+
+        let action = match key {
+            's' => Action::request(StartRequest { tasks }),
+            'p' => Action::request(PauseRequest { tasks, wait: false }),
+            'x' => Action::request(KillRequest {
+                tasks,
+                signal: None,
+            }),
+            'z' => Action::request(StashRequest {
+                tasks,
+                enqueue_at: None,
+            }),
+            'e' => Action::request(EnqueueRequest {
+                tasks,
+                enqueue_at: None,
+            }),
+            'r' => {
+                if !task.is_done() {
+                    self.set_message(format!("Task {} isn't finished yet", task.id), true);
+                    return None;
+                }
+                Action::request(RestartRequest {
+                    tasks: vec![TaskToRestart {
+                        task_id: task.id,
+                        original_command: task.original_command,
+                        path: task.path,
+                        label: task.label,
+                        priority: task.priority,
+                    }],
+                    start_immediately: false,
+                    stashed: false,
+                })
+            }
+            'E' => Action::Edit(task.id),
+            '+' => Action::Priority(task.id, 1),
+            '-' => Action::Priority(task.id, -1),
+            _ => return None,
+        };
+
+        Some(action)
+    }
+
+    /// Actions that target the whole selected group.
+    fn group_action<T: Into<Request>>(
+        &self,
+        request: impl FnOnce(TaskSelection) -> T,
+    ) -> Option<Action> {
+        let group = self.group.clone()?;
+        Some(Action::request(request(TaskSelection::Group(group))))
+    }
+
+    /// The first press marks the selected task, the second press switches it with the marked one.
+    fn switch_tasks(&mut self) -> Option<Action> {
+        let task_id = self.task_id?;
+        match self.marked_task.take() {
+            Some(marked) if marked != task_id => Some(Action::request(SwitchRequest {
+                task_id_1: marked,
+                task_id_2: task_id,
+            })),
+            // Pressing it again on the marked task removes the mark.
+            Some(_) => None,
+            None => {
+                self.marked_task = Some(task_id);
+                self.set_message(
+                    format!("Marked task {task_id}. Press 'w' on another task to switch them."),
+                    false,
+                );
+                None
+            }
+        }
+    }
+
+    /// Move the task selection by the given offset, clamped to the first and last task.
+    fn move_selection(&mut self, offset: isize) {
+        let task_ids: Vec<usize> = self.tasks().iter().map(|task| task.id).collect();
+        if task_ids.is_empty() {
+            return;
+        }
+        let position = task_ids
+            .iter()
+            .position(|id| Some(*id) == self.task_id)
+            .unwrap_or(0);
+        let position = position
+            .saturating_add_signed(offset)
+            .min(task_ids.len() - 1);
+        self.task_id = Some(task_ids[position]);
+    }
+
+    /// Select the previous or next group, wrapping around at both ends.
+    fn switch_group(&mut self, offset: isize) {
+        let groups = self.groups();
+        if groups.is_empty() {
+            return;
+        }
+        let position = groups
+            .iter()
+            .position(|group| Some(group) == self.group.as_ref())
+            .unwrap_or(0);
+        let position = (position as isize + offset).rem_euclid(groups.len() as isize) as usize;
+        self.group = Some(groups[position].clone());
+        self.task_id = self.tasks().first().map(|task| task.id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::Local;
+    use pretty_assertions::assert_eq;
+    use pueue_lib::{
+        state::{Group, GroupStatus},
+        task::TaskStatus,
+    };
+
+    use super::*;
+
+    fn app() -> App {
+        let mut state = State::new();
+        for group in [PUEUE_DEFAULT_GROUP, "other"] {
+            state
+                .groups
+                .insert(group.to_string(), Group::new(GroupStatus::Running, 1));
+        }
+        for (id, group) in [
+            (0, PUEUE_DEFAULT_GROUP),
+            (1, PUEUE_DEFAULT_GROUP),
+            (2, "other"),
+        ] {
+            let task = Task::new(
+                "sleep 60".to_string(),
+                PathBuf::from("/tmp"),
+                Default::default(),
+                group.to_string(),
+                TaskStatus::Queued {
+                    enqueued_at: Local::now(),
+                },
+                Vec::new(),
+                0,
+                None,
+            );
+            state.tasks.insert(id, Task { id, ..task });
+        }
+
+        let mut app = App::default();
+        app.update_state(state);
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) -> Option<Action> {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    #[test]
+    fn navigation() {
+        let mut app = app();
+        assert_eq!(app.group.as_deref(), Some(PUEUE_DEFAULT_GROUP));
+        assert_eq!(app.task_id, Some(0));
+
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.task_id, Some(1));
+        // The selection stops at the last task of the group.
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.task_id, Some(1));
+
+        // Switching groups selects the first task of that group and wraps around.
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.group.as_deref(), Some("other"));
+        assert_eq!(app.task_id, Some(2));
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.group.as_deref(), Some(PUEUE_DEFAULT_GROUP));
+        assert_eq!(app.task_id, Some(0));
+    }
+
+    #[test]
+    fn task_actions() {
+        let mut app = app();
+        assert_eq!(
+            press(&mut app, KeyCode::Char('x')),
+            Some(Action::request(KillRequest {
+                tasks: TaskSelection::TaskIds(vec![0]),
+                signal: None,
+            }))
+        );
+        assert_eq!(
+            press(&mut app, KeyCode::Char('+')),
+            Some(Action::Priority(0, 1))
+        );
+
+        // Unfinished tasks cannot be restarted.
+        assert_eq!(press(&mut app, KeyCode::Char('r')), None);
+        assert!(app.message.as_ref().is_some_and(|(_, error)| *error));
+    }
+
+    #[test]
+    fn switch_marked_task() {
+        let mut app = app();
+        assert_eq!(press(&mut app, KeyCode::Char('w')), None);
+        assert_eq!(app.marked_task, Some(0));
+
+        press(&mut app, KeyCode::Down);
+        assert_eq!(
+            press(&mut app, KeyCode::Char('w')),
+            Some(Action::request(SwitchRequest {
+                task_id_1: 0,
+                task_id_2: 1,
+            }))
+        );
+        assert_eq!(app.marked_task, None);
+    }
+}
@@ -0,0 +1,305 @@
+use std::io::Write;
+
+use comfy_table::{
+    Attribute as ComfyAttribute, Cell, ContentArrangement, Table, presets::UTF8_HORIZONTAL_ONLY,
+};
+use crossterm::{
+    cursor::MoveTo,
+    queue,
+    style::{Attribute, Color, Print},
+    terminal::{Clear, ClearType},
+};
+use pueue_lib::{
+    settings::Settings,
+    state::{GroupStatus, State},
+    task::{Task, TaskStatus},
+};
+
+use unicode_width::UnicodeWidthChar;
+
+use super::app::App;
+use crate::{
+    client::{
+        commands::state::TableBuilder, display_helper::status_text_and_color, style::OutputStyle,
+    },
+    internal_prelude::*,
+};
+
+/// The top border, the header and the header separator of a table.
+/// Each row then takes two lines, as rows are separated by a line as well.
+const TABLE_HEADER_LINES: usize = 3;
+
+const HELP: &str = "Keybindings:
+
+  Up/k, Down/j        Select the previous/next task
+  g, G                Select the first/last task
+  Left/h, Right/l     Select the previous/next group (also Tab/Shift-Tab)
+
+  s, p                Start/pause the selected task
+  S, P                Start/pause the selected group
+  x                   Kill the selected task
+  r                   Restart the selected task, if it's finished
+  z, e                Stash/enqueue the selected task
+  E                   Edit the selected task in your $EDITOR
+  +, -                Increase/decrease the priority of the selected task
+  w                   Mark the selected task, press again on another task to switch them
+
+  ?                   Show this help, any key closes it
+  q, Esc              Quit";
+
+const HINT: &str = "q quit | ? help | s start | p pause | x kill | r restart | z stash | e \
+                    enqueue | E edit | +/- priority | w switch";
+
+/// Write the lines of a rendered frame to the terminal.
+pub fn draw(out: &mut impl Write, lines: &[String]) -> Result<()> {
+    for (row, line) in lines.iter().enumerate() {
+        queue!(
+            out,
+            MoveTo(0, row as u16),
+            Print(line),
+            Clear(ClearType::UntilNewLine)
+        )?;
+    }
+    queue!(out, Clear(ClearType::FromCursorDown))?;
+    out.flush()?;
+
+    Ok(())
+}
+
+/// Render the whole interface into exactly `height` lines.
+///
+/// From top to bottom, it shows the groups, the tasks of the selected group, the output of the
+/// selected task and a status line.
+pub fn render(
+    app: &App,
+    settings: &Settings,
+    style: &OutputStyle,
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    if let Some(state) = &app.state {
+        lines.extend(group_table(app, state, style, width));
+
+        if app.show_help {
+            lines.push(String::new());
+            lines.extend(HELP.lines().map(|line| fit(line, width)));
+        } else {
+            // Split the remaining space between the task table and the log pane.
+            let remaining = height.saturating_sub(lines.len() + 1);
+            let log_height = remaining * 2 / 5;
+            let table_height = remaining - log_height;
+            lines.extend(task_table(app, settings, style, width, table_height));
+            lines.extend(log_pane(app, style, width, log_height));
+        }
+    } else {
+        lines.push("Connecting to the daemon...".to_string());
+    }
+
+    // The status line is always shown in the last line.
+    lines.truncate(height.saturating_sub(1));
+    lines.resize(height.saturating_sub(1), String::new());
+    lines.push(status_line(app, style, width));
+
+    lines
+}
+
+/// A table with all groups, their status and the amount of tasks per status.
+fn group_table(app: &App, state: &State, style: &OutputStyle, width: usize) -> Vec<String> {
+    let mut table = Table::new();
+    table
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .load_preset(UTF8_HORIZONTAL_ONLY)
+        .set_width(width as u16)
+        .set_header(
+            [
+                "Group", "Status", "Parallel", "Running", "Queued", "Stashed", "Done",
+            ]
+            .map(|name| style.styled_cell(name, None, Some(ComfyAttribute::Bold))),
+        );
+
+    for (name, group) in &state.groups {
+        let tasks: Vec<&Task> = state
+            .tasks
+            .values()
+            .filter(|task| &task.group == name)
+            .collect();
+        let count = |condition: fn(&TaskStatus) -> bool| {
+            tasks.iter().filter(|task| condition(&task.status)).count()
+        };
+
+        let (status, color) = match group.status {
+            GroupStatus::Running => ("running", Color::Green),
+            GroupStatus::Paused => ("paused", Color::Yellow),
+            GroupStatus::Reset => ("resetting", Color::Red),
+        };
+        let mut cells = vec![
+            Cell::new(name),
+            style.styled_cell(status, Some(color), None),
+            Cell::new(group.parallel_tasks),
+            Cell::new(count(|status| {
+                matches!(
+                    status,
+                    TaskStatus::Running { .. } | TaskStatus::Paused { .. }
+                )
+            })),
+            Cell::new(count(|status| matches!(status, TaskStatus::Queued { .. }))),
+            Cell::new(count(|status| matches!(status, TaskStatus::Stashed { .. }))),
+            Cell::new(count(|status| matches!(status, TaskStatus::Done { .. }))),
+        ];
+        if app.group.as_ref() == Some(name) {
+            cells = cells
+                .into_iter()
+                .map(|cell| cell.add_attribute(ComfyAttribute::Reverse))
+                .collect();
+        }
+        table.add_row(cells);
+    }
+
+    if style.enabled {
+        table.enforce_styling();
+    }
+
+    table.lines().collect()
+}
+
+/// The tasks of the selected group with the selected task highlighted.
+///
+/// If there are more tasks than lines, only the tasks around the selected task are shown.
+fn task_table(
+    app: &App,
+    settings: &Settings,
+    style: &OutputStyle,
+    width: usize,
+    height: usize,
+) -> Vec<String> {
+    let group = app.group.clone().unwrap_or_default();
+    let tasks = app.tasks();
+    if tasks.is_empty() {
+        return vec![
+            String::new(),
+            fit(
+                &format!("Task list is empty. Add tasks with `pueue add -g {group} -- [cmd]`"),
+                width,
+            ),
+        ];
+    }
+
+    // Each task only gets a single line, so the table fits onto the screen.
+    let mut settings = settings.clone();
+    settings.client.max_status_lines = Some(1);
+
+    let visible = (height.saturating_sub(TABLE_HEADER_LINES) / 2).max(1);
+    let position = tasks
+        .iter()
+        .position(|task| Some(task.id) == app.task_id)
+        .unwrap_or(0);
+    let start = (position + 1).saturating_sub(visible);
+    let tasks: Vec<Task> = tasks
+        .into_iter()
+        .skip(start)
+        .take(visible)
+        .cloned()
+        .collect();
+
+    let mut table_builder = TableBuilder::new(&settings, style);
+    if let Some(task_id) = app.task_id {
+        table_builder.highlight_task(task_id);
+    }
+    let mut table = table_builder.build(&tasks);
+    table.set_width(width as u16);
+
+    table.lines().take(height).collect()
+}
+
+/// The last lines of the selected task's output.
+fn log_pane(app: &App, style: &OutputStyle, width: usize, height: usize) -> Vec<String> {
+    let Some(task) = app.selected_task() else {
+        return Vec::new();
+    };
+
+    let (status, color) = status_text_and_color(&task.status);
+    let title = fit(&format!("Output of task {}", task.id), width);
+    let mut lines = vec![format!(
+        "{} ({})",
+        style.style_text(title, None, Some(Attribute::Bold)),
+        style.style_text(status, Some(color), None),
+    )];
+
+    let available = height.saturating_sub(1);
+    let skip = app.log.len().saturating_sub(available);
+    lines.extend(app.log.iter().skip(skip).map(|line| fit(line, width)));
+
+    lines
+}
+
+/// The last message of the daemon or a short hint on the available keybindings.
+fn status_line(app: &App, style: &OutputStyle, width: usize) -> String {
+    match &app.message {
+        Some((message, error)) => {
+            let color = if *error { Color::Red } else { Color::Green };
+            // Only the first line of multi-line messages fits into the status line.
+            let message = message.lines().next().unwrap_or_default();
+            style.style_text(fit(message, width), Some(color), None)
+        }
+        None => {
+            let hint = match app.marked_task {
+                Some(task_id) => format!("Task {task_id} is marked for switching | {HINT}"),
+                None => HINT.to_string(),
+            };
+            style.style_text(fit(&hint, width), None, Some(Attribute::Dim))
+        }
+    }
+}
+
+/// Make a line of arbitrary output fit into a single terminal line.
+///
+/// ANSI escape sequences and control characters are removed, as they would break the layout.
+/// The line is truncated by its display width, as some characters take up two columns.
+fn fit(line: &str, width: usize) -> String {
+    let mut output = String::new();
+    let mut output_width = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(char) = chars.next() {
+        if char == '\x1b' {
+            // Skip CSI sequences up to and including their final byte.
+            if chars.next_if_eq(&'[').is_some() {
+                for char in chars.by_ref() {
+                    if ('@'..='~').contains(&char) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+
+        let (text, text_width) = match char {
+            '\t' => ("    ".to_string(), 4),
+            char if char.is_control() => continue,
+            char => (char.to_string(), char.width().unwrap_or(0)),
+        };
+        if output_width + text_width > width {
+            break;
+        }
+        output.push_str(&text);
+        output_width += text_width;
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn fit_by_display_width() {
+        assert_eq!(fit("\x1b[31mred\x1b[0m text", 6), "red te");
+        // Wide characters take up two columns and are never split.
+        assert_eq!(fit("日本語", 5), "日本");
+        assert_eq!(fit("a\tb", 4), "a");
+    }
+}
@@ -0,0 +1,283 @@
+//! A full-screen interface, which shows all groups, the tasks of the selected group and the output
+//! of the selected task. The view is refreshed periodically and tasks can be manipulated via
+//! keybindings.
+use std::{
+    io::{IsTerminal, Read, stdout},
+    time::{Duration, Instant},
+};
+
+use crossterm::{
+    cursor::{Hide, Show},
+    event::{self, Event, KeyEventKind},
+    execute,
+    terminal::{
+        EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode, size,
+    },
+};
+use pueue_lib::{
+    Client,
+    log::{get_log_file_handle, seek_to_last_lines},
+    message::*,
+    settings::Settings,
+};
+use snap::read::FrameDecoder;
+
+use super::{edit::edit_tasks, get_state};
+use crate::{client::style::OutputStyle, internal_prelude::*};
+
+mod app;
+mod draw;
+
+use app::{Action, App};
+pub(super) use draw::draw;
+use draw::render;
+
+/// How often the state of the daemon is requested.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Run the interactive interface until the user quits.
+pub async fn tui(client: &mut Client, settings: Settings) -> Result<()> {
+    if !stdout().is_terminal() {
+        bail!("The interactive interface can only be used in a terminal.");
+    }
+
+    // The interface is always styled, as it's running in a terminal anyway.
+    let style = OutputStyle::new(&settings, true);
+    let terminal = Terminal::enter()?;
+    let mut app = App::default();
+    let mut last_refresh: Option<Instant> = None;
+
+    loop {
+        let (width, height) = size()?;
+        if last_refresh.is_none_or(|refresh| refresh.elapsed() >= REFRESH_INTERVAL) {
+            refresh(client, &settings, &mut app, height as usize).await?;
+            last_refresh = Some(Instant::now());
+        }
+
+        let lines = render(&app, &settings, &style, width as usize, height as usize);
+        draw(&mut stdout(), &lines)?;
+
+        // Wait for user input until the next refresh is due.
+        let timeout = REFRESH_INTERVAL
+            .saturating_sub(last_refresh.map_or(REFRESH_INTERVAL, |refresh| refresh.elapsed()));
+        let key = match next_event(timeout).await? {
+            Some(Event::Key(key)) if key.kind == KeyEventKind::Press => key,
+            _ => continue,
+        };
+
+        let previous_task = app.task_id;
+        let action = app.handle_key(key);
+        match action {
+            None => {
+                // Show the output of a newly selected task right away.
+                if app.task_id != previous_task {
+                    last_refresh = None;
+                }
+                continue;
+            }
+            Some(Action::Quit) => break,
+            Some(Action::Request(request)) => {
+                client.send_request(*request).await?;
+                let response = client.receive_response().await?;
+                show_response(&mut app, response);
+            }
+            Some(Action::Edit(task_id)) => {
+                edit(client, &settings, &terminal, &mut app, task_id).await?
+            }
+            Some(Action::Priority(task_id, change)) => {
+                change_priority(client, &mut app, task_id, change).await?
+            }
+        }
+
+        // Show the effect of the action right away.
+        last_refresh = None;
+    }
+
+    Ok(())
+}
+
+/// Wait up to `timeout` for the next terminal event.
+///
+/// Reading events blocks, so it's done on a blocking thread to not stall the async runtime.
+async fn next_event(timeout: Duration) -> Result<Option<Event>> {
+    let event = tokio::task::spawn_blocking(move || -> Result<Option<Event>> {
+        if !event::poll(timeout)? {
+            return Ok(None);
+        }
+        Ok(Some(event::read()?))
+    })
+    .await
+    .context("Failed to wait for terminal events")??;
+
+    Ok(event)
+}
+
+/// Request the latest state and the output of the selected task.
+async fn refresh(
+    client: &mut Client,
+    settings: &Settings,
+    app: &mut App,
+    lines: usize,
+) -> Result<()> {
+    let state = get_state(client).await?;
+    app.update_state(state);
+
+    app.log = match app.task_id {
+        Some(task_id) => read_log(client, settings, task_id, lines).await?,
+        None => Vec::new(),
+    };
+
+    Ok(())
+}
+
+/// Get the last lines of a task's output.
+/// Just like `pueue log`, the log file is read directly, if `client.read_local_logs` is set.
+async fn read_log(
+    client: &mut Client,
+    settings: &Settings,
+    task_id: usize,
+    lines: usize,
+) -> Result<Vec<String>> {
+    let mut output = Vec::new();
+    if settings.client.read_local_logs {
+        // Tasks that never ran don't have a log file.
+        if let Ok(mut file) = get_log_file_handle(task_id, &settings.shared.pueue_directory()) {
+            seek_to_last_lines(&mut file, lines)?;
+            file.read_to_end(&mut output)?;
+        }
+    } else {
+        client
+            .send_request(LogRequest {
+                tasks: TaskSelection::TaskIds(vec![task_id]),
+                send_logs: true,
+                lines: Some(lines),
+            })
+            .await?;
+        if let Response::Log(mut logs) = client.receive_response().await? {
+            if let Some(bytes) = logs.remove(&task_id).and_then(|log| log.output) {
+                FrameDecoder::new(bytes.as_slice()).read_to_end(&mut output)?;
+            }
+        }
+    }
+
+    // Progress bars and the like overwrite their line via carriage returns.
+    // Only the latest content of each line is shown.
+    let output = String::from_utf8_lossy(&output);
+    Ok(output
+        .lines()
+        .map(|line| line.rsplit('\r').next().unwrap_or_default().to_string())
+        .collect())
+}
+
+/// Open the selected task in the user's editor.
+///
+/// The interface is suspended while editing, so the editor can take over the terminal.
+async fn edit(
+    client: &mut Client,
+    settings: &Settings,
+    terminal: &Terminal,
+    app: &mut App,
+    task_id: usize,
+) -> Result<()> {
+    // This locks the task on the daemon side until editing is finished.
+    client
+        .send_request(Request::EditRequest(vec![task_id]))
+        .await?;
+    let response = client.receive_response().await?;
+    let Response::Edit(editable_tasks) = response else {
+        show_response(app, response);
+        return Ok(());
+    };
+
+    terminal.suspend()?;
+    let result = edit_tasks(settings, editable_tasks);
+    terminal.resume()?;
+
+    match result {
+        Ok(editable_tasks) => {
+            client
+                .send_request(Request::EditedTasks(editable_tasks))
+                .await?;
+            let response = client.receive_response().await?;
+            show_response(app, response);
+        }
+        Err(err) => {
+            // Make sure the task isn't locked forever.
+            client
+                .send_request(Request::EditRestore(vec![task_id]))
+                .await?;
+            client.receive_response().await?;
+            app.set_message(format!("Failed to edit task {task_id}: {err}"), true);
+        }
+    }
+
+    Ok(())
+}
+
+/// Change the priority of a task via the same requests that are used for editing.
+async fn change_priority(
+    client: &mut Client,
+    app: &mut App,
+    task_id: usize,
+    change: i32,
+) -> Result<()> {
+    client
+        .send_request(Request::EditRequest(vec![task_id]))
+        .await?;
+    let response = client.receive_response().await?;
+    let Response::Edit(mut editable_tasks) = response else {
+        show_response(app, response);
+        return Ok(());
+    };
+
+    for task in editable_tasks.iter_mut() {
+        task.priority = task.priority.saturating_add(change);
+    }
+    client
+        .send_request(Request::EditedTasks(editable_tasks))
+        .await?;
+    let response = client.receive_response().await?;
+    show_response(app, response);
+
+    Ok(())
+}
+
+/// Show the daemon's response in the status line.
+fn show_response(app: &mut App, response: Response) {
+    match response {
+        Response::Success(text) => app.set_message(text, false),
+        Response::Failure(text) => app.set_message(text, true),
+        _ => app.set_message("Received an unexpected response from the daemon", true),
+    }
+}
+
+/// Puts the terminal into raw mode on an alternate screen and restores it once it's dropped.
+struct Terminal;
+
+impl Terminal {
+    fn enter() -> Result<Self> {
+        let terminal = Terminal;
+        terminal.resume()?;
+        Ok(terminal)
+    }
+
+    fn resume(&self) -> Result<()> {
+        enable_raw_mode()?;
+        execute!(stdout(), EnterAlternateScreen, Hide)?;
+        Ok(())
+    }
+
+    fn suspend(&self) -> Result<()> {
+        execute!(stdout(), LeaveAlternateScreen, Show)?;
+        disable_raw_mode()?;
+        Ok(())
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if let Err(err) = self.suspend() {
+            eprintln!("Failed to restore the terminal: {err}");
+        }
+    }
+}
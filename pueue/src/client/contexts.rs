@@ -0,0 +1,302 @@
+//! Named contexts, which store the connection details of different daemons.
+//!
+//! The contexts are stored in their own file (`client.contexts_file`), so the configuration file
+//! is never rewritten by the client.
+use std::{
+    collections::BTreeMap,
+    fs::{File, create_dir_all},
+    io::{Read, Write},
+    path::PathBuf,
+};
+
+use crossterm::style::Attribute;
+use pueue_lib::{
+    Client,
+    network::socket::ConnectionSettings,
+    secret::read_shared_secret,
+    settings::{Settings, expand_home},
+};
+use serde::{Deserialize, Serialize};
+
+use super::{cli::ContextCommand, style::OutputStyle};
+use crate::internal_prelude::*;
+
+/// The connection details of a single daemon.
+/// Everything that isn't set is taken from the configuration file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Context {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub port: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daemon_cert: Option<PathBuf>,
+    /// If this is set, the daemon is reached via this unix socket instead of TCP.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unix_socket_path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shared_secret_path: Option<PathBuf>,
+}
+
+impl Context {
+    /// Overwrite the connection details of the settings with the ones of this context.
+    pub fn apply(&self, settings: &mut Settings) {
+        let shared = &mut settings.shared;
+        // Unix sockets don't exist on Windows, which is why `context add` rejects them there.
+        #[cfg(not(target_os = "windows"))]
+        if let Some(path) = &self.unix_socket_path {
+            shared.use_unix_socket = true;
+            shared.unix_socket_path = Some(path.clone());
+        } else if self.host.is_some() || self.port.is_some() {
+            shared.use_unix_socket = false;
+        }
+
+        if let Some(host) = &self.host {
+            shared.host = host.clone();
+        }
+        if let Some(port) = &self.port {
+            shared.port = port.clone();
+        }
+        if let Some(cert) = &self.daemon_cert {
+            shared.daemon_cert = Some(cert.clone());
+        }
+        if let Some(secret) = &self.shared_secret_path {
+            shared.shared_secret_path = Some(secret.clone());
+        }
+    }
+
+    /// A short description of where the daemon is reached.
+    fn address(&self) -> String {
+        if let Some(path) = &self.unix_socket_path {
+            return format!("unix:{}", path.to_string_lossy());
+        }
+
+        match (&self.host, &self.port) {
+            (Some(host), Some(port)) => format!("{host}:{port}"),
+            (Some(host), None) => host.clone(),
+            (None, Some(port)) => format!(":{port}"),
+            (None, None) => "(configuration file)".to_string(),
+        }
+    }
+}
+
+/// The content of the contexts file.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Contexts {
+    /// The context that's used, if none is given via `--context`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<String>,
+    #[serde(default)]
+    pub contexts: BTreeMap<String, Context>,
+}
+
+impl Contexts {
+    /// Read the contexts file. If it doesn't exist, there aren't any contexts yet.
+    pub fn read(settings: &Settings) -> Result<Self> {
+        let path = settings.client.contexts_file();
+        if !path.exists() {
+            return Ok(Contexts::default());
+        }
+
+        let mut content = String::new();
+        File::open(&path)
+            .and_then(|mut file| file.read_to_string(&mut content))
+            .context(format!("Failed to read contexts file at {path:?}"))?;
+
+        serde_yaml::from_str(&content).context(format!("Failed to parse contexts file at {path:?}"))
+    }
+
+    pub fn save(&self, settings: &Settings) -> Result<()> {
+        let path = settings.client.contexts_file();
+        if let Some(directory) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            create_dir_all(directory)
+                .context(format!("Failed to create directory {directory:?}"))?;
+        }
+
+        let content = serde_yaml::to_string(self)?;
+        File::create(&path)
+            .and_then(|mut file| file.write_all(content.as_bytes()))
+            .context(format!("Failed to write contexts file at {path:?}"))?;
+
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Context> {
+        let Some(context) = self.contexts.get(name) else {
+            bail!("There's no context with the name '{name}'. Add it via `pueue context add`.");
+        };
+
+        Ok(context)
+    }
+}
+
+/// Apply the given context or, if none is given, the current context to the settings.
+pub fn apply_context(settings: &mut Settings, name: Option<&str>) -> Result<()> {
+    let contexts = Contexts::read(settings)?;
+    let Some(name) = name.or(contexts.current.as_deref()) else {
+        return Ok(());
+    };
+
+    contexts.get(name)?.apply(settings);
+
+    Ok(())
+}
+
+/// Connect to the daemon that's configured in the settings.
+pub async fn connect(settings: &Settings, show_version_warning: bool) -> Result<Client> {
+    let connection_settings = ConnectionSettings::try_from(settings.shared.clone())?;
+    let secret = read_shared_secret(&settings.shared.shared_secret_path())?;
+    let client = Client::new(connection_settings, &secret, show_version_warning)
+        .await
+        .context("Failed to initialize client.")?;
+
+    Ok(client)
+}
+
+/// Handle the `pueue context` subcommands.
+/// These only touch the contexts file and don't need a connection to a daemon.
+pub fn context_command(
+    settings: &Settings,
+    style: &OutputStyle,
+    cmd: Option<ContextCommand>,
+) -> Result<()> {
+    let mut contexts = Contexts::read(settings)?;
+
+    match cmd.unwrap_or(ContextCommand::List) {
+        ContextCommand::Add {
+            name,
+            host,
+            port,
+            cert,
+            unix_socket,
+            secret,
+            use_context,
+        } => {
+            #[cfg(target_os = "windows")]
+            if unix_socket.is_some() {
+                bail!("Unix sockets aren't supported on Windows.");
+            }
+
+            // Relative paths would otherwise depend on the directory the client is called from.
+            let context = Context {
+                host,
+                port,
+                daemon_cert: absolute_path(cert)?,
+                unix_socket_path: absolute_path(unix_socket)?,
+                shared_secret_path: absolute_path(secret)?,
+            };
+            let address = context.address();
+            let replaced = contexts.contexts.insert(name.clone(), context).is_some();
+            if use_context {
+                contexts.current = Some(name.clone());
+            }
+            contexts.save(settings)?;
+
+            let action = if replaced { "Updated" } else { "Added" };
+            println!("{action} context '{name}' ({address}).");
+        }
+        ContextCommand::Use { name } => {
+            match &name {
+                Some(name) => {
+                    contexts.get(name)?;
+                    println!("Using context '{name}'.");
+                }
+                None => println!("Using the connection of the configuration file."),
+            }
+            contexts.current = name;
+            contexts.save(settings)?;
+        }
+        ContextCommand::List => print_contexts(&contexts, style),
+        ContextCommand::Remove { name } => {
+            if contexts.contexts.remove(&name).is_none() {
+                bail!("There's no context with the name '{name}'.");
+            }
+            if contexts.current.as_ref() == Some(&name) {
+                contexts.current = None;
+            }
+            contexts.save(settings)?;
+
+            println!("Removed context '{name}'.");
+        }
+    }
+
+    Ok(())
+}
+
+/// Make a path absolute, relative to the current working directory.
+fn absolute_path(path: Option<PathBuf>) -> Result<Option<PathBuf>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    let absolute = std::path::absolute(expand_home(&path))
+        .context(format!("Failed to determine the absolute path of {path:?}"))?;
+
+    Ok(Some(absolute))
+}
+
+fn print_contexts(contexts: &Contexts, style: &OutputStyle) {
+    if contexts.contexts.is_empty() {
+        println!("No contexts defined. Add one via `pueue context add`.");
+        return;
+    }
+
+    let width = contexts.contexts.keys().map(String::len).max().unwrap_or(0);
+    for (name, context) in &contexts.contexts {
+        let current = contexts.current.as_ref() == Some(name);
+        let marker = if current { "*" } else { " " };
+        let padded = format!("{name:width$}");
+        let name = if current {
+            style.style_text(padded, None, Some(Attribute::Bold))
+        } else {
+            padded
+        };
+        println!("{marker} {name}  {}", context.address());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn apply() {
+        let mut settings = Settings::default();
+        let context = Context {
+            host: Some("10.0.0.2".to_string()),
+            port: Some("6925".to_string()),
+            ..Default::default()
+        };
+        context.apply(&mut settings);
+        assert_eq!(settings.shared.host, "10.0.0.2");
+        assert_eq!(settings.shared.port, "6925");
+    }
+
+    #[test]
+    #[cfg(not(target_os = "windows"))]
+    fn apply_unix_socket() {
+        let mut settings = Settings::default();
+        settings.shared.use_unix_socket = true;
+
+        // Contexts with a host or port switch to TCP.
+        let context = Context {
+            host: Some("10.0.0.2".to_string()),
+            ..Default::default()
+        };
+        context.apply(&mut settings);
+        assert!(!settings.shared.use_unix_socket);
+
+        let context = Context {
+            unix_socket_path: Some(PathBuf::from("/tmp/pueue.socket")),
+            ..Default::default()
+        };
+        context.apply(&mut settings);
+        assert!(settings.shared.use_unix_socket);
+        assert_eq!(
+            settings.shared.unix_socket_path,
+            Some(PathBuf::from("/tmp/pueue.socket"))
+        );
+    }
+}
@@ -1,7 +1,10 @@
 //! This module contains small helper functions that're useful for printing or displaying structured
 //! information. Most of these functions are shared between multiple commands.
 use crossterm::style::{Attribute, Color};
-use pueue_lib::state::{Group, GroupStatus};
+use pueue_lib::{
+    state::{Group, GroupStatus},
+    task::{TaskResult, TaskStatus},
+};
 
 use super::style::OutputStyle;
 
@@ -31,3 +34,20 @@ pub fn get_group_headline(name: &str, group: &Group, style: &OutputStyle) -> Str
 
     format!("{} ({} parallel): {}", name, group.parallel_tasks, status)
 }
+
+/// Return the human readable representation of a task's status and the color it's displayed in.
+pub fn status_text_and_color(status: &TaskStatus) -> (String, Color) {
+    let status_string = status.to_string();
+    match status {
+        TaskStatus::Running { .. } => (status_string, Color::Green),
+        TaskStatus::Paused { .. } | TaskStatus::Locked { .. } => (status_string, Color::White),
+        TaskStatus::Done { result, .. } => match result {
+            TaskResult::Success => (TaskResult::Success.to_string(), Color::Green),
+            TaskResult::DependencyFailed => ("Dependency failed".to_string(), Color::Red),
+            TaskResult::FailedToSpawn(_) => ("Failed to spawn".to_string(), Color::Red),
+            TaskResult::Failed(code) => (format!("Failed ({code})"), Color::Red),
+            _ => (result.to_string(), Color::Red),
+        },
+        _ => (status_string, Color::Yellow),
+    }
+}
@@ -1,9 +1,15 @@
 pub mod cli;
 /// All subcommands have their dedicated file and functions in here.
 mod commands;
+/// Named contexts with the connection details of different daemons.
+pub mod contexts;
 pub(crate) mod display_helper;
+/// Machine-readable output of tasks via presets or handlebars templates.
+pub mod output_format;
 /// The [`OutputStyle`](style::OutputStyle) helper, responsible for formatting and styling output
 /// based on the current settings.
 pub mod style;
+/// Instantiation of task templates for `pueue add --template`.
+mod template;
 
-pub use commands::handle_command;
+pub use commands::{all_contexts_state, handle_command};
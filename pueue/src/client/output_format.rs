@@ -0,0 +1,308 @@
+//! Machine-readable output of tasks for `pueue status --format` and `pueue log --format`.
+//!
+//! Tasks are converted into flat [`TaskRecord`]s, which are independent of the internal structure
+//! of tasks, and rendered either via a preset or a handlebars template.
+use std::{collections::BTreeMap, str::FromStr};
+
+use chrono::Local;
+use handlebars::Handlebars;
+use pueue_lib::task::{Task, TaskResult, TaskStatus};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::internal_prelude::*;
+
+/// The fields that are included in the `csv` and `tsv` presets, in this order.
+/// Environment variables are left out, as they don't fit into a single column.
+const COLUMNS: &[&str] = &[
+    "id",
+    "status",
+    "result",
+    "exit_code",
+    "group",
+    "priority",
+    "label",
+    "command",
+    "path",
+    "dependencies",
+    "created_at",
+    "enqueued_at",
+    "start",
+    "end",
+    "duration",
+];
+
+/// How tasks should be printed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OutputFormat {
+    Csv,
+    Tsv,
+    Yaml,
+    /// A handlebars template that's rendered once per task.
+    Template(String),
+}
+
+impl FromStr for OutputFormat {
+    type Err = String;
+
+    /// Anything that isn't a preset has to be a template, i.e. it has to contain `{{`.
+    /// Otherwise, a typo in the name of a preset would silently be printed once per task.
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        let format = match format {
+            "csv" => OutputFormat::Csv,
+            "tsv" => OutputFormat::Tsv,
+            "yaml" => OutputFormat::Yaml,
+            template if template.contains("{{") => OutputFormat::Template(template.to_string()),
+            _ => {
+                return Err(format!(
+                    "'{format}' is neither one of the presets csv, tsv and yaml nor a template \
+                    containing fields such as '{{{{id}}}}'"
+                ));
+            }
+        };
+
+        Ok(format)
+    }
+}
+
+/// All fields of a task in a flat and stable representation.
+///
+/// Datetimes are formatted as RFC 3339 and durations are given in seconds.
+#[derive(Clone, Debug, Serialize)]
+pub struct TaskRecord {
+    pub id: usize,
+    /// `stashed`, `locked`, `queued`, `running`, `paused` or `done`.
+    pub status: String,
+    /// The result of finished tasks, e.g. `success`, `failed` or `killed`.
+    pub result: Option<String>,
+    pub exit_code: Option<i32>,
+    pub group: String,
+    pub priority: i32,
+    pub label: Option<String>,
+    pub command: String,
+    pub original_command: String,
+    pub path: String,
+    pub dependencies: Vec<usize>,
+    pub created_at: String,
+    /// When the task has been enqueued or, for stashed tasks, when it'll be enqueued.
+    pub enqueued_at: Option<String>,
+    pub start: Option<String>,
+    pub end: Option<String>,
+    pub duration: Option<i64>,
+    pub timeout: Option<u64>,
+    pub array: Option<String>,
+    pub envs: BTreeMap<String, String>,
+    /// The output of the task. Only set for `pueue log`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output: Option<String>,
+}
+
+impl From<&Task> for TaskRecord {
+    fn from(task: &Task) -> Self {
+        let (status, enqueued_at) = match &task.status {
+            TaskStatus::Stashed { enqueue_at } => ("stashed", *enqueue_at),
+            TaskStatus::Locked { .. } => ("locked", None),
+            TaskStatus::Queued { enqueued_at } => ("queued", Some(*enqueued_at)),
+            TaskStatus::Running { enqueued_at, .. } => ("running", Some(*enqueued_at)),
+            TaskStatus::Paused { enqueued_at, .. } => ("paused", Some(*enqueued_at)),
+            TaskStatus::Done { enqueued_at, .. } => ("done", Some(*enqueued_at)),
+        };
+
+        let (result, exit_code) = match &task.status {
+            TaskStatus::Done { result, .. } => match result {
+                TaskResult::Success => (Some("success"), Some(0)),
+                TaskResult::Failed(code) => (Some("failed"), Some(*code)),
+                TaskResult::FailedToSpawn(_) => (Some("failed_to_spawn"), None),
+                TaskResult::Killed => (Some("killed"), None),
+                TaskResult::Errored => (Some("errored"), None),
+                TaskResult::DependencyFailed => (Some("dependency_failed"), None),
+            },
+            _ => (None, None),
+        };
+
+        let (start, end) = task.start_and_end();
+        let duration = start.map(|start| (end.unwrap_or_else(Local::now) - start).num_seconds());
+
+        TaskRecord {
+            id: task.id,
+            status: status.to_string(),
+            result: result.map(ToString::to_string),
+            exit_code,
+            group: task.group.clone(),
+            priority: task.priority,
+            label: task.label.clone(),
+            command: task.command.clone(),
+            original_command: task.original_command.clone(),
+            path: task.path.to_string_lossy().to_string(),
+            dependencies: task.dependencies.clone(),
+            created_at: task.created_at.to_rfc3339(),
+            enqueued_at: enqueued_at.map(|datetime| datetime.to_rfc3339()),
+            start: start.map(|datetime| datetime.to_rfc3339()),
+            end: end.map(|datetime| datetime.to_rfc3339()),
+            duration,
+            timeout: task.timeout,
+            array: task.array.clone(),
+            envs: task.envs.clone().into_iter().collect(),
+            output: None,
+        }
+    }
+}
+
+/// Render a list of task records in the given format.
+pub fn format_records(format: &OutputFormat, records: &[TaskRecord]) -> Result<String> {
+    let output = match format {
+        OutputFormat::Yaml => serde_yaml::to_string(records)?,
+        OutputFormat::Csv => separated(records, ",", escape_csv)?,
+        OutputFormat::Tsv => separated(records, "\t", escape_tsv)?,
+        OutputFormat::Template(template) => {
+            let mut handlebars = Handlebars::new();
+            handlebars.register_escape_fn(handlebars::no_escape);
+            handlebars
+                .register_template_string("format", template)
+                .context("Failed to parse the format template")?;
+
+            let mut output = String::new();
+            for record in records {
+                let rendered = handlebars.render("format", record).context(format!(
+                    "Failed to render the format template for task {}",
+                    record.id
+                ))?;
+                output.push_str(&rendered);
+                output.push('\n');
+            }
+            output
+        }
+    };
+
+    Ok(output)
+}
+
+/// Print a header and one line per record with the values of all [`COLUMNS`].
+/// The output of tasks is added as the last column, if it's present.
+fn separated(
+    records: &[TaskRecord],
+    separator: &str,
+    escape: fn(&str) -> String,
+) -> Result<String> {
+    let with_output = records.iter().any(|record| record.output.is_some());
+    let mut columns = COLUMNS.to_vec();
+    if with_output {
+        columns.push("output");
+    }
+
+    let mut output = columns.join(separator);
+    output.push('\n');
+    for record in records {
+        let Value::Object(mut fields) = serde_json::to_value(record)? else {
+            bail!("Expected the task record to be serialized as an object");
+        };
+
+        let values: Vec<String> = columns
+            .iter()
+            .map(|column| escape(&plain_value(fields.remove(*column).unwrap_or_default())))
+            .collect();
+        output.push_str(&values.join(separator));
+        output.push('\n');
+    }
+
+    Ok(output)
+}
+
+/// Convert a JSON value into plain text. Lists are joined by commas and `null` is empty.
+fn plain_value(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text,
+        Value::Array(values) => values
+            .into_iter()
+            .map(plain_value)
+            .collect::<Vec<String>>()
+            .join(","),
+        value => value.to_string(),
+    }
+}
+
+/// Quote values that contain a separator, a quote or a line break, as described in RFC 4180.
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        return format!("\"{}\"", value.replace('"', "\"\""));
+    }
+
+    value.to_string()
+}
+
+/// Values may not contain tabs or line breaks, which are escaped with a backslash instead.
+fn escape_tsv(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use chrono::TimeZone;
+    use pretty_assertions::assert_eq;
+    use pueue_lib::state::PUEUE_DEFAULT_GROUP;
+
+    use super::*;
+
+    fn record() -> TaskRecord {
+        let mut task = Task::new(
+            "echo \"a, b\"".to_string(),
+            PathBuf::from("/tmp"),
+            Default::default(),
+            PUEUE_DEFAULT_GROUP.to_string(),
+            TaskStatus::Done {
+                enqueued_at: Local.with_ymd_and_hms(2022, 1, 10, 10, 0, 0).unwrap(),
+                start: Local.with_ymd_and_hms(2022, 1, 10, 10, 5, 0).unwrap(),
+                end: Local.with_ymd_and_hms(2022, 1, 10, 10, 10, 0).unwrap(),
+                result: TaskResult::Failed(2),
+            },
+            vec![3, 4],
+            0,
+            None,
+        );
+        task.id = 7;
+
+        TaskRecord::from(&task)
+    }
+
+    #[test]
+    fn template() -> Result<()> {
+        let format =
+            OutputFormat::from_str("{{id}} {{status}} {{result}} {{exit_code}} {{duration}}")
+                .map_err(|err| eyre!(err))?;
+        assert_eq!(
+            format_records(&format, &[record()])?,
+            "7 done failed 2 300\n"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn unknown_preset() {
+        assert_eq!(OutputFormat::from_str("json").ok(), None);
+        assert_eq!(OutputFormat::from_str("yaml"), Ok(OutputFormat::Yaml));
+    }
+
+    #[test]
+    fn csv() -> Result<()> {
+        let output = format_records(&OutputFormat::Csv, &[record()])?;
+        let mut lines = output.lines();
+        assert_eq!(lines.next(), Some(COLUMNS.join(",").as_str()));
+
+        let line = lines.next().unwrap();
+        assert!(
+            line.starts_with("7,done,failed,2,default,0,,\"echo \"\"a, b\"\"\",/tmp,\"3,4\","),
+            "Unexpected csv line: {line}"
+        );
+        assert!(line.ends_with(",300"), "Unexpected csv line: {line}");
+
+        Ok(())
+    }
+}
@@ -0,0 +1,179 @@
+//! Instantiation of the task templates from the `client.templates` setting.
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use handlebars::Handlebars;
+use pueue_lib::settings::Template;
+
+use crate::internal_prelude::*;
+
+/// A template, with all placeholders replaced by the actual parameters.
+#[derive(Debug, Default)]
+pub struct Instance {
+    pub command: String,
+    pub group: Option<String>,
+    pub priority: Option<i32>,
+    pub label: Option<String>,
+    pub env: BTreeMap<String, String>,
+    pub working_directory: Option<PathBuf>,
+    pub timeout: Option<u64>,
+}
+
+/// Fill a template with the given `KEY=VALUE` parameters.
+///
+/// Fails if a parameter without default is missing or if an unknown parameter is given.
+pub fn instantiate(name: &str, template: &Template, parameters: &[String]) -> Result<Instance> {
+    let mut values = template.defaults.clone();
+    let mut given = BTreeMap::new();
+    for parameter in parameters {
+        let Some((key, value)) = parameter.split_once('=') else {
+            bail!("Template parameters have to be in the form KEY=VALUE, got '{parameter}'");
+        };
+        given.insert(key.trim().to_string(), value.to_string());
+    }
+
+    let mut templated: Vec<&str> = vec![&template.command];
+    templated.extend(template.label.as_deref());
+    templated.extend(template.env.values().map(String::as_str));
+    let working_directory = template
+        .working_directory
+        .as_ref()
+        .map(|path| path.to_string_lossy().to_string());
+    templated.extend(working_directory.as_deref());
+
+    let placeholders: BTreeSet<String> = templated.into_iter().flat_map(placeholders).collect();
+
+    let unknown: Vec<&String> = given
+        .keys()
+        .filter(|key| !placeholders.contains(*key) && !values.contains_key(*key))
+        .collect();
+    if !unknown.is_empty() {
+        bail!("Template '{name}' doesn't have the parameter(s) {unknown:?}");
+    }
+
+    values.extend(given);
+    let missing: Vec<&String> = placeholders
+        .iter()
+        .filter(|placeholder| !values.contains_key(*placeholder))
+        .collect();
+    if !missing.is_empty() {
+        bail!("Template '{name}' requires the parameter(s) {missing:?}");
+    }
+
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_escape_fn(handlebars::no_escape);
+    let render = |text: &str| {
+        handlebars
+            .render_template(text, &values)
+            .with_context(|| format!("Failed to render template '{name}'"))
+    };
+
+    let mut env = BTreeMap::new();
+    for (key, value) in &template.env {
+        env.insert(key.clone(), render(value)?);
+    }
+
+    Ok(Instance {
+        command: render(&template.command)?,
+        group: template.group.clone(),
+        priority: template.priority,
+        label: template.label.as_deref().map(render).transpose()?,
+        env,
+        working_directory: working_directory
+            .as_deref()
+            .map(render)
+            .transpose()?
+            .map(PathBuf::from),
+        timeout: template.timeout,
+    })
+}
+
+/// Get the names of all plain `{{name}}` placeholders in a text.
+/// Handlebars helpers and block expressions are ignored.
+fn placeholders(text: &str) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let after_start = &rest[start + 2..];
+        let Some(end) = after_start.find("}}") else {
+            break;
+        };
+
+        let name = after_start[..end].trim();
+        if !name.is_empty()
+            && name
+                .chars()
+                .all(|char| char.is_alphanumeric() || char == '_' || char == '-')
+        {
+            names.push(name.to_string());
+        }
+        rest = &after_start[end + 2..];
+    }
+
+    names
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn deploy_template() -> Template {
+        let mut template = Template {
+            command: "deploy --env {{env}} --region {{ region }}".into(),
+            label: Some("deploy {{env}}".into()),
+            timeout: Some(60),
+            ..Default::default()
+        };
+        template.defaults.insert("region".into(), "us".into());
+        template
+            .env
+            .insert("DEPLOY_TARGET".into(), "{{env}}-{{region}}".into());
+        template
+    }
+
+    #[test]
+    fn instantiate_with_defaults() -> Result<()> {
+        let instance = instantiate("deploy", &deploy_template(), &["env=prod".into()])?;
+
+        assert_eq!(instance.command, "deploy --env prod --region us");
+        assert_eq!(instance.label, Some("deploy prod".into()));
+        assert_eq!(instance.env["DEPLOY_TARGET"], "prod-us");
+        assert_eq!(instance.timeout, Some(60));
+
+        let instance = instantiate(
+            "deploy",
+            &deploy_template(),
+            &["env=prod".into(), "region=eu".into()],
+        )?;
+        assert_eq!(instance.command, "deploy --env prod --region eu");
+
+        Ok(())
+    }
+
+    #[test]
+    fn instantiate_invalid_parameters() {
+        let error = instantiate("deploy", &deploy_template(), &[]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Template 'deploy' requires the parameter(s) [\"env\"]"
+        );
+
+        let parameters = ["env=prod".into(), "zone=a".into()];
+        let error = instantiate("deploy", &deploy_template(), &parameters).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Template 'deploy' doesn't have the parameter(s) [\"zone\"]"
+        );
+
+        let error = instantiate("deploy", &deploy_template(), &["prod".into()]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Template parameters have to be in the form KEY=VALUE, got 'prod'"
+        );
+    }
+}
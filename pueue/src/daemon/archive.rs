@@ -0,0 +1,238 @@
+//! The archive keeps a record of finished tasks after they've been removed from the state.
+//!
+//! Tasks are appended to a JSONL file in the `pueue_directory`, one task per line.
+//! With the SQLite state backend, they're stored in the database's `history` table instead.
+//! The retention policy of the `daemon.archive` setting is applied whenever tasks are archived.
+use std::{
+    fs::{File, OpenOptions, rename},
+    io::{BufRead, BufReader, Write},
+    path::PathBuf,
+};
+
+use chrono::{DateTime, Local, TimeDelta};
+#[cfg(feature = "sqlite")]
+use pueue_lib::settings::StateBackend;
+use pueue_lib::{Settings, Task, TaskStatus};
+
+#[cfg(feature = "sqlite")]
+use crate::daemon::internal_state::sqlite;
+use crate::{daemon::internal_state::state::InternalState, internal_prelude::*};
+
+/// The location of the archive file.
+pub fn archive_path(settings: &Settings) -> PathBuf {
+    settings.shared.pueue_directory().join("archive.jsonl")
+}
+
+/// The point in time before which archived tasks are pruned.
+/// Returns `None`, if the maximum age is too large to be represented, in which case no task is
+/// old enough to be pruned.
+pub fn prune_before(max_age_days: u64) -> Option<DateTime<Local>> {
+    let max_age = TimeDelta::try_days(i64::try_from(max_age_days).ok()?)?;
+    Local::now().checked_sub_signed(max_age)
+}
+
+/// Identifies an archived task, as ids are reused once tasks have been removed.
+#[cfg(feature = "sqlite")]
+pub fn archive_key(task: &Task) -> (usize, Option<DateTime<Local>>) {
+    let end = match task.status {
+        TaskStatus::Done { end, .. } => Some(end),
+        _ => None,
+    };
+    (task.id, end)
+}
+
+/// Add finished tasks to the archive of the state's backend, if archiving is enabled.
+/// Unfinished tasks are ignored, as there's nothing to keep a record of.
+///
+/// Errors are only logged, as a broken archive shouldn't prevent tasks from being cleaned.
+/// Call this only after the state without the tasks has been saved, so a failed save doesn't
+/// leave tasks both in the state and in the archive.
+pub fn archive_tasks(settings: &Settings, state: &InternalState, tasks: &[Task]) {
+    if !settings.daemon.archive.enabled {
+        return;
+    }
+
+    let tasks: Vec<&Task> = tasks.iter().filter(|task| task.is_done()).collect();
+    if tasks.is_empty() {
+        return;
+    }
+
+    if let Err(err) = state.archive(settings, &tasks) {
+        error!("Failed to archive tasks: {err:?}");
+    }
+}
+
+/// Append tasks to the archive file.
+pub fn append(settings: &Settings, tasks: &[&Task]) -> Result<()> {
+    let path = archive_path(settings);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open archive at {path:?}"))?;
+
+    let mut lines = String::new();
+    for task in tasks {
+        lines.push_str(&serde_json::to_string(task)?);
+        lines.push('\n');
+    }
+    file.write_all(lines.as_bytes())?;
+
+    Ok(())
+}
+
+/// Read all tasks of the archive, from the oldest to the most recently archived one.
+pub fn read_archive(settings: &Settings) -> Result<Vec<Task>> {
+    #[cfg(feature = "sqlite")]
+    if settings.daemon.state_backend == StateBackend::Sqlite {
+        return sqlite::read_history(settings);
+    }
+
+    read_archive_file(settings)
+}
+
+/// Read all tasks of the archive file.
+pub fn read_archive_file(settings: &Settings) -> Result<Vec<Task>> {
+    let path = archive_path(settings);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let file = File::open(&path).with_context(|| format!("Failed to open archive at {path:?}"))?;
+    let mut tasks = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(task) => tasks.push(task),
+            Err(err) => warn!("Skipping broken line {} of the archive: {err}", number + 1),
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Remove all tasks that violate the retention policy.
+/// The archive is only rewritten, if any task has actually been removed.
+pub fn prune(settings: &Settings) -> Result<()> {
+    let policy = &settings.daemon.archive;
+    if policy.max_age_days.is_none() && policy.max_tasks.is_none() {
+        return Ok(());
+    }
+
+    let mut tasks = read_archive_file(settings)?;
+    let count = tasks.len();
+
+    if let Some(oldest) = policy.max_age_days.and_then(prune_before) {
+        tasks.retain(|task| match task.status {
+            TaskStatus::Done { end, .. } => end >= oldest,
+            _ => true,
+        });
+    }
+
+    if let Some(max_tasks) = policy.max_tasks {
+        let excess = tasks.len().saturating_sub(max_tasks);
+        tasks.drain(..excess);
+    }
+
+    if tasks.len() == count {
+        return Ok(());
+    }
+    debug!("Pruning {} tasks from the archive", count - tasks.len());
+
+    // Write to a temporary file first, so the archive cannot end up half-written.
+    let path = archive_path(settings);
+    let temp_path = path.with_extension("jsonl.partial");
+    let mut file = File::create(&temp_path)?;
+    for task in &tasks {
+        writeln!(file, "{}", serde_json::to_string(task)?)?;
+    }
+    file.sync_all()?;
+    rename(&temp_path, &path)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+    use pueue_lib::{TaskResult, state::PUEUE_DEFAULT_GROUP};
+    use tempfile::TempDir;
+
+    use super::*;
+
+    fn settings(tempdir: &TempDir) -> Settings {
+        let mut settings = Settings::default();
+        settings.shared.pueue_directory = Some(tempdir.path().to_path_buf());
+        settings.daemon.archive.enabled = true;
+        settings
+    }
+
+    fn done_task(command: &str, days_ago: i64) -> Task {
+        let end = Local::now() - TimeDelta::days(days_ago);
+        let status = TaskStatus::Done {
+            enqueued_at: end,
+            start: end,
+            end,
+            result: TaskResult::Success,
+        };
+        Task::new(
+            command.into(),
+            std::env::temp_dir(),
+            HashMap::new(),
+            PUEUE_DEFAULT_GROUP.into(),
+            status,
+            Vec::new(),
+            0,
+            None,
+        )
+    }
+
+    #[test]
+    fn archive_and_prune() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let mut settings = settings(&tempdir);
+        let state = InternalState::new();
+
+        archive_tasks(
+            &settings,
+            &state,
+            &[done_task("old", 10), done_task("new", 0)],
+        );
+        let commands: Vec<String> = read_archive(&settings)?
+            .into_iter()
+            .map(|task| task.command)
+            .collect();
+        assert_eq!(commands, vec!["old", "new"]);
+
+        // Tasks that are too old are pruned on the next write.
+        settings.daemon.archive.max_age_days = Some(5);
+        archive_tasks(&settings, &state, &[done_task("newer", 0)]);
+        let commands: Vec<String> = read_archive(&settings)?
+            .into_iter()
+            .map(|task| task.command)
+            .collect();
+        assert_eq!(commands, vec!["new", "newer"]);
+
+        // Only the most recently archived tasks are kept.
+        settings.daemon.archive.max_tasks = Some(2);
+        archive_tasks(&settings, &state, &[done_task("newest", 0)]);
+        let commands: Vec<String> = read_archive(&settings)?
+            .into_iter()
+            .map(|task| task.command)
+            .collect();
+        assert_eq!(commands, vec!["newer", "newest"]);
+
+        // Huge maximum ages don't overflow, but simply keep all tasks.
+        settings.daemon.archive.max_age_days = Some(u64::MAX);
+        settings.daemon.archive.max_tasks = None;
+        archive_tasks(&settings, &state, &[done_task("last", 0)]);
+        assert_eq!(read_archive(&settings)?.len(), 3);
+
+        Ok(())
+    }
+}
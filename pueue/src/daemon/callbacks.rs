@@ -1,48 +1,292 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    process::Command,
+};
 
-use chrono::{DateTime, Local};
+use chrono::{DateTime, Local, TimeDelta};
+use command_group::{CommandGroup, GroupChild};
 use handlebars::{Handlebars, RenderError};
 use pueue_lib::{
     Settings, Task, TaskResult, TaskStatus,
     log::{get_log_path, read_last_log_file_lines},
+    message::CallbackRun,
+    state::Hooks,
 };
 
 use crate::{
-    daemon::internal_state::state::LockedState, internal_prelude::*,
+    daemon::{internal_state::state::LockedState, webhook::notify_webhooks},
+    internal_prelude::*,
     process_helper::compile_shell_command,
 };
 
+/// The amount of finished callback runs that are remembered.
+const MAX_CALLBACK_RUNS: usize = 100;
+
+/// The amount of bytes of a callback's output that are remembered.
+const MAX_CALLBACK_OUTPUT: usize = 16 * 1024;
+
+/// A callback or hook process that's still running.
+#[derive(Debug)]
+pub struct RunningCallback {
+    child: GroupChild,
+    task_id: Option<usize>,
+    trigger: String,
+    command: String,
+    start: DateTime<Local>,
+    /// The file to which stdout and stderr of the process are written.
+    output: Option<File>,
+}
+
+/// The events on which user defined hooks are executed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HookEvent {
+    Start,
+    Success,
+    Failure,
+    GroupEmpty,
+    DaemonStart,
+}
+
+impl HookEvent {
+    /// The name of the hook in the configuration file.
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::Start => "on_start",
+            HookEvent::Success => "on_success",
+            HookEvent::Failure => "on_failure",
+            HookEvent::GroupEmpty => "on_group_empty",
+            HookEvent::DaemonStart => "on_daemon_start",
+        }
+    }
+
+    /// Get the template string for this event from a set of hooks.
+    fn template(self, hooks: &Hooks) -> Option<&String> {
+        match self {
+            HookEvent::Start => hooks.on_start.as_ref(),
+            HookEvent::Success => hooks.on_success.as_ref(),
+            HookEvent::Failure => hooks.on_failure.as_ref(),
+            HookEvent::GroupEmpty => hooks.on_group_empty.as_ref(),
+            HookEvent::DaemonStart => hooks.on_daemon_start.as_ref(),
+        }
+    }
+}
+
 /// Users can specify a callback that's fired whenever a task finishes.
 /// The callback is performed by spawning a new subprocess.
+///
+/// On top of the callback, the `on_success`/`on_failure` hooks are executed and the
+/// `on_group_empty` hook, if this has been the last active task of the group.
+/// Webhooks are notified as well.
 pub fn spawn_callback(settings: &Settings, state: &mut LockedState, task: &Task) {
-    // Return early, if there's no callback specified
-    let Some(template_string) = &settings.daemon.callback else {
+    notify_webhooks(settings, state, task);
+
+    // Groups may specify their own callback, which replaces the global one.
+    let group_callback = state
+        .groups()
+        .get(&task.group)
+        .and_then(|group| group.callback.clone());
+    if let Some(template_string) = group_callback.or_else(|| settings.daemon.callback.clone()) {
+        spawn_task_command(settings, state, task, &template_string, "callback");
+    }
+
+    let event = if task.failed() {
+        HookEvent::Failure
+    } else {
+        HookEvent::Success
+    };
+    spawn_task_hook(settings, state, task, event);
+
+    if group_is_empty(state, &task.group) {
+        spawn_group_hook(settings, state, &task.group, HookEvent::GroupEmpty);
+    }
+}
+
+/// Execute the hook of a task specific event, i.e. `on_start`, `on_success` or `on_failure`.
+/// The hook has access to the same variables as the callback.
+pub fn spawn_task_hook(
+    settings: &Settings,
+    state: &mut LockedState,
+    task: &Task,
+    event: HookEvent,
+) {
+    let Some(template_string) = get_hook(settings, state, &task.group, event) else {
+        return;
+    };
+
+    spawn_task_command(settings, state, task, &template_string, event.name());
+}
+
+/// Execute the hook of a group specific event, i.e. `on_group_empty` or `on_daemon_start`.
+/// These hooks only have access to the `group`, `queued_count` and `stashed_count` variables.
+pub fn spawn_group_hook(
+    settings: &Settings,
+    state: &mut LockedState,
+    group: &str,
+    event: HookEvent,
+) {
+    let Some(template_string) = get_hook(settings, state, group, event) else {
         return;
     };
 
+    let mut parameters = HashMap::new();
+    insert_group_parameters(state, group, &mut parameters);
+
+    match render_hook(&template_string, &parameters) {
+        Ok(command) => spawn_command(settings, state, &command, None, event.name()),
+        Err(err) => error!("Failed to create {event:?} hook for group {group}: {err}"),
+    }
+}
+
+/// Execute the `on_daemon_start` hooks.
+///
+/// The global hook is executed once, while the hooks of groups are executed once for each group
+/// that specifies its own hook.
+pub fn spawn_daemon_start_hooks(settings: &Settings, state: &mut LockedState) {
+    if let Some(template_string) = &settings.daemon.hooks.on_daemon_start {
+        match render_hook(template_string, &HashMap::new()) {
+            Ok(command) => spawn_command(settings, state, &command, None, "on_daemon_start"),
+            Err(err) => error!("Failed to create DaemonStart hook: {err}"),
+        }
+    }
+
+    let groups: Vec<String> = state
+        .groups()
+        .iter()
+        .filter(|(_, group)| group.hooks.on_daemon_start.is_some())
+        .map(|(name, _)| name.clone())
+        .collect();
+    for group in groups {
+        spawn_group_hook(settings, state, &group, HookEvent::DaemonStart);
+    }
+}
+
+/// Get the template of an event's hook.
+/// The hook of a group replaces the global hook.
+fn get_hook(
+    settings: &Settings,
+    state: &LockedState,
+    group: &str,
+    event: HookEvent,
+) -> Option<String> {
+    let group_hook = state
+        .groups()
+        .get(group)
+        .and_then(|group| event.template(&group.hooks));
+
+    group_hook
+        .or_else(|| event.template(&settings.daemon.hooks))
+        .cloned()
+}
+
+/// Render a hook template that doesn't belong to a specific task.
+fn render_hook(
+    template_string: &str,
+    parameters: &HashMap<&'static str, String>,
+) -> Result<String, RenderError> {
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_escape_fn(handlebars::no_escape);
+
+    handlebars.render_template(template_string, parameters)
+}
+
+/// Check whether a group has no queued, running or paused tasks left.
+fn group_is_empty(state: &LockedState, group: &str) -> bool {
+    !state.tasks().values().any(|task| {
+        task.group == group
+            && (task.is_queued()
+                || task.is_running()
+                || matches!(task.status, TaskStatus::Locked { .. }))
+    })
+}
+
+/// Render a template with the variables of a task and spawn it.
+fn spawn_task_command(
+    settings: &Settings,
+    state: &mut LockedState,
+    task: &Task,
+    template_string: &str,
+    trigger: &str,
+) {
     // Build the command to be called from the template string in the configuration file.
     let callback_command = match build_callback_command(settings, state, task, template_string) {
         Ok(callback_command) => callback_command,
         Err(err) => {
-            error!("Failed to create callback command from template with error: {err}");
+            error!("Failed to create {trigger} command from template with error: {err}");
             return;
         }
     };
 
-    let mut command = compile_shell_command(settings, &callback_command);
+    spawn_command(settings, state, &callback_command, Some(task.id), trigger);
+    debug!("Spawned {trigger} for task {}", task.id);
+}
 
-    // Spawn the callback subprocess and log if it fails.
-    let spawn_result = command.spawn();
-    let child = match spawn_result {
+/// Spawn a callback or hook subprocess and log if it fails.
+/// The child is tracked, so its outcome can be recorded once it finishes.
+fn spawn_command(
+    settings: &Settings,
+    state: &mut LockedState,
+    callback_command: &str,
+    task_id: Option<usize>,
+    trigger: &str,
+) {
+    let mut command = compile_shell_command(settings, callback_command);
+
+    // Capture stdout and stderr in a temporary file.
+    // A pipe would block the callback as soon as it's full.
+    let output = match capture_output(&mut command) {
+        Ok(file) => Some(file),
+        Err(err) => {
+            warn!("Failed to create output file for {trigger}: {err}");
+            None
+        }
+    };
+
+    let child = match command.group_spawn() {
         Err(error) => {
-            error!("Failed to spawn callback with error: {error}");
+            error!("Failed to spawn {trigger} with error: {error}");
             return;
         }
         Ok(child) => child,
     };
 
-    debug!("Spawned callback for task {}", task.id);
-    state.callbacks.push(child);
+    state.callbacks.push(RunningCallback {
+        child,
+        task_id,
+        trigger: trigger.to_string(),
+        command: callback_command.to_string(),
+        start: Local::now(),
+        output,
+    });
+}
+
+/// Redirect stdout and stderr of a command into a new temporary file.
+fn capture_output(command: &mut Command) -> std::io::Result<File> {
+    let file = tempfile::tempfile()?;
+    command.stdout(file.try_clone()?).stderr(file.try_clone()?);
+
+    Ok(file)
+}
+
+/// Add the name of the group as well as the amount of queued and stashed tasks in that group.
+fn insert_group_parameters(
+    state: &LockedState,
+    group: &str,
+    parameters: &mut HashMap<&'static str, String>,
+) {
+    parameters.insert("group", group.to_string());
+    let queued_tasks = state
+        .filter_tasks_of_group(Task::is_queued, group)
+        .matching_ids
+        .len();
+    parameters.insert("queued_count", queued_tasks.to_string());
+    let stashed_tasks = state
+        .filter_tasks_of_group(|task| task.is_stashed(), group)
+        .matching_ids
+        .len();
+    parameters.insert("stashed_count", stashed_tasks.to_string());
 }
 
 /// Take the callback template string from the configuration and insert all parameters from the
@@ -58,7 +302,17 @@ pub fn build_callback_command(
     handlebars.set_strict_mode(true);
     handlebars.register_escape_fn(handlebars::no_escape);
 
-    // Add templating variables.
+    let parameters = callback_parameters(settings, state, task);
+
+    handlebars.render_template(template_string, &parameters)
+}
+
+/// Collect all variables of a task that're available to callbacks, hooks and webhooks.
+pub fn callback_parameters(
+    settings: &Settings,
+    state: &LockedState,
+    task: &Task,
+) -> HashMap<&'static str, String> {
     let mut parameters = HashMap::new();
     parameters.insert("id", task.id.to_string());
     parameters.insert("command", task.command.clone());
@@ -66,17 +320,7 @@ pub fn build_callback_command(
 
     // Add group information to template
     // This includes how many stashed and queued tasks are left in the group.
-    parameters.insert("group", task.group.clone());
-    let queued_tasks = state
-        .filter_tasks_of_group(Task::is_queued, &task.group)
-        .matching_ids
-        .len();
-    parameters.insert("queued_count", queued_tasks.to_string());
-    let stashed_tasks = state
-        .filter_tasks_of_group(|task| task.is_stashed(), &task.group)
-        .matching_ids
-        .len();
-    parameters.insert("stashed_count", stashed_tasks.to_string());
+    insert_group_parameters(state, &task.group, &mut parameters);
 
     // Result takes the TaskResult Enum strings, unless it didn't finish yet.
     if let TaskStatus::Done { result, .. } = &task.status {
@@ -121,33 +365,87 @@ pub fn build_callback_command(
         parameters.insert("exit_code", "None".into());
     }
 
-    handlebars.render_template(template_string, &parameters)
+    parameters
 }
 
 /// Look at all running callbacks and check if they're still running.
-/// Handle finished callbacks and log their outcome.
-pub fn check_callbacks(state: &mut LockedState) {
+/// Callbacks that exceed the `callback_timeout` are killed.
+/// The outcome of finished callbacks is recorded, so it can be inspected via `pueue callbacks`.
+pub fn check_callbacks(settings: &Settings, state: &mut LockedState) {
+    // Timeouts that cannot be represented are so large, that they never expire.
+    let timeout = settings
+        .daemon
+        .callback_timeout
+        .and_then(|seconds| i64::try_from(seconds).ok().and_then(TimeDelta::try_seconds));
+
     let mut finished = Vec::new();
-    for (id, child) in state.callbacks.iter_mut().enumerate() {
-        match child.try_wait() {
+    for (id, callback) in state.callbacks.iter_mut().enumerate() {
+        match callback.child.try_wait() {
             // Handle a child error.
             Err(error) => {
                 error!("Callback failed with error {error:?}");
-                finished.push(id);
+                finished.push((id, None, false));
             }
             // Child process did not exit yet.
-            Ok(None) => continue,
-            Ok(exit_status) => {
+            Ok(None) => {
+                let timed_out =
+                    timeout.is_some_and(|timeout| Local::now() - callback.start > timeout);
+                if !timed_out {
+                    continue;
+                }
+
+                warn!(
+                    "Killing {} after it exceeded the callback timeout: {}",
+                    callback.trigger, callback.command
+                );
+                if let Err(err) = callback.child.kill() {
+                    error!("Failed to kill callback with error {err:?}");
+                }
+                // Reap the process, we just killed it, so this returns immediately.
+                let _ = callback.child.wait();
+                finished.push((id, None, true));
+            }
+            Ok(Some(exit_status)) => {
                 info!("Callback finished with exit code {exit_status:?}");
-                finished.push(id);
+                finished.push((id, exit_status.code(), false));
             }
         }
     }
 
     finished.reverse();
-    for id in finished.iter() {
-        // Explicitly allow this lint since we did a try_wait above and know that it finished.
-        #[allow(clippy::zombie_processes)]
-        state.callbacks.remove(*id);
+    for (id, exit_code, timed_out) in finished {
+        let callback = state.callbacks.remove(id);
+        let run = CallbackRun {
+            task_id: callback.task_id,
+            trigger: callback.trigger,
+            command: callback.command,
+            start: callback.start,
+            end: Local::now(),
+            exit_code,
+            timed_out,
+            output: callback.output.map(read_output).unwrap_or_default(),
+        };
+
+        state.callback_runs.push_back(run);
+        if state.callback_runs.len() > MAX_CALLBACK_RUNS {
+            state.callback_runs.pop_front();
+        }
+    }
+}
+
+/// Read the captured output of a callback.
+/// Only the end of very large outputs is read.
+fn read_output(mut file: File) -> String {
+    let mut output = Vec::new();
+    let result = file.seek(SeekFrom::End(0)).and_then(|len| {
+        file.seek(SeekFrom::Start(
+            len.saturating_sub(MAX_CALLBACK_OUTPUT as u64),
+        ))?;
+        file.read_to_end(&mut output)
+    });
+    if let Err(err) = result {
+        warn!("Failed to read callback output: {err}");
     }
+
+    String::from_utf8_lossy(&output).into_owned()
 }
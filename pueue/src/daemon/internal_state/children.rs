@@ -1,14 +1,80 @@
-use std::collections::BTreeMap;
+use std::{collections::BTreeMap, process::ChildStdin};
 
 use command_group::GroupChild;
 
+#[cfg(unix)]
+use crate::process_helper::AdoptedChild;
+use crate::{
+    internal_prelude::*,
+    process_helper::{Signal, kill_child, send_signal_to_child},
+};
+
+/// The process group of a task that's managed by the daemon.
+#[derive(Debug)]
+pub enum TaskChild {
+    /// A process group that has been spawned by this daemon instance.
+    Spawned(GroupChild),
+    /// A supervised process group that has been spawned by a previous daemon instance and
+    /// which has been re-adopted on startup.
+    #[cfg(unix)]
+    Adopted(AdoptedChild),
+}
+
+impl TaskChild {
+    /// Check whether the process finished, without blocking.
+    ///
+    /// Returns `None` while the process is still running.
+    /// Otherwise its exit code is returned, which is `None` if it has been killed by a signal.
+    pub fn try_wait(&mut self) -> std::io::Result<Option<Option<i32>>> {
+        match self {
+            TaskChild::Spawned(child) => Ok(child.try_wait()?.map(|status| status.code())),
+            #[cfg(unix)]
+            TaskChild::Adopted(child) => Ok(child.try_wait()),
+        }
+    }
+
+    /// Send a signal to the process group.
+    pub fn signal(&mut self, signal: Signal) -> Result<()> {
+        match self {
+            TaskChild::Spawned(child) => send_signal_to_child(child, signal),
+            #[cfg(unix)]
+            TaskChild::Adopted(child) => Ok(child.signal(signal)?),
+        }
+    }
+
+    /// Kill the whole process group.
+    pub fn kill(&mut self, task_id: usize) -> std::io::Result<()> {
+        match self {
+            TaskChild::Spawned(child) => kill_child(task_id, child),
+            #[cfg(unix)]
+            TaskChild::Adopted(child) => match child.signal(Signal::SIGKILL) {
+                Err(err) if err.raw_os_error() == Some(libc::ESRCH) => {
+                    info!("Task {task_id} has already finished by itself.");
+                    Ok(())
+                }
+                result => result,
+            },
+        }
+    }
+
+    /// Get the stdin handle of the process.
+    /// Re-adopted processes don't have a stdin handle, as it belonged to the previous daemon.
+    pub fn stdin(&mut self) -> Option<&mut ChildStdin> {
+        match self {
+            TaskChild::Spawned(child) => child.inner().stdin.as_mut(),
+            #[cfg(unix)]
+            TaskChild::Adopted(_) => None,
+        }
+    }
+}
+
 /// This structure is needed to manage worker pools for groups.
 /// It's a newtype pattern around a nested BTreeMap, which implements some convenience functions.
 ///
 /// The datastructure represents the following data:
 /// BTreeMap<group_name, BTreeMap<group_worker_id, (task_id, subprocess_handle)>
 #[derive(Debug, Default)]
-pub struct Children(pub BTreeMap<String, BTreeMap<usize, (usize, GroupChild)>>);
+pub struct Children(pub BTreeMap<String, BTreeMap<usize, (usize, TaskChild)>>);
 
 impl Children {
     /// Returns whether there are any active tasks across all groups.
@@ -43,7 +109,7 @@ impl Children {
     /// A convenience function to get a mutable child by its respective task_id.
     /// We have to do a nested linear search over all children of all pools,
     /// beceause these datastructure aren't indexed via task_ids.
-    pub fn get_child_mut(&mut self, task_id: usize) -> Option<&mut GroupChild> {
+    pub fn get_child_mut(&mut self, task_id: usize) -> Option<&mut TaskChild> {
         for pool in self.0.values_mut() {
             for (child_task_id, child) in pool.values_mut() {
                 if child_task_id == &task_id {
@@ -104,7 +170,7 @@ impl Children {
     /// This function should only be called when spawning a new process.
     /// At this point, we're sure that the worker pool for the given group already exists, hence
     /// the expect call.
-    pub fn add_child(&mut self, group: &str, worker_id: usize, task_id: usize, child: GroupChild) {
+    pub fn add_child(&mut self, group: &str, worker_id: usize, task_id: usize, child: TaskChild) {
         let pool = self
             .0
             .get_mut(group)
@@ -0,0 +1,510 @@
+//! The state journal.
+//!
+//! Rewriting the full state on every change is O(all tasks), which gets expensive for long task
+//! lists. Instead, only the tasks and groups that changed since the last save are appended as
+//! single JSON lines to `state.journal`. Once the journal grows larger than the last snapshot, it's
+//! compacted by writing a full snapshot (`state.json`) and starting a fresh journal.
+//!
+//! The first line of every journal is a header containing the checksum of the snapshot the journal
+//! builds upon. This way a journal that's left over from a crash during compaction is detected
+//! and won't be replayed on top of a newer snapshot.
+use std::{
+    fs::{File, OpenOptions, read_to_string},
+    io::{BufRead, BufReader, Read, Write},
+    path::PathBuf,
+};
+
+use pueue_lib::{Group, Settings, State, Task, settings::StateBackend};
+use serde::{Deserialize, Serialize};
+
+use super::store::{Changes, StateStore, remove_other_backend};
+use crate::{daemon::archive, internal_prelude::*};
+
+/// Journals smaller than this are never compacted, even if the snapshot is tiny.
+const MIN_COMPACTION_SIZE: u64 = 1024 * 1024;
+
+/// The first line of each journal file.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+struct JournalHeader {
+    /// The CRC32 checksum of the serialized snapshot this journal applies to.
+    snapshot_crc: u32,
+}
+
+/// A single mutation of the state.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub(super) enum JournalEntry {
+    /// A task has been added or changed.
+    Task { task: Box<Task> },
+    /// A task has been removed.
+    RemoveTask { id: usize },
+    /// A group has been added or changed.
+    Group { name: String, group: Box<Group> },
+    /// A group has been removed.
+    RemoveGroup { name: String },
+}
+
+impl JournalEntry {
+    pub(super) fn apply(self, state: &mut State) {
+        match self {
+            JournalEntry::Task { task } => {
+                state.tasks.insert(task.id, *task);
+            }
+            JournalEntry::RemoveTask { id } => {
+                state.tasks.remove(&id);
+            }
+            JournalEntry::Group { name, group } => {
+                state.groups.insert(name, *group);
+            }
+            JournalEntry::RemoveGroup { name } => {
+                state.groups.remove(&name);
+            }
+        }
+    }
+}
+
+/// Appends the changes of the state to the journal and compacts it from time to time.
+#[derive(Debug, Default)]
+pub struct Journal {
+    /// Whether this daemon has already written a snapshot and a journal.
+    /// Until then, or after a failed write, the next save is a full compaction.
+    compacted: bool,
+    /// The current size of the journal file in bytes.
+    journal_size: u64,
+    /// The size of the last written snapshot in bytes.
+    snapshot_size: u64,
+    /// The value of `compress_state_file` the last snapshot has been written with.
+    /// The journal always belongs to a snapshot in the currently configured format, so a new
+    /// snapshot has to be written as soon as this setting changes.
+    compressed: bool,
+}
+
+impl StateStore for Journal {
+    fn backend(&self) -> StateBackend {
+        StateBackend::Json
+    }
+
+    /// Only the changed tasks and groups are appended to the journal, unless the journal has
+    /// grown large enough to be compacted into a new snapshot.
+    fn save(&mut self, settings: &Settings, state: &State, changes: &Changes) -> Result<()> {
+        if !self.compacted || self.compressed != settings.daemon.compress_state_file {
+            return self.compact(settings, state);
+        }
+
+        let entries = entries(state, changes);
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        if self.journal_size > self.snapshot_size.max(MIN_COMPACTION_SIZE) {
+            return self.compact(settings, state);
+        }
+
+        let mut lines = String::new();
+        for entry in &entries {
+            lines.push_str(
+                &serde_json::to_string(entry).context("Failed to serialize journal entry:")?,
+            );
+            lines.push('\n');
+        }
+
+        let path = journal_path(settings);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .context("Failed to open state journal.")?;
+        if let Err(err) = file.write_all(lines.as_bytes()) {
+            // Cut off the partially written entries, so no broken line is left in the journal.
+            // If even that fails, the next save writes a fresh snapshot and journal.
+            if file.set_len(self.journal_size).is_err() {
+                self.compacted = false;
+            }
+            return Err(err).context("Failed to append to state journal.");
+        }
+        self.journal_size += lines.len() as u64;
+        debug!("State journal appended at: {path:?}");
+
+        Ok(())
+    }
+
+    /// Archived tasks are appended to `archive.jsonl`.
+    fn archive(&mut self, settings: &Settings, tasks: &[&Task]) -> Result<()> {
+        archive::append(settings, tasks)?;
+        archive::prune(settings).context("Failed to prune the archive.")
+    }
+}
+
+impl Journal {
+    /// Write a full snapshot of the state and start a new, empty journal for it.
+    fn compact(&mut self, settings: &Settings, state: &State) -> Result<()> {
+        let serialized = serde_json::to_string(state).context("Failed to serialize state:")?;
+        write_snapshot(settings, &serialized)?;
+
+        // Create the new journal next to the old one, so the header and the snapshot can never
+        // get out of sync.
+        let header = serde_json::to_string(&JournalHeader {
+            snapshot_crc: checksum(&serialized),
+        })
+        .context("Failed to serialize journal header:")?;
+        let path = journal_path(settings);
+        let temp = path.with_extension("journal.partial");
+        std::fs::write(&temp, format!("{header}\n"))
+            .context("Failed to write temp file while creating state journal.")?;
+        std::fs::rename(&temp, &path).context("Failed to overwrite old state journal.")?;
+
+        let first_write = !self.compacted;
+        let compression_changed = self.compressed != settings.daemon.compress_state_file;
+        self.compacted = true;
+        self.journal_size = header.len() as u64 + 1;
+        self.snapshot_size = serialized.len() as u64;
+        self.compressed = settings.daemon.compress_state_file;
+
+        // Remove the snapshot in the previous format, so it can't be restored once the setting
+        // is switched back.
+        if !first_write && compression_changed {
+            let other = other_snapshot_path(settings);
+            if other.exists() {
+                std::fs::remove_file(&other)
+                    .with_context(|| format!("Failed to remove old state file at {other:?}"))?;
+            }
+        }
+        if first_write {
+            remove_other_backend(settings, StateBackend::Json)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Create the journal entries for all changed tasks and groups.
+/// Changed tasks and groups that no longer exist have been removed.
+pub(super) fn entries(state: &State, changes: &Changes) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+
+    for id in &changes.tasks {
+        match state.tasks.get(id) {
+            Some(task) => entries.push(JournalEntry::Task {
+                task: Box::new(task.clone()),
+            }),
+            None => entries.push(JournalEntry::RemoveTask { id: *id }),
+        }
+    }
+
+    for name in &changes.groups {
+        match state.groups.get(name) {
+            Some(group) => entries.push(JournalEntry::Group {
+                name: name.clone(),
+                group: Box::new(group.clone()),
+            }),
+            None => entries.push(JournalEntry::RemoveGroup { name: name.clone() }),
+        }
+    }
+
+    entries
+}
+
+/// Restore the state from the last snapshot and replay the journal on top of it.
+pub fn restore(settings: &Settings) -> Result<Option<State>> {
+    let path = snapshot_path(settings);
+
+    // Ignore if the file doesn't exist. It doesn't have to.
+    if !path.exists() {
+        info!("Couldn't find state from previous session at location: {path:?}");
+        return Ok(None);
+    }
+    info!("Restoring state");
+
+    // Try to load the file.
+    let data = if settings.daemon.compress_state_file {
+        let file = File::open(path)?;
+        let mut decoder = flate2::read::GzDecoder::new(file);
+        let mut data = String::new();
+        decoder.read_to_string(&mut data)?;
+
+        data
+    } else {
+        read_to_string(&path).context("State restore: Failed to read file:\n\n{}")?
+    };
+
+    // Try to deserialize the state file.
+    let mut state: State = serde_json::from_str(&data).context("Failed to deserialize state.")?;
+
+    // Apply all changes that have been made since the last snapshot.
+    replay(settings, &data, &mut state)?;
+
+    Ok(Some(state))
+}
+
+/// Remove the snapshots and the journal.
+pub fn remove(settings: &Settings) -> Result<()> {
+    let path = settings.shared.pueue_directory();
+    for file in ["state.json", "state.json.gz", "state.journal"] {
+        let path = path.join(file);
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove state file at {path:?}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Replay the journal on top of a freshly deserialized snapshot.
+///
+/// `snapshot` is the raw (uncompressed) snapshot data `state` has been deserialized from.
+/// Journals that belong to another snapshot are ignored, as is a broken line at the end of the
+/// journal, which happens if the daemon crashes while appending.
+/// Any other broken line means that the journal is corrupt, in which case the restore fails.
+fn replay(settings: &Settings, snapshot: &str, state: &mut State) -> Result<()> {
+    let path = journal_path(settings);
+    if !path.exists() {
+        return Ok(());
+    }
+
+    let file = File::open(&path).context("Failed to open state journal.")?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header: Option<JournalHeader> = lines
+        .next()
+        .transpose()?
+        .and_then(|line| serde_json::from_str(&line).ok());
+    if header.map(|header| header.snapshot_crc) != Some(checksum(snapshot)) {
+        info!("Ignoring state journal, as it doesn't belong to the current state snapshot.");
+        return Ok(());
+    }
+
+    let mut replayed = 0;
+    let mut lines = lines.enumerate().peekable();
+    while let Some((index, line)) = lines.next() {
+        let line = line?;
+        match serde_json::from_str::<JournalEntry>(&line) {
+            Ok(entry) => entry.apply(state),
+            Err(err) if lines.peek().is_none() => {
+                warn!("Ignoring broken last entry of the state journal: {err}");
+                break;
+            }
+            Err(err) => bail!(
+                "The state journal at {path:?} is corrupt at line {}: {err}",
+                // The header is the first line.
+                index + 2
+            ),
+        }
+        replayed += 1;
+    }
+    info!("Replayed {replayed} state journal entries");
+
+    Ok(())
+}
+
+/// Write the full state to the snapshot file.
+/// The snapshot is written to a temporary file first, to prevent loss due to crashes.
+pub fn write_snapshot(settings: &Settings, serialized: &str) -> Result<()> {
+    let path = settings.shared.pueue_directory();
+    let mut temp = path.join("state.json.partial");
+    let mut real = path.join("state.json");
+
+    if settings.daemon.compress_state_file {
+        temp = path.join("state.json.gz.partial");
+        real = path.join("state.json.gz");
+
+        let file = File::create(&temp)?;
+        let mut encoder = flate2::write::GzEncoder::new(file, flate2::Compression::default());
+        encoder.write_all(serialized.as_bytes())?;
+        encoder.finish()?;
+    } else {
+        std::fs::write(&temp, serialized)
+            .context("Failed to write temp file while saving state.")?;
+    }
+
+    // Overwrite the original with the temp file, if everything went fine.
+    std::fs::rename(&temp, &real).context("Failed to overwrite old state while saving state")?;
+
+    debug!("State saved at: {real:?}");
+
+    Ok(())
+}
+
+fn snapshot_path(settings: &Settings) -> PathBuf {
+    let path = settings.shared.pueue_directory();
+    if settings.daemon.compress_state_file {
+        path.join("state.json.gz")
+    } else {
+        path.join("state.json")
+    }
+}
+
+/// The snapshot path for the compression that's currently not configured.
+fn other_snapshot_path(settings: &Settings) -> PathBuf {
+    let path = settings.shared.pueue_directory();
+    if settings.daemon.compress_state_file {
+        path.join("state.json")
+    } else {
+        path.join("state.json.gz")
+    }
+}
+
+fn journal_path(settings: &Settings) -> PathBuf {
+    settings.shared.pueue_directory().join("state.journal")
+}
+
+fn checksum(data: &str) -> u32 {
+    let mut crc = flate2::Crc::new();
+    crc.update(data.as_bytes());
+    crc.sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use pretty_assertions::assert_eq;
+    use pueue_lib::{GroupStatus, TaskStatus, state::PUEUE_DEFAULT_GROUP};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::daemon::internal_state::state::InternalState;
+
+    fn settings(tempdir: &TempDir) -> Settings {
+        let mut settings = Settings::default();
+        settings.shared.pueue_directory = Some(tempdir.path().to_path_buf());
+        settings
+    }
+
+    fn stashed_task(command: &str) -> Task {
+        Task::new(
+            command.into(),
+            std::env::temp_dir(),
+            HashMap::new(),
+            PUEUE_DEFAULT_GROUP.into(),
+            TaskStatus::Stashed { enqueue_at: None },
+            Vec::new(),
+            0,
+            None,
+        )
+    }
+
+    /// Create a state that has been compacted once and then journaled a few changes.
+    fn journaled_state(settings: &Settings) -> Result<InternalState> {
+        let mut state = InternalState::new();
+        state.add_task(stashed_task("first"));
+        state.save(settings)?;
+
+        state.add_task(stashed_task("second"));
+        state.remove_task(0);
+        state.create_group("test").status = GroupStatus::Paused;
+        state.save(settings)?;
+
+        Ok(state)
+    }
+
+    #[test]
+    fn replay_journaled_changes() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let settings = settings(&tempdir);
+        let state = journaled_state(&settings)?;
+
+        // The snapshot only contains the first task, the rest lives in the journal.
+        let snapshot = std::fs::read_to_string(tempdir.path().join("state.json"))?;
+        assert!(snapshot.contains("first") && !snapshot.contains("second"));
+
+        let restored = InternalState::restore_state(&settings)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_broken_last_line() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let settings = settings(&tempdir);
+        let state = journaled_state(&settings)?;
+
+        // Simulate a crash while appending to the journal.
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(journal_path(&settings))?;
+        file.write_all(b"{\"op\":\"task\",\"ta")?;
+
+        let restored = InternalState::restore_state(&settings)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+
+        Ok(())
+    }
+
+    #[test]
+    fn journal_only_changed_tasks() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let settings = settings(&tempdir);
+        let mut state = journaled_state(&settings)?;
+        let journal = std::fs::read_to_string(journal_path(&settings))?;
+
+        state.task_mut(1).unwrap().label = Some("changed".into());
+        state.save(&settings)?;
+
+        // Only a single line for the changed task has been appended.
+        let appended = std::fs::read_to_string(journal_path(&settings))?;
+        let appended = appended.strip_prefix(&journal).unwrap();
+        assert_eq!(appended.lines().count(), 1);
+        assert!(appended.contains("changed"));
+
+        let restored = InternalState::restore_state(&settings)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fail_on_corrupt_entry() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let settings = settings(&tempdir);
+        let mut state = journaled_state(&settings)?;
+
+        // A broken line that's followed by further entries isn't the result of a crash.
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(journal_path(&settings))?;
+        file.write_all(b"{\"op\":\"task\",\"ta\n")?;
+        state.task_mut(1).unwrap().label = Some("changed".into());
+        state.save(&settings)?;
+
+        assert!(InternalState::restore_state(&settings).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn compact_on_compression_change() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let mut settings = settings(&tempdir);
+        let mut state = journaled_state(&settings)?;
+
+        // The journal would otherwise be appended to, while the snapshot is in the old format.
+        settings.daemon.compress_state_file = true;
+        state.task_mut(1).unwrap().label = Some("changed".into());
+        state.save(&settings)?;
+
+        assert!(tempdir.path().join("state.json.gz").exists());
+        assert!(!tempdir.path().join("state.json").exists());
+        let restored = InternalState::restore_state(&settings)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+
+        Ok(())
+    }
+
+    #[test]
+    fn ignore_journal_of_other_snapshot() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let settings = settings(&tempdir);
+        journaled_state(&settings)?;
+
+        // Simulate a crash during compaction, after the new snapshot has been written.
+        let mut state = InternalState::new();
+        state.add_task(stashed_task("other"));
+        write_snapshot(&settings, &serde_json::to_string(&state.inner)?)?;
+
+        let restored = InternalState::restore_state(&settings)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+
+        Ok(())
+    }
+}
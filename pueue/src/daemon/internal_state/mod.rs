@@ -5,7 +5,16 @@ use state::InternalState;
 /// A helper newtype struct, which implements convenience methods for our child process management
 /// datastructure.
 pub mod children;
+/// Incremental persistence of the state via an append-only journal.
+pub mod journal;
+/// Incremental persistence of the state in an SQLite database.
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
 /// The main struct used to represent the daemon's current state.
 pub mod state;
+/// The interface to the different state backends.
+pub mod store;
+/// Bookkeeping that's needed to enforce task timeouts.
+pub mod timeouts;
 
 pub type SharedState = Arc<Mutex<InternalState>>;
@@ -0,0 +1,480 @@
+//! The SQLite state backend.
+//!
+//! Tasks and groups are stored as rows in `state.sqlite` inside the `pueue_directory` and are
+//! updated incrementally. Archived tasks are kept in the `history` table.
+//!
+//! Each row contains the full serialized task or group in its `data` column. The remaining
+//! columns only exist to make ad-hoc queries convenient and are never read by the daemon.
+use std::path::PathBuf;
+
+use pueue_lib::{Group, Settings, State, Task, TaskStatus, settings::StateBackend};
+use rusqlite::{CachedStatement, Connection, Transaction, params};
+
+use super::{
+    journal::{JournalEntry, entries},
+    store::{Changes, StateStore, remove_other_backend},
+};
+use crate::{daemon::archive, internal_prelude::*};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS tasks (
+    id INTEGER PRIMARY KEY,
+    group_name TEXT NOT NULL,
+    status TEXT NOT NULL,
+    result TEXT,
+    label TEXT,
+    priority INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    path TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    ended_at TEXT,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS history (
+    id INTEGER NOT NULL,
+    group_name TEXT NOT NULL,
+    status TEXT NOT NULL,
+    result TEXT,
+    label TEXT,
+    priority INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    path TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    started_at TEXT,
+    ended_at TEXT,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS groups (
+    name TEXT PRIMARY KEY,
+    status TEXT NOT NULL,
+    parallel_tasks INTEGER NOT NULL,
+    data TEXT NOT NULL
+);
+";
+
+/// The columns of the `tasks` and `history` tables.
+const TASK_COLUMNS: &str = "id, group_name, status, result, label, priority, command, path, \
+                            created_at, started_at, ended_at, data";
+const TASK_VALUES: &str = "?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12";
+
+/// The location of the SQLite database.
+pub fn database_path(settings: &Settings) -> PathBuf {
+    settings.shared.pueue_directory().join("state.sqlite")
+}
+
+/// Persists the state in an SQLite database.
+///
+/// Just like the [journal](super::journal::Journal), only the tasks and groups that changed since
+/// the last save are written.
+#[derive(Default)]
+pub struct SqliteStore {
+    connection: Option<Connection>,
+    /// Whether this daemon has already written the full state to the database.
+    /// Until then, the next save replaces everything that's in the database.
+    written: bool,
+}
+
+impl std::fmt::Debug for SqliteStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SqliteStore")
+            .field("connected", &self.connection.is_some())
+            .field("written", &self.written)
+            .finish()
+    }
+}
+
+impl StateStore for SqliteStore {
+    fn backend(&self) -> StateBackend {
+        StateBackend::Sqlite
+    }
+
+    fn save(&mut self, settings: &Settings, state: &State, changes: &Changes) -> Result<()> {
+        let written = self.written;
+        let connection = self.connection(settings)?;
+
+        if !written {
+            // Replace everything that might still be in the database.
+            let transaction = connection.transaction()?;
+            transaction.execute_batch("DELETE FROM tasks; DELETE FROM groups;")?;
+            for task in state.tasks.values() {
+                upsert_task(&transaction, task)?;
+            }
+            for (name, group) in state.groups.iter() {
+                upsert_group(&transaction, name, group)?;
+            }
+            transaction
+                .commit()
+                .context("Failed to save state to database.")?;
+            self.written = true;
+
+            return remove_other_backend(settings, StateBackend::Sqlite);
+        }
+
+        let entries = entries(state, changes);
+        if entries.is_empty() {
+            return Ok(());
+        }
+
+        let transaction = connection.transaction()?;
+        for entry in entries.iter() {
+            match entry {
+                JournalEntry::Task { task } => upsert_task(&transaction, task)?,
+                JournalEntry::RemoveTask { id } => {
+                    transaction.execute("DELETE FROM tasks WHERE id = ?1", [*id as i64])?;
+                }
+                JournalEntry::Group { name, group } => upsert_group(&transaction, name, group)?,
+                JournalEntry::RemoveGroup { name } => {
+                    transaction.execute("DELETE FROM groups WHERE name = ?1", [name])?;
+                }
+            }
+        }
+        transaction
+            .commit()
+            .context("Failed to save state to database.")?;
+        debug!("State saved to database");
+
+        Ok(())
+    }
+
+    /// Archived tasks are moved to the `history` table.
+    fn archive(&mut self, settings: &Settings, tasks: &[&Task]) -> Result<()> {
+        archive_tasks(self.connection(settings)?, settings, tasks)
+    }
+}
+
+impl SqliteStore {
+    /// The connection to the database, which is opened on first use.
+    fn connection(&mut self, settings: &Settings) -> Result<&mut Connection> {
+        if self.connection.is_none() {
+            self.connection = Some(open(settings)?);
+        }
+
+        Ok(self.connection.as_mut().unwrap())
+    }
+}
+
+/// Restore the state from the database, if there is one.
+pub fn restore(settings: &Settings) -> Result<Option<State>> {
+    let path = database_path(settings);
+    if !path.exists() {
+        info!("Couldn't find state database from previous session at location: {path:?}");
+        return Ok(None);
+    }
+    info!("Restoring state from database");
+
+    let connection = open(settings)?;
+    let mut state = State::default();
+
+    let mut statement = connection.prepare("SELECT data FROM tasks ORDER BY id")?;
+    for data in statement.query_map([], |row| row.get::<_, String>(0))? {
+        let task: Task = serde_json::from_str(&data?).context("Failed to deserialize task.")?;
+        state.tasks.insert(task.id, task);
+    }
+
+    let mut statement = connection.prepare("SELECT name, data FROM groups")?;
+    let rows = statement.query_map([], |row| {
+        Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+    })?;
+    for row in rows {
+        let (name, data) = row?;
+        let group: Group = serde_json::from_str(&data).context("Failed to deserialize group.")?;
+        state.groups.insert(name, group);
+    }
+
+    Ok(Some(state))
+}
+
+/// Add finished tasks to the `history` table and apply the archive's retention policy.
+fn archive_tasks(connection: &mut Connection, settings: &Settings, tasks: &[&Task]) -> Result<()> {
+    let transaction = connection.transaction()?;
+    insert_history(&transaction, tasks, false)?;
+
+    let policy = &settings.daemon.archive;
+    if let Some(oldest) = policy.max_age_days.and_then(archive::prune_before) {
+        transaction.execute(
+            "DELETE FROM history WHERE unixepoch(ended_at) < ?1",
+            [oldest.timestamp()],
+        )?;
+    }
+    if let Some(max_tasks) = policy.max_tasks {
+        transaction.execute(
+            "DELETE FROM history WHERE rowid NOT IN \
+             (SELECT rowid FROM history ORDER BY rowid DESC LIMIT ?1)",
+            [max_tasks as i64],
+        )?;
+    }
+
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Read all tasks of the `history` table, from the oldest to the most recently archived one.
+/// Returns an empty list, if there's no database.
+pub fn read_history(settings: &Settings) -> Result<Vec<Task>> {
+    if !database_path(settings).exists() {
+        return Ok(Vec::new());
+    }
+
+    let connection = open(settings)?;
+    let mut statement = connection.prepare("SELECT data FROM history ORDER BY rowid")?;
+    let mut tasks = Vec::new();
+    for data in statement.query_map([], |row| row.get::<_, String>(0))? {
+        match serde_json::from_str(&data?) {
+            Ok(task) => tasks.push(task),
+            Err(err) => warn!("Skipping broken task of the history: {err}"),
+        }
+    }
+
+    Ok(tasks)
+}
+
+/// Move the given tasks into the `history` table.
+/// This is used to migrate the archive when switching to this backend.
+///
+/// Tasks that are already in the `history` table are skipped, so an interrupted migration can
+/// be repeated without duplicating tasks.
+pub fn import_history(settings: &Settings, tasks: &[Task]) -> Result<()> {
+    let mut connection = open(settings)?;
+    let transaction = connection.transaction()?;
+    insert_history(&transaction, &tasks.iter().collect::<Vec<&Task>>(), true)?;
+    transaction.commit()?;
+
+    Ok(())
+}
+
+/// Remove the database including SQLite's temporary files.
+pub fn remove(settings: &Settings) -> Result<()> {
+    let path = database_path(settings);
+    for suffix in ["", "-journal", "-wal", "-shm"] {
+        let path = PathBuf::from(format!("{}{suffix}", path.to_string_lossy()));
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove state database at {path:?}"))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Open the database and make sure all tables exist.
+fn open(settings: &Settings) -> Result<Connection> {
+    let path = database_path(settings);
+    let connection = Connection::open(&path)
+        .with_context(|| format!("Failed to open state database at {path:?}"))?;
+    connection.busy_timeout(std::time::Duration::from_secs(5))?;
+    connection
+        .execute_batch(SCHEMA)
+        .context("Failed to create state database tables.")?;
+
+    Ok(connection)
+}
+
+fn upsert_task(transaction: &Transaction, task: &Task) -> Result<()> {
+    let mut statement = transaction.prepare_cached(&format!(
+        "INSERT OR REPLACE INTO tasks ({TASK_COLUMNS}) VALUES ({TASK_VALUES})"
+    ))?;
+    execute_for_task(&mut statement, task)
+}
+
+/// Insert tasks into the `history` table.
+/// If `skip_archived` is set, tasks with the same id and end time as an archived task are skipped.
+fn insert_history(transaction: &Transaction, tasks: &[&Task], skip_archived: bool) -> Result<()> {
+    let mut statement = if skip_archived {
+        transaction.prepare_cached(&format!(
+            "INSERT INTO history ({TASK_COLUMNS}) SELECT {TASK_VALUES} WHERE NOT EXISTS \
+             (SELECT 1 FROM history WHERE id = ?1 AND ended_at IS ?11)"
+        ))?
+    } else {
+        transaction.prepare_cached(&format!(
+            "INSERT INTO history ({TASK_COLUMNS}) VALUES ({TASK_VALUES})"
+        ))?
+    };
+    for task in tasks {
+        execute_for_task(&mut statement, task)?;
+    }
+
+    Ok(())
+}
+
+/// Execute an insert statement on the [TASK_COLUMNS] of a task.
+fn execute_for_task(statement: &mut CachedStatement, task: &Task) -> Result<()> {
+    let (result, started_at, ended_at) = match &task.status {
+        TaskStatus::Running { start, .. } | TaskStatus::Paused { start, .. } => {
+            (None, Some(start.to_rfc3339()), None)
+        }
+        TaskStatus::Done {
+            start, end, result, ..
+        } => (
+            Some(result.to_string()),
+            Some(start.to_rfc3339()),
+            Some(end.to_rfc3339()),
+        ),
+        _ => (None, None, None),
+    };
+
+    statement.execute(params![
+        task.id as i64,
+        task.group,
+        task.status.to_string(),
+        result,
+        task.label,
+        task.priority,
+        task.command,
+        task.path.to_string_lossy(),
+        task.created_at.to_rfc3339(),
+        started_at,
+        ended_at,
+        serde_json::to_string(task).context("Failed to serialize task:")?,
+    ])?;
+
+    Ok(())
+}
+
+fn upsert_group(transaction: &Transaction, name: &str, group: &Group) -> Result<()> {
+    transaction
+        .prepare_cached(
+            "INSERT OR REPLACE INTO groups (name, status, parallel_tasks, data) \
+             VALUES (?1, ?2, ?3, ?4)",
+        )?
+        .execute(params![
+            name,
+            format!("{:?}", group.status),
+            group.parallel_tasks as i64,
+            serde_json::to_string(group).context("Failed to serialize group:")?,
+        ])?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use chrono::Local;
+    use pretty_assertions::assert_eq;
+    use pueue_lib::{GroupStatus, state::PUEUE_DEFAULT_GROUP};
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::daemon::internal_state::state::InternalState;
+
+    fn settings(tempdir: &TempDir, backend: StateBackend) -> Settings {
+        let mut settings = Settings::default();
+        settings.shared.pueue_directory = Some(tempdir.path().to_path_buf());
+        settings.daemon.state_backend = backend;
+        settings.daemon.archive.enabled = true;
+        settings
+    }
+
+    fn stashed_task(command: &str) -> Task {
+        Task::new(
+            command.into(),
+            std::env::temp_dir(),
+            HashMap::new(),
+            PUEUE_DEFAULT_GROUP.into(),
+            TaskStatus::Stashed { enqueue_at: None },
+            Vec::new(),
+            0,
+            None,
+        )
+    }
+
+    #[test]
+    fn save_and_restore() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let settings = settings(&tempdir, StateBackend::Sqlite);
+
+        let mut state = InternalState::new();
+        state.add_task(stashed_task("first"));
+        state.save(&settings)?;
+
+        // Incremental changes.
+        state.add_task(stashed_task("second"));
+        state.remove_task(0);
+        state.create_group("test").status = GroupStatus::Paused;
+        state.save(&settings)?;
+
+        let restored = InternalState::restore_state(&settings)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+
+        // The columns can be used for ad-hoc queries.
+        let connection = Connection::open(database_path(&settings))?;
+        let command: String = connection.query_row(
+            "SELECT command FROM tasks WHERE status = 'Stashed'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(command, "second");
+
+        Ok(())
+    }
+
+    #[test]
+    fn migrate_between_backends() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let json = settings(&tempdir, StateBackend::Json);
+        let sqlite = settings(&tempdir, StateBackend::Sqlite);
+
+        let mut state = InternalState::new();
+        state.add_task(stashed_task("task"));
+        state.save(&json)?;
+        let mut finished = stashed_task("archived");
+        finished.status = TaskStatus::Done {
+            enqueued_at: Local::now(),
+            start: Local::now(),
+            end: Local::now(),
+            result: pueue_lib::TaskResult::Success,
+        };
+        archive::archive_tasks(&json, &state, &[finished]);
+
+        // JSON -> SQLite
+        let restored = InternalState::restore_state(&sqlite)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+        restored.save(&sqlite)?;
+        assert!(!tempdir.path().join("state.json").exists());
+        assert!(!archive::archive_path(&sqlite).exists());
+        assert_eq!(archive::read_archive(&sqlite)?.len(), 1);
+
+        // SQLite -> JSON
+        let restored = InternalState::restore_state(&json)?.unwrap();
+        assert_eq!(restored.inner, state.inner);
+        restored.save(&json)?;
+        assert!(!database_path(&json).exists());
+        assert_eq!(archive::read_archive(&json)?.len(), 1);
+
+        Ok(())
+    }
+
+    /// A migration that's interrupted after the archive has been moved is simply repeated.
+    #[test]
+    fn repeat_interrupted_migration() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        let json = settings(&tempdir, StateBackend::Json);
+        let sqlite = settings(&tempdir, StateBackend::Sqlite);
+
+        let state = InternalState::new();
+        state.save(&json)?;
+        let mut finished = stashed_task("archived");
+        finished.status = TaskStatus::Done {
+            enqueued_at: Local::now(),
+            start: Local::now(),
+            end: Local::now(),
+            result: pueue_lib::TaskResult::Success,
+        };
+        archive::archive_tasks(&json, &state, &[finished]);
+
+        // JSON -> SQLite, with the archive already imported before the interruption.
+        import_history(&sqlite, &archive::read_archive_file(&json)?)?;
+        remove_other_backend(&sqlite, StateBackend::Sqlite)?;
+        assert_eq!(archive::read_archive(&sqlite)?.len(), 1);
+
+        // SQLite -> JSON, with the history already appended before the interruption.
+        archive::append(&json, &read_history(&sqlite)?.iter().collect::<Vec<_>>())?;
+        remove_other_backend(&json, StateBackend::Json)?;
+        assert_eq!(archive::read_archive(&json)?.len(), 1);
+
+        Ok(())
+    }
+}
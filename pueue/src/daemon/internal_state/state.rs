@@ -1,23 +1,36 @@
 use std::{
-    collections::BTreeMap,
-    fs::{File, read_to_string},
-    io::{Read, Write},
-    process::Child,
+    collections::{BTreeMap, VecDeque},
+    path::PathBuf,
     sync::{Arc, Mutex, MutexGuard},
 };
 
 use chrono::Local;
-use flate2::Compression;
 use pueue_lib::{
     Group, GroupStatus, Settings, State, TaskResult,
     error::Error,
-    message::request::ShutdownRequest,
+    message::{CallbackRun, request::ShutdownRequest},
+    settings::GroupSettings,
     state::{FilteredTasks, PUEUE_DEFAULT_GROUP},
     task::{Task, TaskStatus},
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{daemon::internal_state::children::Children, internal_prelude::*};
+#[cfg(unix)]
+use crate::daemon::{
+    internal_state::children::TaskChild,
+    supervisor::{Adoption, adopt},
+};
+use crate::{
+    daemon::{
+        callbacks::RunningCallback,
+        internal_state::{
+            children::Children,
+            store::{self, Changes, StateStore, create_store},
+            timeouts::Timeouts,
+        },
+    },
+    internal_prelude::*,
+};
 
 pub type SharedState = Arc<Mutex<InternalState>>;
 pub type LockedState<'a> = MutexGuard<'a, InternalState>;
@@ -62,7 +75,24 @@ pub struct InternalState {
     pub children: Children,
     /// These are the currently running callbacks. They're usually very short-lived.
     #[serde(default, skip)]
-    pub callbacks: Vec<Child>,
+    pub callbacks: Vec<RunningCallback>,
+    /// The most recently finished callbacks, oldest first.
+    /// This is runtime state and won't be serialised to disk.
+    #[serde(default, skip)]
+    pub callback_runs: VecDeque<CallbackRun>,
+    /// Pause and kill bookkeeping for tasks with a timeout.
+    /// This is runtime state and won't be serialised to disk.
+    #[serde(default, skip)]
+    pub timeouts: Timeouts,
+    /// The backend the state is persisted with. It's created on the first save.
+    /// This is runtime state and won't be serialised to disk.
+    #[serde(default, skip)]
+    store: Mutex<Option<Box<dyn StateStore>>>,
+    /// The tasks and groups that changed since the last save.
+    /// All changes have to go through the mutating methods of this struct, so they're tracked.
+    /// This is runtime state and won't be serialised to disk.
+    #[serde(default, skip)]
+    changes: Mutex<Changes>,
 }
 
 // Implement a custom Clone, as the child processes don't implement Clone.
@@ -96,45 +126,75 @@ impl InternalState {
         &self.inner.tasks
     }
 
-    pub fn tasks_mut(&mut self) -> &mut BTreeMap<usize, Task> {
-        &mut self.inner.tasks
+    /// Get a task for modification. The task is written on the next save.
+    pub fn task_mut(&mut self, id: usize) -> Option<&mut Task> {
+        let task = self.inner.tasks.get_mut(&id)?;
+        self.changes.get_mut().unwrap().tasks.insert(id);
+        Some(task)
+    }
+
+    /// Insert a task with its current id, replacing any task with the same id.
+    pub fn insert_task(&mut self, task: Task) {
+        self.changes.get_mut().unwrap().tasks.insert(task.id);
+        self.inner.tasks.insert(task.id, task);
+    }
+
+    /// Remove a task from the state.
+    pub fn remove_task(&mut self, id: usize) -> Option<Task> {
+        let task = self.inner.tasks.remove(&id)?;
+        self.changes.get_mut().unwrap().tasks.insert(id);
+        Some(task)
     }
 
     pub fn groups(&self) -> &BTreeMap<String, Group> {
         &self.inner.groups
     }
 
-    pub fn groups_mut(&mut self) -> &mut BTreeMap<String, Group> {
-        &mut self.inner.groups
+    /// Get a group for modification. The group is written on the next save.
+    pub fn group_mut(&mut self, name: &str) -> Option<&mut Group> {
+        let group = self.inner.groups.get_mut(name)?;
+        self.changes
+            .get_mut()
+            .unwrap()
+            .groups
+            .insert(name.to_string());
+        Some(group)
     }
 
-    /// Add a new task
-    pub fn add_task(&mut self, mut task: Task) -> usize {
-        let next_id = match self.tasks().last_key_value() {
+    /// The id that the next added task will get.
+    pub fn next_task_id(&self) -> usize {
+        match self.tasks().last_key_value() {
             None => 0,
             Some((id, _)) => id + 1,
-        };
+        }
+    }
+
+    /// Add a new task
+    pub fn add_task(&mut self, mut task: Task) -> usize {
+        let next_id = self.next_task_id();
         task.id = next_id;
-        self.tasks_mut().insert(next_id, task);
+        self.insert_task(task);
 
         next_id
     }
 
     /// A small helper to change the status of a specific task.
     pub fn change_status(&mut self, id: usize, new_status: TaskStatus) {
-        if let Some(ref mut task) = self.tasks_mut().get_mut(&id) {
+        if let Some(task) = self.task_mut(id) {
             task.status = new_status;
         };
     }
 
     /// Add a new group to the daemon. \
     /// This also check if the given group already exists.
-    /// Create a state.group entry and a settings.group entry, if it doesn't.
+    /// Create a state.group entry and the group's worker pool, if it doesn't.
     pub fn create_group(&mut self, name: &str) -> &mut Group {
-        self.groups_mut().entry(name.into()).or_insert(Group {
-            status: GroupStatus::Running,
-            parallel_tasks: 1,
-        })
+        self.changes.get_mut().unwrap().groups.insert(name.into());
+        self.children.0.entry(name.into()).or_default();
+        self.inner
+            .groups
+            .entry(name.into())
+            .or_insert(Group::new(GroupStatus::Running, 1))
     }
 
     /// Remove a group.
@@ -147,14 +207,52 @@ impl InternalState {
             ));
         }
 
-        self.groups_mut().remove(group);
+        self.changes.get_mut().unwrap().groups.insert(group.into());
+        self.inner.groups.remove(group);
 
         Ok(())
     }
 
+    /// Create all groups that're declared in the configuration file and apply their settings.
+    /// Groups that aren't declared in the configuration file are left untouched.
+    pub fn apply_group_settings(&mut self, groups: &BTreeMap<String, GroupSettings>) {
+        for (name, group_settings) in groups {
+            if !self.groups().contains_key(name) {
+                info!("Creating group {name} from configuration");
+            }
+
+            let group = self.create_group(name);
+            group.parallel_tasks = group_settings.parallel_tasks;
+            group.priority = group_settings.priority;
+            group.pause_on_failure = group_settings.pause_on_failure;
+            group.envs = group_settings.envs.clone();
+            group.callback = group_settings.callback.clone();
+            group.working_directory = group_settings.working_directory.clone();
+            group.shell_command = group_settings.shell_command.clone();
+            group.hooks = group_settings.hooks.clone();
+        }
+    }
+
+    /// Get the working directory of a new task in the given group.
+    ///
+    /// If `path` is only the client's current directory, the group's default working directory
+    /// takes precedence.
+    pub fn task_working_directory(&self, group: &str, path: PathBuf, is_fallback: bool) -> PathBuf {
+        if !is_fallback {
+            return path;
+        }
+
+        self.groups()
+            .get(group)
+            .and_then(|group| group.working_directory.clone())
+            .unwrap_or(path)
+    }
+
     /// Set the group status (running/paused) for all groups including the default queue.
     pub fn set_status_for_all_groups(&mut self, status: GroupStatus) {
-        for (_, group) in self.groups_mut().iter_mut() {
+        let changes = self.changes.get_mut().unwrap();
+        for (name, group) in self.inner.groups.iter_mut() {
+            changes.groups.insert(name.clone());
             group.status = status;
         }
     }
@@ -231,8 +329,15 @@ impl InternalState {
     ///
     /// `group` should be the name of the failed task.
     pub fn pause_on_failure(&mut self, settings: &Settings, group: &str) {
-        if settings.daemon.pause_group_on_failure {
-            if let Some(group) = self.groups_mut().get_mut(group) {
+        // Groups may override the global `pause_group_on_failure` setting.
+        let pause_group = self
+            .groups()
+            .get(group)
+            .and_then(|group| group.pause_on_failure)
+            .unwrap_or(settings.daemon.pause_group_on_failure);
+
+        if pause_group {
+            if let Some(group) = self.group_mut(group) {
                 group.status = GroupStatus::Paused;
             }
         } else if settings.daemon.pause_all_on_failure {
@@ -241,86 +346,46 @@ impl InternalState {
     }
 
     /// Save the current state to disk. \
-    /// We do this to restore in case of a crash. \
-    /// If log == true, the file will be saved with a time stamp.
+    /// We do this to restore in case of a crash.
     ///
-    /// In comparison to the daemon -> client communication, the state is saved
-    /// as JSON for readability and debugging purposes.
+    /// Only the tasks and groups that changed since the last save are written by the configured
+    /// [StateStore].
     pub fn save(&self, settings: &Settings) -> Result<()> {
-        let serialized =
-            serde_json::to_string(&self.inner).context("Failed to serialize state:")?;
-
-        let path = settings.shared.pueue_directory();
-        let mut temp = path.join("state.json.partial");
-        let mut real = path.join("state.json");
-
-        if settings.daemon.compress_state_file {
-            temp = path.join("state.json.gz.partial");
-            real = path.join("state.json.gz");
-
-            let file = if temp.exists() {
-                File::open(&temp)?
-            } else {
-                File::create(&temp)?
-            };
-
-            let mut encoder = flate2::write::GzEncoder::new(file, Compression::default());
-            encoder.write_all(serialized.as_bytes())?;
-        } else {
-            // Write to temporary log file first, to prevent loss due to crashes.
-            std::fs::write(&temp, serialized)
-                .context("Failed to write temp file while saving state.")?;
-        }
-
-        // Overwrite the original with the temp file, if everything went fine.
-        std::fs::rename(&temp, &real)
-            .context("Failed to overwrite old state while saving state")?;
-
-        debug!("State saved at: {real:?}");
+        let mut store = self.store.lock().unwrap();
+        let mut changes = self.changes.lock().unwrap();
+        current_store(&mut store, settings).save(settings, &self.inner, &changes)?;
+        // Only forget the changes once they've been written, so they're retried otherwise.
+        *changes = Changes::default();
 
         Ok(())
     }
 
+    /// Add finished tasks to the archive of the configured [StateStore].
+    pub fn archive(&self, settings: &Settings, tasks: &[&Task]) -> Result<()> {
+        let mut store = self.store.lock().unwrap();
+        current_store(&mut store, settings).archive(settings, tasks)
+    }
+
     /// Restore the last state from a previous session. \
-    /// The state is stored as json in the `pueue_directory`.
+    /// The state is loaded from the configured [StateStore] in the `pueue_directory`.
     ///
     /// If the state cannot be deserialized, an empty default state will be used instead. \
     /// All groups with queued tasks will be automatically paused to prevent unwanted execution.
     pub fn restore_state(settings: &Settings) -> Result<Option<InternalState>> {
         let pueue_directory = settings.shared.pueue_directory();
-        let mut path = pueue_directory.join("state.json");
-
-        if settings.daemon.compress_state_file {
-            path = pueue_directory.join("state.json.gz");
-        }
-
-        // Ignore if the file doesn't exist. It doesn't have to.
-        if !path.exists() {
-            info!("Couldn't find state from previous session at location: {path:?}");
+        let Some(state) = store::restore(settings)? else {
             return Ok(None);
-        }
-        info!("Restoring state");
-
-        // Try to load the file.
-        let data = if settings.daemon.compress_state_file {
-            let file = File::open(path)?;
-            let mut decoder = flate2::read::GzDecoder::new(file);
-            let mut data = String::new();
-            decoder.read_to_string(&mut data)?;
-
-            data
-        } else {
-            read_to_string(&path).context("State restore: Failed to read file:\n\n{}")?
         };
 
-        // Try to deserialize the state file.
-        let state: State = serde_json::from_str(&data).context("Failed to deserialize state.")?;
-
         let mut state = InternalState {
             inner: state,
             ..Default::default()
         };
 
+        // Tasks that survived the restart of the daemon and which have been re-adopted.
+        #[cfg(unix)]
+        let mut adopted = Vec::new();
+
         // Restore all tasks.
         // While restoring the tasks, check for any invalid/broken stati.
         for (_, task) in state.inner.tasks.iter_mut() {
@@ -328,17 +393,30 @@ impl InternalState {
             if let TaskStatus::Running { start, enqueued_at }
             | TaskStatus::Paused { start, enqueued_at } = task.status
             {
+                #[allow(unused_mut)]
+                let mut result = TaskResult::Killed;
+
+                // Try to find supervised tasks, which might still be running.
+                #[cfg(unix)]
+                if settings.daemon.reattach_tasks {
+                    match adopt(task.id, &pueue_directory) {
+                        Adoption::Running(child) => {
+                            adopted.push((task.id, child));
+                            continue;
+                        }
+                        Adoption::Finished(task_result) => result = task_result,
+                    }
+                }
+
                 info!(
                     "Setting task {} with previous status {:?} to new status {:?}",
-                    task.id,
-                    task.status,
-                    TaskResult::Killed
+                    task.id, task.status, result
                 );
                 task.status = TaskStatus::Done {
                     start,
                     end: Local::now(),
                     enqueued_at,
-                    result: TaskResult::Killed,
+                    result,
                 };
             }
 
@@ -357,10 +435,7 @@ impl InternalState {
                         .inner
                         .groups
                         .entry(PUEUE_DEFAULT_GROUP.into())
-                        .or_insert(Group {
-                            status: GroupStatus::Running,
-                            parallel_tasks: 1,
-                        })
+                        .or_insert(Group::new(GroupStatus::Running, 1))
                 }
             };
 
@@ -375,6 +450,44 @@ impl InternalState {
             }
         }
 
+        // Put all re-adopted tasks back into their worker pools.
+        // Try to keep their previous worker id, as the task has been started with it.
+        #[cfg(unix)]
+        for (task_id, child) in adopted {
+            let task = &state.inner.tasks[&task_id];
+            let group = task.group.clone();
+            let previous_worker_id = task
+                .envs
+                .get("PUEUE_WORKER_ID")
+                .and_then(|id| id.parse::<usize>().ok());
+
+            state.children.0.entry(group.clone()).or_default();
+            let worker_id = match previous_worker_id {
+                Some(id) if !state.children.0[&group].contains_key(&id) => id,
+                _ => state.children.get_next_group_worker(&group),
+            };
+            state
+                .children
+                .add_child(&group, worker_id, task_id, TaskChild::Adopted(child));
+        }
+
         Ok(Some(state))
     }
 }
+
+/// Get the store of the configured backend.
+/// The store is (re-)created, if the backend has been changed in the meantime.
+fn current_store<'a>(
+    store: &'a mut Option<Box<dyn StateStore>>,
+    settings: &Settings,
+) -> &'a mut dyn StateStore {
+    let backend = settings.daemon.state_backend;
+    if store
+        .as_ref()
+        .is_none_or(|store| store.backend() != backend)
+    {
+        *store = Some(create_store(backend));
+    }
+
+    store.as_mut().unwrap().as_mut()
+}
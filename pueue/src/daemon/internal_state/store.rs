@@ -0,0 +1,130 @@
+//! Pluggable backends to persist the daemon's state.
+//!
+//! The backend is chosen via the `daemon.state_backend` setting. When the backend is changed, the
+//! state is restored from the previously used backend and migrated on the next save.
+use std::{collections::BTreeSet, fmt::Debug};
+
+use pueue_lib::{Settings, State, Task, settings::StateBackend};
+
+use super::journal::{self, Journal};
+#[cfg(feature = "sqlite")]
+use super::sqlite::{self, SqliteStore};
+#[cfg(feature = "sqlite")]
+use crate::daemon::archive;
+use crate::internal_prelude::*;
+
+/// A backend that persists the daemon's state.
+pub trait StateStore: Debug + Send {
+    /// The backend that's implemented by this store.
+    fn backend(&self) -> StateBackend;
+
+    /// Persist the given state.
+    ///
+    /// `changes` contains the tasks and groups that changed since the last successful save.
+    /// Stores write the full state instead, if they haven't written anything yet.
+    fn save(&mut self, settings: &Settings, state: &State, changes: &Changes) -> Result<()>;
+
+    /// Add finished tasks to the archive and apply the archive's retention policy.
+    fn archive(&mut self, settings: &Settings, tasks: &[&Task]) -> Result<()>;
+}
+
+/// The ids of the tasks and the names of the groups that have been added, changed or removed
+/// since the state has last been saved.
+#[derive(Debug, Default)]
+pub struct Changes {
+    pub tasks: BTreeSet<usize>,
+    pub groups: BTreeSet<String>,
+}
+
+/// Create a new store for the given backend.
+pub fn create_store(backend: StateBackend) -> Box<dyn StateStore> {
+    match backend {
+        StateBackend::Json => Box::new(Journal::default()),
+        #[cfg(feature = "sqlite")]
+        StateBackend::Sqlite => Box::new(SqliteStore::default()),
+        // The settings are validated on startup and reload, which rejects this backend.
+        #[cfg(not(feature = "sqlite"))]
+        StateBackend::Sqlite => unreachable!("The sqlite state backend isn't available"),
+    }
+}
+
+/// Restore the state of a previous session.
+///
+/// The configured backend is tried first. If it doesn't contain a state, the state of the other
+/// backend is used instead, which migrates it to the configured backend on the next save.
+pub fn restore(settings: &Settings) -> Result<Option<State>> {
+    let backend = settings.daemon.state_backend;
+    if let Some(state) = restore_from(settings, backend)? {
+        return Ok(Some(state));
+    }
+
+    let other = other_backend(backend);
+    let state = restore_from(settings, other)?;
+    if state.is_some() {
+        info!("Migrating state from the {other:?} backend to the {backend:?} backend");
+    }
+
+    Ok(state)
+}
+
+/// Remove all files of the backend that isn't in use anymore.
+/// The archived tasks are moved along to the given backend.
+///
+/// Stores call this after they've written the full state for the first time, so nothing can get
+/// lost while migrating. Archived tasks that have already been moved are skipped, so an
+/// interrupted migration can safely be repeated.
+#[cfg(feature = "sqlite")]
+pub fn remove_other_backend(settings: &Settings, backend: StateBackend) -> Result<()> {
+    match backend {
+        StateBackend::Json => {
+            if sqlite::database_path(settings).exists() {
+                let archived: BTreeSet<_> = archive::read_archive_file(settings)?
+                    .iter()
+                    .map(archive::archive_key)
+                    .collect();
+                let history: Vec<Task> = sqlite::read_history(settings)?
+                    .into_iter()
+                    .filter(|task| !archived.contains(&archive::archive_key(task)))
+                    .collect();
+                if !history.is_empty() {
+                    archive::append(settings, &history.iter().collect::<Vec<_>>())?;
+                }
+                sqlite::remove(settings)?;
+            }
+
+            Ok(())
+        }
+        StateBackend::Sqlite => {
+            let archive_path = archive::archive_path(settings);
+            if archive_path.exists() {
+                sqlite::import_history(settings, &archive::read_archive_file(settings)?)?;
+                std::fs::remove_file(&archive_path)?;
+            }
+            journal::remove(settings)
+        }
+    }
+}
+
+/// Without the sqlite backend, there's no other backend that could be removed.
+#[cfg(not(feature = "sqlite"))]
+pub fn remove_other_backend(_settings: &Settings, _backend: StateBackend) -> Result<()> {
+    Ok(())
+}
+
+fn restore_from(settings: &Settings, backend: StateBackend) -> Result<Option<State>> {
+    match backend {
+        StateBackend::Json => journal::restore(settings),
+        #[cfg(feature = "sqlite")]
+        StateBackend::Sqlite => sqlite::restore(settings),
+        // There's nothing that could be restored without the sqlite backend.
+        #[cfg(not(feature = "sqlite"))]
+        StateBackend::Sqlite => Ok(None),
+    }
+}
+
+fn other_backend(backend: StateBackend) -> StateBackend {
+    match backend {
+        StateBackend::Json => StateBackend::Sqlite,
+        StateBackend::Sqlite => StateBackend::Json,
+    }
+}
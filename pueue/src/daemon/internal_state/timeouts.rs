@@ -0,0 +1,67 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Local, TimeDelta};
+
+/// Bookkeeping that's needed to enforce task timeouts.
+///
+/// Time that a task spends paused doesn't count against its timeout.
+/// This is runtime state, so pauses that happened before a daemon restart aren't known
+/// afterwards and count against the timeout.
+#[derive(Debug, Default)]
+pub struct Timeouts {
+    /// The total time each task has spent paused in the past.
+    paused: BTreeMap<usize, TimeDelta>,
+    /// The point in time at which each currently paused task has been paused.
+    paused_since: BTreeMap<usize, DateTime<Local>>,
+    /// Tasks that have already been killed due to their timeout, but haven't exited yet.
+    killed: BTreeSet<usize>,
+}
+
+impl Timeouts {
+    /// Remember that a task has been paused.
+    pub fn pause(&mut self, task_id: usize) {
+        self.paused_since.entry(task_id).or_insert_with(Local::now);
+    }
+
+    /// Remember that a task has been resumed and add the paused interval to its total.
+    pub fn resume(&mut self, task_id: usize) {
+        if let Some(since) = self.paused_since.remove(&task_id) {
+            *self.paused.entry(task_id).or_default() += Local::now() - since;
+        }
+    }
+
+    /// The time the given task has been running, excluding the time it spent paused.
+    pub fn runtime(
+        &self,
+        task_id: usize,
+        start: DateTime<Local>,
+        now: DateTime<Local>,
+    ) -> TimeDelta {
+        let paused = self.paused.get(&task_id).copied().unwrap_or_default();
+        now - start - paused
+    }
+
+    /// Whether the task has already been killed due to its timeout.
+    pub fn is_killed(&self, task_id: usize) -> bool {
+        self.killed.contains(&task_id)
+    }
+
+    /// Remember that the task has been killed due to its timeout.
+    pub fn mark_killed(&mut self, task_id: usize) {
+        self.killed.insert(task_id);
+    }
+
+    /// Forget everything about the given task.
+    pub fn forget(&mut self, task_id: usize) {
+        self.paused.remove(&task_id);
+        self.paused_since.remove(&task_id);
+        self.killed.remove(&task_id);
+    }
+
+    /// Forget everything about tasks that are no longer alive.
+    pub fn retain(&mut self, alive: impl Fn(usize) -> bool) {
+        self.paused.retain(|id, _| alive(*id));
+        self.paused_since.retain(|id, _| alive(*id));
+        self.killed.retain(|id| alive(*id));
+    }
+}
@@ -10,15 +10,18 @@ use tokio::try_join;
 
 use crate::{
     daemon::{
+        callbacks::spawn_daemon_start_hooks,
         internal_state::{SharedState, state::InternalState},
         network::{
             certificate::create_certificates,
             socket::{accept_incoming, socket_cleanup},
         },
+        settings::SharedSettings,
     },
     internal_prelude::*,
 };
 
+mod archive;
 mod callbacks;
 pub mod cli;
 /// The daemon's state representation that contains process related data not exposed to clients.
@@ -28,7 +31,13 @@ mod pid;
 mod process_handler;
 #[cfg(target_os = "windows")]
 pub mod service;
+/// The daemon's settings, which can be reloaded at runtime.
+pub mod settings;
+mod snapshot;
+#[cfg(unix)]
+mod supervisor;
 pub mod task_handler;
+mod webhook;
 
 /// The main entry point for the daemon logic.
 /// It's basically the `main`, but publicly exported as a library.
@@ -56,6 +65,7 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
     if let Some(profile) = &profile {
         settings.load_profile(profile)?;
     }
+    settings::validate(&settings).context("Invalid configuration.")?;
 
     init_directories(&settings.shared.pueue_directory())?;
     if !settings.shared.daemon_key().exists() && !settings.shared.daemon_cert().exists() {
@@ -68,7 +78,7 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
     // Restore the previous state and save any changes that might have happened during this
     // process. If no previous state exists, just create a new one.
     // Create a new empty state if any errors occur, but print the error message.
-    let state = match InternalState::restore_state(&settings) {
+    let mut state = match InternalState::restore_state(&settings) {
         Ok(Some(state)) => state,
         Ok(None) => InternalState::new(),
         Err(error) => {
@@ -78,11 +88,15 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
         }
     };
 
+    // Make sure that all groups from the configuration file exist.
+    state.apply_group_settings(&settings.daemon.groups);
+
     // Save the state once at the very beginning.
     state
         .save(&settings)
         .context("Failed to save state on startup.")?;
     let state = Arc::new(Mutex::new(state));
+    spawn_daemon_start_hooks(&settings, &mut state.lock().unwrap());
 
     // Don't set ctrlc and panic handlers during testing.
     // This is necessary for multithreaded integration testing, since multiple listener per process
@@ -91,10 +105,17 @@ pub async fn run(config_path: Option<PathBuf>, profile: Option<String>, test: bo
         setup_signal_panic_handling(&settings, state.clone())?;
     }
 
+    // From here on, the settings may be reloaded at any time.
+    let settings = SharedSettings::new(settings, config_path, profile);
+    #[cfg(unix)]
+    if !test {
+        setup_reload_signal_handling(settings.clone(), state.clone())?;
+    }
+
     // Run both the task handler and the message handler in the same tokio task.
     // If any of them fails, return an error immediately.
     let task_handler = task_handler::run(state.clone(), settings.clone());
-    let message_handler = accept_incoming(settings.clone(), state.clone());
+    let message_handler = accept_incoming(settings, state.clone());
     try_join!(task_handler, message_handler).map(|_| ())
 }
 
@@ -128,6 +149,44 @@ fn init_directories(pueue_dir: &Path) -> Result<()> {
             .map_err(|err| Error::IoPathError(logs_dir, "creating task log directory", err))?;
     }
 
+    // Task supervisor dir
+    let supervisor_dir = pueue_dir.join("task_supervisor");
+    if !supervisor_dir.exists() {
+        create_dir_all(&supervisor_dir).map_err(|err| {
+            Error::IoPathError(supervisor_dir, "creating task supervisor directory", err)
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Reload the configuration file on SIGHUP.
+///
+/// The outcome of the reload is only logged, as there's nobody to respond to.
+/// Use `pueue reload` to get a report about the changed settings.
+#[cfg(unix)]
+fn setup_reload_signal_handling(settings: SharedSettings, state: SharedState) -> Result<()> {
+    use tokio::signal::unix::{SignalKind, signal};
+
+    let mut hangup = signal(SignalKind::hangup()).context("Failed to listen for SIGHUP.")?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!("Received SIGHUP, reloading configuration.");
+            match settings.reload(&state) {
+                Ok(report) => {
+                    info!("Applied changed settings: {:?}", report.applied);
+                    if !report.requires_restart.is_empty() {
+                        warn!(
+                            "Settings that require a restart: {:?}",
+                            report.requires_restart
+                        );
+                    }
+                }
+                Err(err) => error!("Failed to reload configuration:\n{err:?}"),
+            }
+        }
+    });
+
     Ok(())
 }
 
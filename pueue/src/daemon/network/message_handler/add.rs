@@ -1,5 +1,7 @@
 use chrono::Local;
-use pueue_lib::{GroupStatus, Settings, Task, TaskStatus, failure_msg, message::*};
+use pueue_lib::{
+    GroupStatus, Settings, Task, TaskStatus, failure_msg, message::*, task::DependencyCondition,
+};
 
 use crate::{
     aliasing::insert_alias,
@@ -17,7 +19,15 @@ use pueue_lib::State;
 /// Invoked when calling `pueue add`.
 /// Queues a new [Task] to the [State].
 /// If the start_immediately flag is set, send a StartMessage to the task handler.
-pub fn add_task(settings: &Settings, state: &SharedState, message: AddRequest) -> Response {
+pub fn add_task(settings: &Settings, state: &SharedState, mut message: AddRequest) -> Response {
+    // Older clients still send plain dependencies, which have to succeed.
+    for id in std::mem::take(&mut message.dependencies) {
+        message
+            .dependency_conditions
+            .entry(id)
+            .or_insert(DependencyCondition::Success);
+    }
+
     let mut state = state.lock().unwrap();
     if let Err(response) = ensure_group_exists(&mut state, &message.group) {
         return response;
@@ -25,27 +35,36 @@ pub fn add_task(settings: &Settings, state: &SharedState, message: AddRequest) -
 
     // Ensure that specified dependencies actually exist.
     let not_found: Vec<_> = message
-        .dependencies
-        .iter()
+        .dependency_conditions
+        .keys()
         .filter(|id| !state.tasks().contains_key(id))
         .collect();
     if !not_found.is_empty() {
         return failure_msg!("Unable to setup dependencies : task(s) {not_found:?} not found",);
     }
 
+    // Fall back to the group's default priority, if no priority has been specified.
+    let priority = message
+        .priority
+        .or_else(|| state.groups().get(&message.group)?.priority)
+        .unwrap_or(0);
+
+    let path = state.task_working_directory(&message.group, message.path, message.path_is_fallback);
+
     // Create a new task and add it to the state.
     let mut task = Task::new(
         message.command,
-        message.path,
+        path,
         message.envs,
         message.group,
         TaskStatus::Queued {
             enqueued_at: Local::now(),
         },
-        message.dependencies,
-        message.priority.unwrap_or(0),
+        message.dependency_conditions.keys().copied().collect(),
+        priority,
         message.label,
     );
+    task.timeout = message.timeout;
 
     // Handle if the command is to be stashed and/or automatically enqueued later.
     if message.stashed || message.enqueue_at.is_some() {
@@ -62,9 +81,12 @@ pub fn add_task(settings: &Settings, state: &SharedState, message: AddRequest) -
     // command (for instance if they made a typo and used the wrong alias).
     task.command = insert_alias(settings, task.original_command.clone());
 
-    // Sort and deduplicate dependency ids.
-    task.dependencies.sort_unstable();
-    task.dependencies.dedup();
+    // Only remember conditions that differ from the default.
+    task.dependency_conditions = message
+        .dependency_conditions
+        .into_iter()
+        .filter(|(_, condition)| *condition != DependencyCondition::default())
+        .collect();
 
     // Check if the task's group is paused before we pass it to the state
     let group_status = state
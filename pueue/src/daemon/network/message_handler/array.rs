@@ -0,0 +1,179 @@
+use chrono::Local;
+use pueue_lib::{Settings, Task, TaskStatus, failure_msg, message::*, success_msg};
+
+use crate::{
+    aliasing::insert_alias,
+    daemon::{
+        internal_state::{SharedState, state::LockedState},
+        network::{
+            message_handler::{ok_or_failure_message, restart::restart},
+            response_helper::{ensure_group_exists, task_action_response_helper},
+        },
+        process_handler,
+    },
+    ok_or_save_state_failure,
+};
+
+/// Invoked when calling `pueue array add`.
+/// Add one task per command and group them as an array.
+pub fn add_array(settings: &Settings, state: &SharedState, message: AddArrayRequest) -> Response {
+    let mut state = state.lock().unwrap();
+    if let Err(response) = ensure_group_exists(&mut state, &message.group) {
+        return response;
+    }
+
+    if message.commands.is_empty() {
+        return failure_msg!("A task array needs at least one task.");
+    }
+
+    let name = message
+        .name
+        .unwrap_or_else(|| format!("array-{}", state.next_task_id()));
+    if state
+        .tasks()
+        .values()
+        .any(|task| task.array.as_ref() == Some(&name))
+    {
+        return failure_msg!("There's already an array with the name '{name}'.");
+    }
+
+    // Fall back to the group's default priority, if no priority has been specified.
+    let priority = message
+        .priority
+        .or_else(|| state.groups().get(&message.group)?.priority)
+        .unwrap_or(0);
+
+    let path = state.task_working_directory(&message.group, message.path, message.path_is_fallback);
+
+    let mut task_ids = Vec::with_capacity(message.commands.len());
+    for (index, command) in message.commands.into_iter().enumerate() {
+        let mut envs = message.envs.clone();
+        envs.insert("PUEUE_ARRAY_INDEX".into(), index.to_string());
+
+        let mut task = Task::new(
+            command,
+            path.clone(),
+            envs,
+            message.group.clone(),
+            TaskStatus::Queued {
+                enqueued_at: Local::now(),
+            },
+            Vec::new(),
+            priority,
+            Some(name.clone()),
+        );
+        if message.stashed {
+            task.status = TaskStatus::Stashed { enqueue_at: None };
+        }
+        task.command = insert_alias(settings, task.original_command.clone());
+        task.timeout = message.timeout;
+        task.array = Some(name.clone());
+
+        task_ids.push(state.add_task(task));
+    }
+
+    ok_or_save_state_failure!(state.save(settings));
+
+    AddedArrayResponse { name, task_ids }.into()
+}
+
+/// Invoked when calling `pueue array kill`.
+/// The queued members are stashed and the running members are killed under the same lock,
+/// so no member can start while the others are being killed.
+pub fn kill_array(settings: &Settings, state: &SharedState, name: String) -> Response {
+    let mut state = state.lock().unwrap();
+    let members = match array_members(&state, &name) {
+        Ok(members) => members,
+        Err(response) => return response,
+    };
+
+    let mut running = Vec::new();
+    for task_id in members {
+        let task = state.task_mut(task_id).unwrap();
+        match task.status {
+            TaskStatus::Queued { .. } | TaskStatus::Locked { .. } => {
+                task.status = TaskStatus::Stashed { enqueue_at: None };
+            }
+            TaskStatus::Running { .. } | TaskStatus::Paused { .. } => running.push(task_id),
+            _ => (),
+        }
+    }
+
+    let response = if running.is_empty() {
+        success_msg!("No running tasks in array '{name}'.")
+    } else {
+        let response = task_action_response_helper(
+            "Tasks are being killed",
+            running.clone(),
+            Task::is_running,
+            &state,
+        );
+        process_handler::kill::kill(
+            settings,
+            &mut state,
+            TaskSelection::TaskIds(running),
+            true,
+            None,
+        );
+        response
+    };
+
+    ok_or_save_state_failure!(state.save(settings));
+
+    response
+}
+
+/// Invoked when calling `pueue array restart`.
+/// Restart all failed members in place, so they stay part of the array.
+pub fn restart_array(settings: &Settings, state: &SharedState, name: String) -> Response {
+    let mut state = state.lock().unwrap();
+    let members = match array_members(&state, &name) {
+        Ok(members) => members,
+        Err(response) => return response,
+    };
+
+    let failed: Vec<TaskToRestart> = members
+        .iter()
+        .filter_map(|task_id| state.tasks().get(task_id))
+        .filter(|task| task.failed())
+        .map(|task| TaskToRestart {
+            task_id: task.id,
+            original_command: task.original_command.clone(),
+            path: task.path.clone(),
+            label: task.label.clone(),
+            priority: task.priority,
+        })
+        .collect();
+    if failed.is_empty() {
+        return success_msg!("No failed tasks in array '{name}'.");
+    }
+
+    let response = task_action_response_helper(
+        "Tasks has restarted",
+        failed.iter().map(|task| task.task_id).collect(),
+        Task::is_done,
+        &state,
+    );
+    for task in failed {
+        restart(&mut state, task, false, settings);
+    }
+
+    ok_or_save_state_failure!(state.save(settings));
+
+    response
+}
+
+/// Get the ids of all tasks of an array.
+fn array_members(state: &LockedState, name: &str) -> Result<Vec<usize>, Response> {
+    let members: Vec<usize> = state
+        .tasks()
+        .values()
+        .filter(|task| task.array.as_deref() == Some(name))
+        .map(|task| task.id)
+        .collect();
+    if members.is_empty() {
+        return Err(failure_msg!("There's no array with the name '{name}'"));
+    }
+
+    Ok(members)
+}
@@ -1,7 +1,14 @@
-use pueue_lib::{TaskResult, TaskStatus, log::clean_log_handles, message::*};
+use pueue_lib::{Task, TaskResult, TaskStatus, log::clean_log_handles, message::*};
 
 use super::*;
-use crate::{daemon::internal_state::SharedState, ok_or_save_state_failure};
+use crate::{
+    daemon::{
+        archive::archive_tasks,
+        internal_state::{SharedState, state::LockedState},
+        snapshot::take_snapshot,
+    },
+    ok_or_save_state_failure,
+};
 
 fn construct_success_clean_message(message: CleanRequest) -> String {
     let successful_only_fix = if message.successful_only {
@@ -23,44 +30,63 @@ fn construct_success_clean_message(message: CleanRequest) -> String {
 pub fn clean(settings: &Settings, state: &SharedState, message: CleanRequest) -> Response {
     let mut state = state.lock().unwrap();
 
+    let cleaned = clean_tasks(settings, &mut state, |task| {
+        // Check if we should ignore this task, if only successful tasks should be removed.
+        if message.successful_only
+            && !matches!(
+                task.status,
+                TaskStatus::Done {
+                    result: TaskResult::Success,
+                    ..
+                }
+            )
+        {
+            return false;
+        }
+
+        // User's can specify a specific group to be cleaned.
+        // Skip the task if that's the case and the task's group doesn't match.
+        message.group.is_none() || message.group.as_deref() == Some(&task.group)
+    });
+    take_snapshot(settings, &state, "clean", cleaned.clone(), &[]);
+
+    ok_or_save_state_failure!(state.save(settings));
+    archive_tasks(settings, &state, &cleaned);
+
+    create_success_response(construct_success_clean_message(message))
+}
+
+/// Remove all finished tasks that match the filter, including their logs.
+///
+/// Tasks that unfinished tasks depend on are kept.
+/// The state isn't saved and the removed tasks aren't archived, this is up to the caller.
+///
+/// Returns the removed tasks.
+pub fn clean_tasks(
+    settings: &Settings,
+    state: &mut LockedState,
+    filter: impl Fn(&Task) -> bool,
+) -> Vec<Task> {
     let filtered_tasks =
         state.filter_tasks(|task| matches!(task.status, TaskStatus::Done { .. }), None);
 
+    let mut cleaned = Vec::new();
     for task_id in &filtered_tasks.matching_ids {
         // Ensure the task is removable, i.e. there are no dependant tasks.
         if !state.is_task_removable(task_id, &[]) {
             continue;
         }
 
-        if message.successful_only || message.group.is_some() {
-            if let Some(task) = state.tasks().get(task_id) {
-                // Check if we should ignore this task, if only successful tasks should be removed.
-                if message.successful_only
-                    && !matches!(
-                        task.status,
-                        TaskStatus::Done {
-                            result: TaskResult::Success,
-                            ..
-                        }
-                    )
-                {
-                    continue;
-                }
-
-                // User's can specify a specific group to be cleaned.
-                // Skip the task if that's the case and the task's group doesn't match.
-                if message.group.is_some() && message.group.as_deref() != Some(&task.group) {
-                    continue;
-                }
-            }
+        if !state.tasks().get(task_id).is_some_and(&filter) {
+            continue;
         }
-        let _ = state.tasks_mut().remove(task_id).unwrap();
+
+        let task = state.remove_task(*task_id).unwrap();
         clean_log_handles(*task_id, &settings.shared.pueue_directory());
+        cleaned.push(task);
     }
 
-    ok_or_save_state_failure!(state.save(settings));
-
-    create_success_response(construct_success_clean_message(message))
+    cleaned
 }
 
 #[cfg(test)]
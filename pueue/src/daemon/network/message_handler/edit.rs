@@ -13,7 +13,7 @@ pub fn edit_request(state: &SharedState, task_ids: Vec<usize>) -> Response {
     let mut state = state.lock().unwrap();
     let mut editable_tasks: Vec<EditableTask> = Vec::new();
     for task_id in task_ids {
-        match state.tasks_mut().get_mut(&task_id) {
+        match state.task_mut(task_id) {
             Some(task) => {
                 if !task.is_queued() && !task.is_stashed() {
                     return create_failure_response("You can only edit a queued/stashed task");
@@ -41,7 +41,7 @@ pub fn edit(
     // Check whether the task exists and is locked. Abort if that's not the case.
     let mut state = state.lock().unwrap();
     for editable_task in editable_tasks {
-        match state.tasks_mut().get_mut(&editable_task.id) {
+        match state.task_mut(editable_task.id) {
             Some(task) => {
                 let TaskStatus::Locked { previous_status } = &task.status else {
                     return create_failure_response(format!(
@@ -75,7 +75,7 @@ pub fn edit_restore(state: &SharedState, task_ids: Vec<usize>) -> Response {
     let mut state = state.lock().unwrap();
     let mut failed_tasks = Vec::new();
     for task_id in &task_ids {
-        match state.tasks_mut().get_mut(task_id) {
+        match state.task_mut(*task_id) {
             Some(task) => {
                 let TaskStatus::Locked { previous_status } = &task.status else {
                     failed_tasks.push(format!("Task {} isn't locked! Cannot be unlocked", task_id));
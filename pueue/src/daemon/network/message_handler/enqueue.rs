@@ -13,8 +13,8 @@ pub fn enqueue(settings: &Settings, state: &SharedState, message: EnqueueRequest
     // Get the affected task ids, based on the task selection.
     let selected_tasks = match message.tasks {
         TaskSelection::TaskIds(ref task_ids) => state
-            .tasks_mut()
-            .iter_mut()
+            .tasks()
+            .iter()
             .filter(|(task_id, task)| {
                 if !task_ids.contains(task_id) {
                     return false;
@@ -25,10 +25,11 @@ pub fn enqueue(settings: &Settings, state: &SharedState, message: EnqueueRequest
                     TaskStatus::Stashed { .. } | TaskStatus::Locked { .. }
                 )
             })
-            .collect::<Vec<(&usize, &mut Task)>>(),
+            .map(|(task_id, _)| *task_id)
+            .collect::<Vec<usize>>(),
         TaskSelection::Group(ref group) => state
-            .tasks_mut()
-            .iter_mut()
+            .tasks()
+            .iter()
             .filter(|(_, task)| {
                 if task.group != *group {
                     return false;
@@ -39,20 +40,23 @@ pub fn enqueue(settings: &Settings, state: &SharedState, message: EnqueueRequest
                     TaskStatus::Stashed { .. } | TaskStatus::Locked { .. }
                 )
             })
-            .collect::<Vec<(&usize, &mut Task)>>(),
+            .map(|(task_id, _)| *task_id)
+            .collect::<Vec<usize>>(),
         TaskSelection::All => state
-            .tasks_mut()
-            .iter_mut()
+            .tasks()
+            .iter()
             .filter(|(_, task)| {
                 matches!(
                     task.status,
                     TaskStatus::Stashed { .. } | TaskStatus::Locked { .. }
                 )
             })
-            .collect::<Vec<(&usize, &mut Task)>>(),
+            .map(|(task_id, _)| *task_id)
+            .collect::<Vec<usize>>(),
     };
 
-    for (_, task) in selected_tasks {
+    for task_id in selected_tasks {
+        let task = state.task_mut(task_id).unwrap();
         // Either specify the point of time the task should be enqueued or enqueue the task
         // immediately.
         if message.enqueue_at.is_some() {
@@ -18,7 +18,7 @@ pub fn env(settings: &Settings, state: &SharedState, message: EnvRequest) -> Res
             key,
             value,
         } => {
-            let Some(task) = state.tasks_mut().get_mut(&task_id) else {
+            let Some(task) = state.task_mut(task_id) else {
                 return create_failure_response(format!("No task with id {task_id}"));
             };
 
@@ -31,7 +31,7 @@ pub fn env(settings: &Settings, state: &SharedState, message: EnvRequest) -> Res
             create_success_response("Environment variable set.")
         }
         EnvRequest::Unset { task_id, key } => {
-            let Some(task) = state.tasks_mut().get_mut(&task_id) else {
+            let Some(task) = state.task_mut(task_id) else {
                 return create_failure_response(format!("No task with id {task_id}"));
             };
 
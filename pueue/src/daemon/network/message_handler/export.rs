@@ -0,0 +1,193 @@
+use std::{collections::BTreeMap, io::Read};
+
+use chrono::Local;
+use pueue_lib::{
+    GroupStatus, Settings, TaskStatus, failure_msg,
+    log::{get_log_file_handle, get_log_path},
+    message::*,
+};
+
+use crate::{
+    daemon::{
+        internal_state::{SharedState, state::LockedState},
+        network::{message_handler::ok_or_failure_message, response_helper::ensure_group_exists},
+    },
+    internal_prelude::*,
+    ok_or_save_state_failure,
+};
+
+/// Invoked when calling `pueue export`.
+/// Return the selected tasks, the groups they belong to and optionally their logs.
+pub fn export(settings: &Settings, state: &SharedState, message: ExportRequest) -> Response {
+    let mut state = state.lock().unwrap();
+
+    let task_ids: Vec<usize> = match message.tasks {
+        TaskSelection::TaskIds(task_ids) => {
+            let unknown: Vec<usize> = task_ids
+                .iter()
+                .filter(|id| !state.tasks().contains_key(id))
+                .copied()
+                .collect();
+            if !unknown.is_empty() {
+                return failure_msg!("Tasks don't exist: {unknown:?}");
+            }
+            task_ids
+        }
+        TaskSelection::Group(group) => {
+            if let Err(response) = ensure_group_exists(&mut state, &group) {
+                return response;
+            }
+            state.task_ids_in_group(&group)
+        }
+        TaskSelection::All => state.tasks().keys().copied().collect(),
+    };
+
+    let mut export = TaskExport::default();
+    let pueue_directory = settings.shared.pueue_directory();
+    for task_id in task_ids {
+        let task = state.tasks()[&task_id].clone();
+        if let Some(group) = state.groups().get(&task.group) {
+            export.groups.insert(task.group.clone(), group.clone());
+        }
+
+        // Tasks that never ran don't have any log output.
+        if message.logs {
+            if let Ok(mut file) = get_log_file_handle(task_id, &pueue_directory) {
+                let mut output = Vec::new();
+                if let Err(err) = file.read_to_end(&mut output) {
+                    warn!("Failed to read log of task {task_id} for export: {err}");
+                }
+                export
+                    .logs
+                    .insert(task_id, String::from_utf8_lossy(&output).to_string());
+            }
+        }
+
+        export.tasks.push(task);
+    }
+
+    Response::Export(Box::new(export))
+}
+
+/// Invoked when calling `pueue import`.
+/// Recreate exported tasks with new ids and remap their dependencies.
+///
+/// Unfinished tasks are stashed, unless they should be enqueued right away.
+pub fn import(settings: &Settings, state: &SharedState, message: ImportRequest) -> Response {
+    let mut state = state.lock().unwrap();
+    let ImportRequest {
+        mut export,
+        enqueue,
+    } = message;
+
+    if export.tasks.is_empty() {
+        return failure_msg!("The export doesn't contain any tasks.");
+    }
+
+    for task in export.tasks.iter_mut() {
+        match task.status {
+            TaskStatus::Done { .. } | TaskStatus::Stashed { .. } => (),
+            _ if enqueue => {
+                task.status = TaskStatus::Queued {
+                    enqueued_at: Local::now(),
+                }
+            }
+            _ => task.status = TaskStatus::Stashed { enqueue_at: None },
+        }
+    }
+
+    let response = match recreate_tasks(settings, &mut state, export) {
+        Ok(response) => response,
+        Err(response) => return response,
+    };
+    ok_or_save_state_failure!(state.save(settings));
+
+    response.into()
+}
+
+/// Add the tasks of an export to the state.
+/// The tasks get new ids and their dependencies are remapped accordingly.
+/// Their status is left untouched.
+///
+/// Missing groups are created with the configuration they had when they were exported.
+/// Returns the new task ids by the ids the tasks had in the export and all dependencies that
+/// had to be dropped, as they point to tasks that aren't part of the export.
+pub fn recreate_tasks(
+    settings: &Settings,
+    state: &mut LockedState,
+    export: TaskExport,
+) -> Result<ImportedResponse, Response> {
+    // Make sure every task ends up in a group, before anything is changed.
+    for task in &export.tasks {
+        if !state.groups().contains_key(&task.group) && !export.groups.contains_key(&task.group) {
+            return Err(failure_msg!(
+                "Group {} of task {} doesn't exist and isn't part of the export.",
+                task.group,
+                task.id
+            ));
+        }
+    }
+
+    for (name, mut group) in export.groups {
+        if state.groups().contains_key(&name) {
+            continue;
+        }
+        if group.status == GroupStatus::Reset {
+            group.status = GroupStatus::Running;
+        }
+        *state.create_group(&name) = group;
+    }
+
+    // Add the tasks in the order of their old ids, so the relative order of the tasks is kept.
+    let mut tasks = export.tasks;
+    tasks.sort_by_key(|task| task.id);
+    let pueue_directory = settings.shared.pueue_directory();
+    let mut task_ids = BTreeMap::new();
+    for task in tasks {
+        let old_id = task.id;
+        let new_id = state.add_task(task);
+        task_ids.insert(old_id, new_id);
+
+        if let Some(output) = export.logs.get(&old_id) {
+            let path = get_log_path(new_id, &pueue_directory);
+            if let Err(err) = std::fs::write(&path, output) {
+                warn!("Failed to write log of recreated task {new_id}: {err}");
+            }
+        }
+    }
+
+    // Point the dependencies to the new ids, now that all tasks have one.
+    let mut dropped_dependencies = BTreeMap::new();
+    for (old_id, new_id) in &task_ids {
+        let task = state.task_mut(*new_id).unwrap();
+
+        // Dependencies on tasks that haven't been exported cannot be kept.
+        let dropped: Vec<usize> = task
+            .dependencies
+            .iter()
+            .filter(|id| !task_ids.contains_key(id))
+            .copied()
+            .collect();
+        if !dropped.is_empty() {
+            info!("Dropping dependencies {dropped:?} of recreated task {old_id}");
+            dropped_dependencies.insert(*old_id, dropped);
+        }
+
+        task.dependencies = task
+            .dependencies
+            .iter()
+            .filter_map(|id| task_ids.get(id).copied())
+            .collect();
+        task.dependencies.sort_unstable();
+        task.dependency_conditions = task
+            .dependency_conditions
+            .iter()
+            .filter_map(|(id, condition)| Some((*task_ids.get(id)?, *condition)))
+            .collect();
+    }
+
+    Ok(ImportedResponse {
+        task_ids,
+        dropped_dependencies,
+    })
+}
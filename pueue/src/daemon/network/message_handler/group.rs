@@ -1,5 +1,3 @@
-use std::collections::BTreeMap;
-
 use pueue_lib::{
     failure_msg, message::*, settings::Settings, state::PUEUE_DEFAULT_GROUP, success_msg,
 };
@@ -9,8 +7,10 @@ use crate::{
         internal_state::SharedState,
         network::{message_handler::ok_or_failure_message, response_helper::ensure_group_exists},
         process_handler::initiate_shutdown,
+        snapshot::take_snapshot,
     },
     ok_or_save_state_failure,
+    process_helper::validate_shell_command,
 };
 
 /// Invoked on `pueue groups`.
@@ -18,6 +18,7 @@ use crate::{
 /// - Show groups
 /// - Add group
 /// - Remove group
+/// - Edit the task defaults of a group
 pub fn group(settings: &Settings, state: &SharedState, message: GroupRequest) -> Response {
     let mut state = state.lock().unwrap();
 
@@ -41,8 +42,6 @@ pub fn group(settings: &Settings, state: &SharedState, message: GroupRequest) ->
             if let Some(parallel_tasks) = parallel_tasks {
                 group.parallel_tasks = parallel_tasks;
             }
-            // Create the worker pool.
-            state.children.0.insert(name.clone(), BTreeMap::new());
 
             // Persist the state.
             ok_or_save_state_failure!(state.save(settings));
@@ -81,6 +80,13 @@ pub fn group(settings: &Settings, state: &SharedState, message: GroupRequest) ->
                 );
             }
 
+            take_snapshot(
+                settings,
+                &state,
+                "group remove",
+                Vec::new(),
+                std::slice::from_ref(&group),
+            );
             if let Err(error) = state.remove_group(&group) {
                 return failure_msg!("Error while removing group: \"{error}\"");
             }
@@ -93,5 +99,42 @@ pub fn group(settings: &Settings, state: &SharedState, message: GroupRequest) ->
 
             success_msg!("Group \"{group}\" has been removed")
         }
+        GroupRequest::Edit(edit) => {
+            if let Err(message) = ensure_group_exists(&mut state, &edit.name) {
+                return message;
+            }
+
+            if let Some(shell_command) = &edit.shell_command {
+                if let Err(error) = validate_shell_command(shell_command) {
+                    return failure_msg!("Invalid shell command: {error}");
+                }
+            }
+
+            let group = state
+                .group_mut(&edit.name)
+                .expect("We ensured that the group exists.");
+
+            group.envs.extend(edit.set_envs);
+            for key in edit.unset_envs {
+                group.envs.remove(&key);
+            }
+
+            if edit.clear_working_directory {
+                group.working_directory = None;
+            } else if let Some(working_directory) = edit.working_directory {
+                group.working_directory = Some(working_directory);
+            }
+
+            if edit.clear_shell_command {
+                group.shell_command = None;
+            } else if let Some(shell_command) = edit.shell_command {
+                group.shell_command = Some(shell_command);
+            }
+
+            // Persist the state.
+            ok_or_save_state_failure!(state.save(settings));
+
+            success_msg!("Group \"{}\" has been updated", edit.name)
+        }
     }
 }
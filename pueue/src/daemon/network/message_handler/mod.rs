@@ -7,26 +7,34 @@ use pueue_lib::{
 };
 
 use crate::{
-    daemon::{internal_state::SharedState, process_handler::initiate_shutdown},
+    daemon::{
+        archive::read_archive, internal_state::SharedState, process_handler::initiate_shutdown,
+        settings::SharedSettings,
+    },
     internal_prelude::*,
 };
 
 mod add;
-mod clean;
+mod array;
+pub mod clean;
 mod edit;
 mod enqueue;
 mod env;
+mod export;
 mod group;
 mod kill;
 mod log;
 mod parallel;
 mod pause;
+mod reload;
 mod remove;
 mod reset;
 mod restart;
 mod send;
+mod snapshot;
 mod start;
 mod stash;
+mod submit;
 mod switch;
 
 pub use log::follow_log;
@@ -35,8 +43,11 @@ pub async fn handle_request(
     stream: &mut GenericStream,
     request: Request,
     state: &SharedState,
-    settings: &Settings,
+    shared_settings: &SharedSettings,
 ) -> Result<()> {
+    // Use the same snapshot of the settings for the whole request.
+    let settings = &shared_settings.current();
+
     let response = match request {
         // The client requested the output of a task.
         // Since this involves streaming content, we have to do some special handling.
@@ -60,23 +71,33 @@ pub async fn handle_request(
             return Ok(());
         }
         Request::Add(message) => add::add_task(settings, state, message),
+        Request::AddArray(message) => array::add_array(settings, state, message),
+        Request::KillArray(name) => array::kill_array(settings, state, name),
+        Request::RestartArray(name) => array::restart_array(settings, state, name),
+        Request::Callbacks => get_callbacks(state),
+        Request::History => get_history(settings),
         Request::Clean(message) => clean::clean(settings, state, message),
         Request::EditedTasks(editable_tasks) => edit::edit(settings, state, editable_tasks),
         Request::EditRequest(task_ids) => edit::edit_request(state, task_ids),
         Request::EditRestore(task_ids) => edit::edit_restore(state, task_ids),
         Request::Env(message) => env::env(settings, state, message),
+        Request::Export(message) => export::export(settings, state, message),
         Request::Enqueue(message) => enqueue::enqueue(settings, state, message),
         Request::Group(message) => group::group(settings, state, message),
+        Request::Import(message) => export::import(settings, state, message),
         Request::Kill(message) => kill::kill(settings, state, message),
         Request::Log(message) => log::get_log(settings, state, message),
         Request::Parallel(message) => parallel::set_parallel_tasks(message, state),
+        Request::ReloadConfig => reload::reload(shared_settings, state),
         Request::Pause(message) => pause::pause(settings, state, message),
         Request::Remove(task_ids) => remove::remove(settings, state, task_ids),
         Request::Reset(message) => reset::reset(settings, state, message),
         Request::Restart(message) => restart::restart_multiple(settings, state, message),
         Request::Send(message) => send::send(state, message),
+        Request::Snapshot(message) => snapshot::snapshot(settings, state, message),
         Request::Start(message) => start::start(settings, state, message),
         Request::Stash(message) => stash::stash(settings, state, message),
+        Request::Submit(message) => submit::submit(settings, state, message),
         Request::Switch(message) => switch::switch(settings, state, message),
         Request::Status => get_status(state),
     };
@@ -93,6 +114,25 @@ fn get_status(state: &SharedState) -> Response {
     Response::Status(Box::new(state.inner))
 }
 
+/// Invoked when calling `pueue callbacks`.
+/// Return the most recently finished callbacks.
+fn get_callbacks(state: &SharedState) -> Response {
+    let state = state.lock().unwrap();
+    CallbacksResponse {
+        runs: state.callback_runs.iter().cloned().collect(),
+    }
+    .into()
+}
+
+/// Invoked when calling `pueue history`.
+/// Return all tasks of the archive.
+fn get_history(settings: &Settings) -> Response {
+    match read_archive(settings) {
+        Ok(tasks) => HistoryResponse { tasks }.into(),
+        Err(err) => failure_msg!("Failed to read the archive: {err:?}"),
+    }
+}
+
 fn ok_or_failure_message<T, E: Display>(result: Result<T, E>) -> Result<T, Response> {
     match result {
         Ok(inner) => Ok(inner),
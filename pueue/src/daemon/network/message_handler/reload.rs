@@ -0,0 +1,12 @@
+use pueue_lib::{failure_msg, message::*};
+
+use crate::daemon::{internal_state::SharedState, settings::SharedSettings};
+
+/// Invoked when calling `pueue reload`.
+/// Re-read the configuration file and swap the new settings into the running daemon.
+pub fn reload(settings: &SharedSettings, state: &SharedState) -> Response {
+    match settings.reload(state) {
+        Ok(response) => response.into(),
+        Err(err) => failure_msg!("Failed to reload configuration:\n{err:?}"),
+    }
+}
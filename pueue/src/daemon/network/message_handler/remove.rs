@@ -2,7 +2,10 @@ use pueue_lib::{Settings, Task, TaskStatus, log::clean_log_handles, message::*};
 
 use super::ok_or_failure_message;
 use crate::{
-    daemon::{internal_state::SharedState, network::response_helper::*},
+    daemon::{
+        archive::archive_tasks, internal_state::SharedState, network::response_helper::*,
+        snapshot::take_snapshot,
+    },
     ok_or_save_state_failure,
 };
 
@@ -33,13 +36,20 @@ pub fn remove(settings: &Settings, state: &SharedState, task_ids: Vec<usize>) ->
         };
     }
 
+    let mut removed = Vec::new();
     for task_id in &filtered_tasks.matching_ids {
-        state.tasks_mut().remove(task_id);
+        if let Some(task) = state.remove_task(*task_id) {
+            removed.push(task);
+        }
 
         clean_log_handles(*task_id, &settings.shared.pueue_directory());
     }
 
+    take_snapshot(settings, &state, "remove", removed.clone(), &[]);
+
     ok_or_save_state_failure!(state.save(settings));
+    // Keep a record of removed tasks that have actually run.
+    archive_tasks(settings, &state, &removed);
 
     compile_task_response("Tasks removed from list", filtered_tasks)
 }
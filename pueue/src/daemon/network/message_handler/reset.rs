@@ -1,6 +1,6 @@
 use pueue_lib::{GroupStatus, Settings, failure_msg, message::*};
 
-use crate::daemon::{internal_state::SharedState, process_handler};
+use crate::daemon::{internal_state::SharedState, process_handler, snapshot::take_snapshot};
 
 /// Invoked when calling `pueue reset`.
 /// Kill all children by using the `kill` function.
@@ -10,10 +10,11 @@ pub fn reset(settings: &Settings, state: &SharedState, message: ResetRequest) ->
 
     match message.target {
         ResetTarget::All => {
+            let tasks = state.tasks().values().cloned().collect();
+            take_snapshot(settings, &state, "reset", tasks, &[]);
+
             // Mark all groups to be reset and kill all tasks
-            for (_name, group) in state.groups_mut().iter_mut() {
-                group.status = GroupStatus::Reset;
-            }
+            state.set_status_for_all_groups(GroupStatus::Reset);
             process_handler::kill::kill(settings, &mut state, TaskSelection::All, false, None);
         }
         ResetTarget::Groups(groups) => {
@@ -25,9 +26,17 @@ pub fn reset(settings: &Settings, state: &SharedState, message: ResetRequest) ->
                 }
             }
 
+            let tasks = state
+                .tasks()
+                .values()
+                .filter(|task| groups.contains(&task.group))
+                .cloned()
+                .collect();
+            take_snapshot(settings, &state, "reset", tasks, &[]);
+
             // Mark all groups to be reset and kill its tasks
             for name in groups.iter() {
-                let group = state.groups_mut().get_mut(name).unwrap();
+                let group = state.group_mut(name).unwrap();
                 group.status = GroupStatus::Reset;
 
                 process_handler::kill::kill(
@@ -49,9 +49,14 @@ pub fn restart_multiple(
 ///
 /// The "not in-place" restart functionality is actually just a copy the finished task + create a
 /// new task, which is completely handled on the client-side.
-fn restart(state: &mut LockedState, to_restart: TaskToRestart, stashed: bool, settings: &Settings) {
+pub fn restart(
+    state: &mut LockedState,
+    to_restart: TaskToRestart,
+    stashed: bool,
+    settings: &Settings,
+) {
     // Check if we actually know this task.
-    let Some(task) = state.tasks_mut().get_mut(&to_restart.task_id) else {
+    let Some(task) = state.task_mut(to_restart.task_id) else {
         return;
     };
 
@@ -19,7 +19,11 @@ pub fn send(state: &SharedState, message: SendRequest) -> Response {
         }
     };
     {
-        let child_stdin = child.inner().stdin.as_mut().unwrap();
+        let Some(child_stdin) = child.stdin() else {
+            return failure_msg!(
+                "Task {task_id} has been re-adopted after a daemon restart and has no stdin."
+            );
+        };
         if let Err(err) = child_stdin.write_all(&message.input.into_bytes()) {
             return failure_msg!("Failed to send input to task {task_id} with err {err:?}");
         };
@@ -0,0 +1,60 @@
+use pueue_lib::{Settings, TaskStatus, failure_msg, message::*};
+
+use super::{export::recreate_tasks, ok_or_failure_message};
+use crate::{
+    daemon::{
+        internal_state::SharedState,
+        snapshot::{read_snapshots, remove_snapshot},
+    },
+    internal_prelude::*,
+    ok_or_save_state_failure,
+};
+
+/// Invoked when calling `pueue snapshot` or `pueue undo`.
+pub fn snapshot(settings: &Settings, state: &SharedState, message: SnapshotRequest) -> Response {
+    // Hold the lock for the whole operation, so no snapshot is taken or restored in between.
+    let mut state = state.lock().unwrap();
+    let snapshots = match read_snapshots(settings) {
+        Ok(snapshots) => snapshots,
+        Err(err) => return failure_msg!("Failed to read snapshots: {err:?}"),
+    };
+
+    let id = match message {
+        SnapshotRequest::List => {
+            return SnapshotsResponse {
+                snapshots: snapshots.iter().map(SnapshotInfo::from).collect(),
+            }
+            .into();
+        }
+        SnapshotRequest::Restore(id) => id,
+    };
+
+    let snapshot = match id {
+        Some(id) => snapshots.into_iter().find(|snapshot| snapshot.id == id),
+        None => snapshots.into_iter().last(),
+    };
+    let Some(mut snapshot) = snapshot else {
+        return match id {
+            Some(id) => failure_msg!("Snapshot {id} doesn't exist."),
+            None => failure_msg!("There are no snapshots to restore."),
+        };
+    };
+
+    // All tasks come back as stashed tasks, so nothing is executed by accident.
+    for task in snapshot.export.tasks.iter_mut() {
+        task.status = TaskStatus::Stashed { enqueue_at: None };
+    }
+
+    let response = match recreate_tasks(settings, &mut state, snapshot.export) {
+        Ok(response) => response,
+        Err(response) => return response,
+    };
+    ok_or_save_state_failure!(state.save(settings));
+
+    // Each snapshot can only be restored once.
+    if let Err(err) = remove_snapshot(settings, snapshot.id) {
+        warn!("Failed to remove restored snapshot: {err:?}");
+    }
+
+    response.into()
+}
@@ -1,4 +1,4 @@
-use pueue_lib::{Settings, Task, TaskStatus, message::*, success_msg};
+use pueue_lib::{Settings, TaskStatus, message::*, success_msg};
 
 use crate::{
     daemon::{internal_state::SharedState, network::response_helper::*},
@@ -13,8 +13,8 @@ pub fn stash(settings: &Settings, state: &SharedState, message: StashRequest) ->
     // Get the affected task ids, based on the task selection.
     let selected_tasks = match message.tasks {
         TaskSelection::TaskIds(ref task_ids) => state
-            .tasks_mut()
-            .iter_mut()
+            .tasks()
+            .iter()
             .filter(|(task_id, task)| {
                 if !task_ids.contains(task_id) {
                     return false;
@@ -25,10 +25,11 @@ pub fn stash(settings: &Settings, state: &SharedState, message: StashRequest) ->
                     TaskStatus::Queued { .. } | TaskStatus::Locked { .. }
                 )
             })
-            .collect::<Vec<(&usize, &mut Task)>>(),
+            .map(|(task_id, _)| *task_id)
+            .collect::<Vec<usize>>(),
         TaskSelection::Group(ref group) => state
-            .tasks_mut()
-            .iter_mut()
+            .tasks()
+            .iter()
             .filter(|(_, task)| {
                 if task.group != *group {
                     return false;
@@ -39,20 +40,23 @@ pub fn stash(settings: &Settings, state: &SharedState, message: StashRequest) ->
                     TaskStatus::Queued { .. } | TaskStatus::Locked { .. }
                 )
             })
-            .collect::<Vec<(&usize, &mut Task)>>(),
+            .map(|(task_id, _)| *task_id)
+            .collect::<Vec<usize>>(),
         TaskSelection::All => state
-            .tasks_mut()
-            .iter_mut()
+            .tasks()
+            .iter()
             .filter(|(_, task)| {
                 matches!(
                     task.status,
                     TaskStatus::Queued { .. } | TaskStatus::Locked { .. }
                 )
             })
-            .collect::<Vec<(&usize, &mut Task)>>(),
+            .map(|(task_id, _)| *task_id)
+            .collect::<Vec<usize>>(),
     };
 
-    for (_, task) in selected_tasks {
+    for task_id in selected_tasks {
+        let task = state.task_mut(task_id).unwrap();
         task.status = TaskStatus::Stashed {
             enqueue_at: message.enqueue_at,
         };
@@ -0,0 +1,201 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use chrono::Local;
+use pueue_lib::{Settings, Task, TaskStatus, failure_msg, message::*, task::DependencyCondition};
+
+use crate::{
+    aliasing::insert_alias,
+    daemon::{
+        internal_state::SharedState,
+        network::{message_handler::ok_or_failure_message, response_helper::ensure_group_exists},
+    },
+    ok_or_save_state_failure,
+};
+
+/// Invoked when calling `pueue submit`.
+/// Add a whole graph of tasks to the state.
+///
+/// The graph is validated up front, so either all tasks are added or none of them.
+pub fn submit(settings: &Settings, state: &SharedState, message: SubmitRequest) -> Response {
+    let mut state = state.lock().unwrap();
+
+    if message.tasks.is_empty() {
+        return failure_msg!("The submitted workflow doesn't contain any tasks.");
+    }
+
+    for (name, task) in &message.tasks {
+        if let Err(response) = ensure_group_exists(&mut state, &task.group) {
+            return response;
+        }
+
+        let unknown: Vec<&String> = task
+            .dependencies
+            .keys()
+            .filter(|dependency| !message.tasks.contains_key(*dependency))
+            .collect();
+        if !unknown.is_empty() {
+            return failure_msg!("Task '{name}' depends on unknown task(s) {unknown:?}");
+        }
+    }
+
+    let order = match topological_order(&message.tasks) {
+        Ok(order) => order,
+        Err(cycle) => {
+            return failure_msg!("The workflow contains a dependency cycle between {cycle:?}");
+        }
+    };
+
+    // Tasks are added in topological order, which means that every dependency has been assigned
+    // an id before its dependants are created.
+    let mut task_ids: BTreeMap<String, usize> = BTreeMap::new();
+    let mut tasks = message.tasks;
+    for name in order {
+        let submitted = tasks
+            .remove(&name)
+            .expect("Every name has been ordered exactly once.");
+
+        // Fall back to the group's default priority, if no priority has been specified.
+        let priority = submitted
+            .priority
+            .or_else(|| state.groups().get(&submitted.group)?.priority)
+            .unwrap_or(0);
+
+        let mut dependencies: Vec<usize> = submitted
+            .dependencies
+            .keys()
+            .map(|dependency| task_ids[dependency])
+            .collect();
+        dependencies.sort_unstable();
+
+        let path = state.task_working_directory(
+            &submitted.group,
+            submitted.path,
+            submitted.path_is_fallback,
+        );
+
+        let mut task = Task::new(
+            submitted.command,
+            path,
+            submitted.envs,
+            submitted.group,
+            TaskStatus::Queued {
+                enqueued_at: Local::now(),
+            },
+            dependencies,
+            priority,
+            Some(submitted.label.unwrap_or_else(|| name.clone())),
+        );
+        if submitted.stashed {
+            task.status = TaskStatus::Stashed { enqueue_at: None };
+        }
+        task.command = insert_alias(settings, task.original_command.clone());
+
+        // Only remember conditions that differ from the default.
+        task.dependency_conditions = submitted
+            .dependencies
+            .iter()
+            .filter(|(_, condition)| **condition != DependencyCondition::default())
+            .map(|(dependency, condition)| (task_ids[dependency], *condition))
+            .collect();
+
+        let id = state.add_task(task);
+        task_ids.insert(name, id);
+    }
+
+    ok_or_save_state_failure!(state.save(settings));
+
+    SubmittedResponse { task_ids }.into()
+}
+
+/// Sort the tasks of a workflow, so that each task comes after all of its dependencies.
+///
+/// If the graph contains a cycle, the names of all tasks that're part of or depend on a cycle
+/// are returned as error.
+fn topological_order(tasks: &BTreeMap<String, SubmittedTask>) -> Result<Vec<String>, Vec<String>> {
+    // The number of unresolved dependencies of each task.
+    let mut pending: BTreeMap<&String, usize> = tasks
+        .iter()
+        .map(|(name, task)| (name, task.dependencies.len()))
+        .collect();
+
+    let mut ready: VecDeque<&String> = pending
+        .iter()
+        .filter(|(_, count)| **count == 0)
+        .map(|(name, _)| *name)
+        .collect();
+
+    let mut order = Vec::with_capacity(tasks.len());
+    while let Some(name) = ready.pop_front() {
+        order.push(name.clone());
+
+        for (dependant, task) in tasks {
+            if !task.dependencies.contains_key(name) {
+                continue;
+            }
+            let count = pending
+                .get_mut(dependant)
+                .expect("Every task has a counter.");
+            *count -= 1;
+            if *count == 0 {
+                ready.push_back(dependant);
+            }
+        }
+    }
+
+    if order.len() == tasks.len() {
+        return Ok(order);
+    }
+
+    Err(pending
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, _)| name.clone())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    fn workflow(edges: &[(&str, &[&str])]) -> BTreeMap<String, SubmittedTask> {
+        edges
+            .iter()
+            .map(|(name, dependencies)| {
+                let task = SubmittedTask {
+                    command: name.to_string(),
+                    dependencies: dependencies
+                        .iter()
+                        .map(|dependency| (dependency.to_string(), DependencyCondition::Success))
+                        .collect(),
+                    ..Default::default()
+                };
+                (name.to_string(), task)
+            })
+            .collect()
+    }
+
+    #[test]
+    /// Dependencies are always ordered before their dependants.
+    fn order_diamond() {
+        let tasks = workflow(&[
+            ("build", &[]),
+            ("lint", &["build"]),
+            ("test", &["build"]),
+            ("deploy", &["lint", "test"]),
+        ]);
+
+        let order = topological_order(&tasks).unwrap();
+        assert_eq!(order, vec!["build", "lint", "test", "deploy"]);
+    }
+
+    #[test]
+    /// Cycles are detected and all affected tasks are reported.
+    fn order_cycle() {
+        let tasks = workflow(&[("a", &["c"]), ("b", &["a"]), ("c", &["b"]), ("d", &[])]);
+
+        let cycle = topological_order(&tasks).unwrap_err();
+        assert_eq!(cycle, vec!["a", "b", "c"]);
+    }
+}
@@ -27,8 +27,8 @@ pub fn switch(settings: &Settings, state: &SharedState, message: SwitchRequest)
     }
 
     // Get the tasks. Expect them to be there, since we found no mismatch
-    let mut first_task = state.tasks_mut().remove(&task_ids[0]).unwrap();
-    let mut second_task = state.tasks_mut().remove(&task_ids[1]).unwrap();
+    let mut first_task = state.remove_task(task_ids[0]).unwrap();
+    let mut second_task = state.remove_task(task_ids[1]).unwrap();
 
     // Switch task ids
     let first_id = first_task.id;
@@ -37,10 +37,32 @@ pub fn switch(settings: &Settings, state: &SharedState, message: SwitchRequest)
     second_task.id = first_id;
 
     // Put tasks back in again
-    state.tasks_mut().insert(first_task.id, first_task);
-    state.tasks_mut().insert(second_task.id, second_task);
+    state.insert_task(first_task);
+    state.insert_task(second_task);
+
+    // Only touch the tasks that depend on either of the switched tasks.
+    let dependants: Vec<usize> = state
+        .tasks()
+        .values()
+        .filter(|task| {
+            [first_id, second_id].iter().any(|id| {
+                task.dependencies.contains(id) || task.dependency_conditions.contains_key(id)
+            })
+        })
+        .map(|task| task.id)
+        .collect();
+    for task_id in dependants {
+        let task = state.task_mut(task_id).unwrap();
+        // The conditions of the dependencies move along with the ids.
+        let first_condition = task.dependency_conditions.remove(&first_id);
+        let second_condition = task.dependency_conditions.remove(&second_id);
+        if let Some(condition) = first_condition {
+            task.dependency_conditions.insert(second_id, condition);
+        }
+        if let Some(condition) = second_condition {
+            task.dependency_conditions.insert(first_id, condition);
+        }
 
-    for (_, task) in state.tasks_mut().iter_mut() {
         // If the task depends on both, we can just keep it as it is.
         if task.dependencies.contains(&first_id) && task.dependencies.contains(&second_id) {
             continue;
@@ -63,6 +85,7 @@ pub fn switch(settings: &Settings, state: &SharedState, message: SwitchRequest)
 #[cfg(test)]
 mod tests {
     use pretty_assertions::assert_eq;
+    use pueue_lib::task::DependencyCondition;
     use tempfile::TempDir;
 
     use super::{super::fixtures::*, *};
@@ -93,6 +116,8 @@ mod tests {
 
             let mut task = get_stub_task("4", StubStatus::Queued);
             task.dependencies = vec![0, 3];
+            task.dependency_conditions
+                .insert(3, DependencyCondition::Failure);
             state.add_task(task);
 
             let mut task = get_stub_task("5", StubStatus::Stashed { enqueue_at: None });
@@ -148,7 +173,11 @@ mod tests {
         switch(&settings, &state, get_message(0, 3));
 
         let state = state.lock().unwrap();
-        assert_eq!(state.tasks().get(&4).unwrap().dependencies, vec![0, 3]);
+        let task = state.tasks().get(&4).unwrap();
+        assert_eq!(task.dependencies, vec![0, 3]);
+        // The condition moves along with the switched dependency.
+        assert_eq!(task.dependency_condition(0), DependencyCondition::Failure);
+        assert_eq!(task.dependency_condition(3), DependencyCondition::Success);
     }
 
     #[test]
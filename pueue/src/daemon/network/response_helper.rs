@@ -12,7 +12,7 @@ pub fn ensure_group_exists<'state>(
     group: &str,
 ) -> Result<&'state mut Group, Response> {
     let group_keys: Vec<String> = state.groups().keys().cloned().collect();
-    if let Some(group) = state.groups_mut().get_mut(group) {
+    if let Some(group) = state.group_mut(group) {
         return Ok(group);
     }
 
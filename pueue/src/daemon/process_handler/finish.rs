@@ -1,6 +1,8 @@
 use chrono::Local;
 use pueue_lib::{GroupStatus, Settings, TaskResult, TaskStatus, log::clean_log_handles};
 
+#[cfg(unix)]
+use crate::daemon::supervisor::cleanup_supervisor_files;
 use crate::{
     daemon::{callbacks::spawn_callback, internal_state::state::LockedState},
     internal_prelude::*,
@@ -18,9 +20,9 @@ pub fn handle_finished_tasks(settings: &Settings, state: &mut LockedState) {
         return;
     }
 
-    for ((task_id, group, worker_id), error) in finished.iter() {
+    for ((task_id, group, worker_id), exit_code) in finished.into_iter() {
         let (enqueued_at, start) = {
-            let task = state.tasks().get(task_id).unwrap();
+            let task = state.tasks().get(&task_id).unwrap();
             // Get the enqueued_at/start times from the current state.
             match task.status {
                 TaskStatus::Running { enqueued_at, start }
@@ -35,71 +37,61 @@ pub fn handle_finished_tasks(settings: &Settings, state: &mut LockedState) {
 
         // Handle std::io errors on child processes.
         // I have never seen something like this, but it might happen.
-        if let Some(error) = error {
-            let (_taks_id, _child) = state
-                .children
-                .0
-                .get_mut(group)
-                .expect("Worker group must exist when handling finished tasks.")
-                .remove(worker_id)
-                .expect("Errored child went missing while handling finished task.");
-
-            // Update the tasks's state and return a clone for callback handling.
-            let task = {
-                let task = state.tasks_mut().get_mut(task_id).unwrap();
-
-                task.status = TaskStatus::Done {
-                    enqueued_at,
-                    start,
-                    end: Local::now(),
-                    result: TaskResult::Errored,
+        let exit_code = match exit_code {
+            Ok(exit_code) => exit_code,
+            Err(error) => {
+                let (_taks_id, _child) = state
+                    .children
+                    .0
+                    .get_mut(&group)
+                    .expect("Worker group must exist when handling finished tasks.")
+                    .remove(&worker_id)
+                    .expect("Errored child went missing while handling finished task.");
+
+                // Update the tasks's state and return a clone for callback handling.
+                let task = {
+                    let task = state.task_mut(task_id).unwrap();
+
+                    task.status = TaskStatus::Done {
+                        enqueued_at,
+                        start,
+                        end: Local::now(),
+                        result: TaskResult::Errored,
+                    };
+
+                    task.clone()
                 };
 
-                task.clone()
-            };
-
-            spawn_callback(settings, state, &task);
-            error!("Child {} failed with io::Error: {:?}", task_id, error);
+                spawn_callback(settings, state, &task);
+                error!("Child {} failed with io::Error: {:?}", task_id, error);
 
-            state.pause_on_failure(settings, &task.group);
-            continue;
-        }
+                state.pause_on_failure(settings, &task.group);
+                continue;
+            }
+        };
 
         // Handle any tasks that exited with some kind of exit code
-        let (_task_id, mut child) = state
+        let (_task_id, _child) = state
             .children
             .0
-            .get_mut(group)
+            .get_mut(&group)
             .expect("Worker group must exist when handling finished tasks.")
-            .remove(worker_id)
+            .remove(&worker_id)
             .expect("Child of task {} went away while handling finished task.");
 
-        // Get the exit code of the child.
-        // Errors really shouldn't happen in here, since we already checked if it's finished
-        // with try_wait() before.
-        let exit_code_result = child.wait();
-        let exit_code = exit_code_result
-            .context(format!(
-                "Failed on wait() for finished task {task_id} with error: {error:?}"
-            ))
-            .unwrap()
-            .code();
-
-        // Processes with exit code 0 exited successfully
-        // Processes with `None` have been killed by a Signal
-        let result = match exit_code {
-            Some(0) => TaskResult::Success,
-            Some(exit_code) => TaskResult::Failed(exit_code),
-            None => TaskResult::Killed,
-        };
+        #[cfg(unix)]
+        if settings.daemon.reattach_tasks {
+            cleanup_supervisor_files(task_id, &settings.shared.pueue_directory());
+        }
+
+        let result = result_from_exit_code(exit_code);
 
         info!("Task {task_id} finished with result: {result:?}");
 
         // Update the tasks's state and return a clone for callback handling.
         let task = {
             let task = state
-                .tasks_mut()
-                .get_mut(task_id)
+                .task_mut(task_id)
                 .expect("Task was removed before child process has finished!");
 
             task.status = TaskStatus::Done {
@@ -125,28 +117,44 @@ pub fn handle_finished_tasks(settings: &Settings, state: &mut LockedState) {
             .map(|group| group.status == GroupStatus::Reset)
             .unwrap_or(true)
         {
-            clean_log_handles(*task_id, &settings.shared.pueue_directory());
+            clean_log_handles(task_id, &settings.shared.pueue_directory());
         }
     }
 
     ok_or_shutdown!(settings, state, state.save(settings));
 }
 
+/// Convert the exit code of a finished process into a [TaskResult].
+///
+/// Processes with exit code 0 exited successfully.
+/// Processes with `None` have been killed by a Signal.
+pub fn result_from_exit_code(exit_code: Option<i32>) -> TaskResult {
+    match exit_code {
+        Some(0) => TaskResult::Success,
+        Some(exit_code) => TaskResult::Failed(exit_code),
+        None => TaskResult::Killed,
+    }
+}
+
+/// A finished child process, identified by `(task_id, group, worker_id)`.
+/// Contains either the exit code of the process or the error that occurred while waiting for it.
+type FinishedChild = ((usize, String, usize), std::io::Result<Option<i32>>);
+
 /// Gather all finished tasks and sort them by finished and errored.
-/// Returns a list of finished task ids and whether they errored or not.
-fn get_finished(state: &mut LockedState) -> Vec<((usize, String, usize), Option<std::io::Error>)> {
+/// Returns a list of finished task ids and their exit code or error.
+fn get_finished(state: &mut LockedState) -> Vec<FinishedChild> {
     let mut finished = Vec::new();
     for (group, children) in state.children.0.iter_mut() {
         for (worker_id, (task_id, child)) in children.iter_mut() {
             match child.try_wait() {
                 // Handle a child error.
                 Err(error) => {
-                    finished.push(((*task_id, group.clone(), *worker_id), Some(error)));
+                    finished.push(((*task_id, group.clone(), *worker_id), Err(error)));
                 }
                 // Child process did not exit yet
                 Ok(None) => continue,
-                Ok(_exit_status) => {
-                    finished.push(((*task_id, group.clone(), *worker_id), None));
+                Ok(Some(exit_code)) => {
+                    finished.push(((*task_id, group.clone(), *worker_id), Ok(exit_code)));
                 }
             }
         }
@@ -4,9 +4,7 @@ use pueue_lib::{
 };
 
 use crate::{
-    daemon::internal_state::state::LockedState,
-    internal_prelude::*,
-    process_helper::{ProcessAction, send_signal_to_child},
+    daemon::internal_state::state::LockedState, internal_prelude::*, process_helper::ProcessAction,
 };
 
 pub mod finish;
@@ -44,18 +42,32 @@ pub fn initiate_shutdown(settings: &Settings, state: &mut LockedState, shutdown:
     // Otherwise, we might end up with an endless recursion as `kill` might fail and initiate
     // shutdown once again.
     if state.shutdown.is_none() {
+        // Supervised tasks are left running on a graceful shutdown.
+        // They'll be re-adopted by the next daemon.
+        let keep_tasks = keep_tasks_on_shutdown(settings, &shutdown);
         state.shutdown = Some(shutdown);
-        self::kill::kill(settings, state, TaskSelection::All, false, None);
+        if !keep_tasks {
+            self::kill::kill(settings, state, TaskSelection::All, false, None);
+        }
     }
 }
 
+/// Whether running tasks should survive the shutdown of the daemon.
+/// This is only the case for graceful shutdowns with `reattach_tasks` enabled and only on
+/// platforms on which the identity of a task's process can be verified after a restart.
+pub fn keep_tasks_on_shutdown(settings: &Settings, shutdown: &ShutdownRequest) -> bool {
+    cfg!(any(target_os = "linux", target_os = "macos"))
+        && settings.daemon.reattach_tasks
+        && matches!(shutdown, ShutdownRequest::Graceful)
+}
+
 /// This is a small wrapper around the real platform dependant process handling logic
 /// It only ensures, that the process we want to manipulate really does exists.
 pub fn perform_action(state: &mut LockedState, id: usize, action: ProcessAction) -> Result<bool> {
     match state.children.get_child_mut(id) {
         Some(child) => {
             debug!("Executing action {action:?} to {id}");
-            send_signal_to_child(child, action.into())?;
+            child.signal(action.into())?;
 
             Ok(true)
         }
@@ -16,7 +16,7 @@ pub fn pause(settings: &Settings, state: &mut LockedState, selection: TaskSelect
         TaskSelection::TaskIds(task_ids) => task_ids,
         TaskSelection::Group(group_name) => {
             // Ensure that a given group exists. (Might not happen due to concurrency)
-            let group = match state.groups_mut().get_mut(&group_name) {
+            let group = match state.group_mut(&group_name) {
                 Some(group) => group,
                 None => return,
             };
@@ -45,7 +45,7 @@ pub fn pause(settings: &Settings, state: &mut LockedState, selection: TaskSelect
     if !wait {
         for id in keys {
             // Get the enqueued_at/start times from the current state.
-            let (enqueued_at, start) = match state.tasks_mut().get(&id).unwrap().status {
+            let (enqueued_at, start) = match state.tasks().get(&id).unwrap().status {
                 TaskStatus::Running { enqueued_at, start }
                 | TaskStatus::Paused { enqueued_at, start } => (enqueued_at, start),
                 _ => continue,
@@ -61,6 +61,7 @@ pub fn pause(settings: &Settings, state: &mut LockedState, selection: TaskSelect
 
             if success {
                 state.change_status(id, TaskStatus::Paused { enqueued_at, start });
+                state.timeouts.pause(id);
             }
         }
     }
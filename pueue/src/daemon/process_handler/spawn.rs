@@ -7,8 +7,16 @@ use pueue_lib::{
     log::{create_log_file_handles, get_writable_log_file_handle},
 };
 
+#[cfg(unix)]
 use crate::{
-    daemon::{callbacks::spawn_callback, internal_state::state::LockedState},
+    daemon::supervisor::{cleanup_supervisor_files, get_exit_code_path, write_pid_file},
+    process_helper::supervise_command,
+};
+use crate::{
+    daemon::{
+        callbacks::{HookEvent, spawn_callback, spawn_task_hook},
+        internal_state::{children::TaskChild, state::LockedState},
+    },
     internal_prelude::*,
     ok_or_shutdown,
     process_helper::compile_shell_command,
@@ -80,11 +88,13 @@ pub fn get_next_task_id(state: &LockedState) -> Option<usize> {
                 running_tasks < group.parallel_tasks
             })
             .filter(|(_, task)| {
-                // Check whether all dependencies for this task are fulfilled.
+                // Check whether the conditions of all dependencies for this task are fulfilled.
                 task.dependencies
                     .iter()
                     .flat_map(|id| state.tasks().get(id))
-                    .all(|task| matches!(task.status, TaskStatus::Done{result: TaskResult::Success, ..}))
+                    .all(|dependency| {
+                        task.dependency_condition(dependency.id).is_met(dependency) == Some(true)
+                    })
             })
             .map(|(_, task)| {task})
             .collect();
@@ -151,8 +161,34 @@ pub fn spawn_process(settings: &Settings, state: &mut LockedState, task_id: usiz
         )
     };
 
+    // Inject the group's environment variables.
+    // These take precedence over the environment the task has been added with.
+    let mut group_shell_command = None;
+    if let Some(group) = state.groups().get(&group) {
+        envs.extend(group.envs.clone());
+        group_shell_command = group.shell_command.clone();
+    }
+
     // Build the shell command that should be executed.
-    let mut command = compile_shell_command(settings, &command);
+    // Groups may use their own shell command instead of the globally configured one.
+    let mut command = match group_shell_command {
+        Some(shell_command) => {
+            let mut group_settings = settings.clone();
+            group_settings.daemon.shell_command = Some(shell_command);
+            compile_shell_command(&group_settings, &command)
+        }
+        None => compile_shell_command(settings, &command),
+    };
+
+    // Wrap the command in a supervisor, if tasks should survive a restart of the daemon.
+    #[cfg(unix)]
+    if settings.daemon.reattach_tasks {
+        cleanup_supervisor_files(task_id, &pueue_directory);
+        let exit_code_path = get_exit_code_path(task_id, &pueue_directory);
+        if let Err(err) = supervise_command(&mut command, &exit_code_path) {
+            error!("Failed to supervise task {task_id}, it won't survive a restart: {err:?}");
+        }
+    }
 
     // Determine the worker's id depending on the current group.
     // Inject that info into the environment.
@@ -203,7 +239,7 @@ pub fn spawn_process(settings: &Settings, state: &mut LockedState, task_id: usiz
 
             // Update all necessary fields on the task.
             let task = {
-                let task = state.tasks_mut().get_mut(&task_id).unwrap();
+                let task = state.task_mut(task_id).unwrap();
                 task.status = TaskStatus::Done {
                     enqueued_at,
                     start: Local::now(),
@@ -222,10 +258,23 @@ pub fn spawn_process(settings: &Settings, state: &mut LockedState, task_id: usiz
         }
     };
 
+    // Remember the supervisor's pid, so a restarted daemon is able to find the task again.
+    #[cfg(unix)]
+    if settings.daemon.reattach_tasks {
+        if let Err(err) = write_pid_file(task_id, child.id(), &pueue_directory) {
+            error!("Failed to write supervisor pid for task {task_id}: {err:?}");
+        }
+    }
+
+    // Drop any timeout bookkeeping from a previous run of this task.
+    state.timeouts.forget(task_id);
+
     // Save the process handle in our self.children datastructure.
-    state.children.add_child(&group, worker_id, task_id, child);
+    state
+        .children
+        .add_child(&group, worker_id, task_id, TaskChild::Spawned(child));
 
-    let task = state.tasks_mut().get_mut(&task_id).unwrap();
+    let task = state.task_mut(task_id).unwrap();
     task.status = TaskStatus::Running {
         enqueued_at,
         start: Local::now(),
@@ -235,5 +284,8 @@ pub fn spawn_process(settings: &Settings, state: &mut LockedState, task_id: usiz
     task.envs = envs;
 
     info!("Started task: {}", task.command);
+    let task = task.clone();
+    spawn_task_hook(settings, state, &task, HookEvent::Start);
+
     ok_or_shutdown!(settings, state, state.save(settings));
 }
@@ -35,7 +35,7 @@ pub fn start(settings: &Settings, state: &mut LockedState, tasks: TaskSelection)
         }
         TaskSelection::Group(group_name) => {
             // Ensure that a given group exists. (Might not happen due to concurrency)
-            let group = match state.groups_mut().get_mut(&group_name) {
+            let group = match state.group_mut(&group_name) {
                 Some(group) => group,
                 None => return,
             };
@@ -78,7 +78,7 @@ fn continue_task(state: &mut LockedState, task_id: usize) {
     // Encapsulate to prevent a duplicate borrow on `state`.
     let (enqueued_at, start) = {
         // Task is already done
-        let Some(task) = state.tasks_mut().get_mut(&task_id) else {
+        let Some(task) = state.task_mut(task_id) else {
             return;
         };
 
@@ -106,5 +106,6 @@ fn continue_task(state: &mut LockedState, task_id: usize) {
 
     if success {
         state.change_status(task_id, TaskStatus::Running { enqueued_at, start });
+        state.timeouts.resume(task_id);
     }
 }
@@ -0,0 +1,190 @@
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+};
+
+use pueue_lib::{Settings, message::ConfigReloadResponse, settings::GroupSettings};
+use serde::Serialize;
+use serde_json::Value;
+use url::Url;
+
+use crate::{
+    daemon::internal_state::SharedState, internal_prelude::*,
+    process_helper::validate_shell_command,
+};
+
+/// Settings of the `daemon` section that are only safe to change during startup.
+/// On reload, their old values are kept until the daemon is restarted.
+const STARTUP_ONLY_KEYS: &[&str] = &["daemon.compress_state_file", "daemon.reattach_tasks"];
+
+/// A handle to the daemon's current settings.
+///
+/// All parts of the daemon work on a snapshot of the settings, which is retrieved via
+/// [SharedSettings::current]. A reload swaps the settings atomically, which is why a snapshot is
+/// never a mix of old and new values.
+#[derive(Clone, Debug)]
+pub struct SharedSettings {
+    settings: Arc<RwLock<Settings>>,
+    /// The configuration file the daemon has been started with.
+    config_path: Option<PathBuf>,
+    /// The profile the daemon has been started with.
+    profile: Option<String>,
+}
+
+impl SharedSettings {
+    pub fn new(settings: Settings, config_path: Option<PathBuf>, profile: Option<String>) -> Self {
+        SharedSettings {
+            settings: Arc::new(RwLock::new(settings)),
+            config_path,
+            profile,
+        }
+    }
+
+    /// Get a snapshot of the current settings.
+    pub fn current(&self) -> Settings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Re-read the configuration file and swap the new settings in.
+    ///
+    /// The `shared` section contains things like sockets and certificates, which are only used
+    /// during startup. Changes to that section are reported, but only take effect after a restart.
+    /// The same goes for the [STARTUP_ONLY_KEYS] of the `daemon` section.
+    ///
+    /// Groups whose declaration changed in the new configuration are applied to the state right
+    /// away.
+    pub fn reload(&self, state: &SharedState) -> Result<ConfigReloadResponse> {
+        let (mut new_settings, config_found) =
+            Settings::read(&self.config_path).context("Error while reading configuration.")?;
+        if !config_found {
+            bail!("Couldn't find a configuration file to reload.");
+        }
+
+        if let Some(profile) = &self.profile {
+            new_settings.load_profile(profile)?;
+        }
+        validate(&new_settings)?;
+
+        let changed_groups: BTreeMap<String, GroupSettings>;
+        let response = {
+            let mut settings = self.settings.write().unwrap();
+            let (mut requires_restart, applied): (Vec<String>, Vec<String>) =
+                changed_keys("daemon", &settings.daemon, &new_settings.daemon)?
+                    .into_iter()
+                    .partition(|key| STARTUP_ONLY_KEYS.contains(&key.as_str()));
+            requires_restart.extend(changed_keys(
+                "shared",
+                &settings.shared,
+                &new_settings.shared,
+            )?);
+            let response = ConfigReloadResponse {
+                applied,
+                requires_restart,
+            };
+
+            // Keep the values that are only used during startup.
+            new_settings.daemon.compress_state_file = settings.daemon.compress_state_file;
+            new_settings.daemon.reattach_tasks = settings.daemon.reattach_tasks;
+
+            // Only apply group declarations that actually changed.
+            // Otherwise, runtime changes of those groups would be reverted on every reload.
+            changed_groups = new_settings
+                .daemon
+                .groups
+                .iter()
+                .filter(|(name, group)| settings.daemon.groups.get(*name) != Some(group))
+                .map(|(name, group)| (name.clone(), group.clone()))
+                .collect();
+
+            settings.daemon = new_settings.daemon;
+            settings.client = new_settings.client;
+            settings.profiles = new_settings.profiles;
+
+            response
+        };
+
+        let settings = self.current();
+        let mut state = state.lock().unwrap();
+        state.apply_group_settings(&changed_groups);
+        state.save(&settings)?;
+
+        Ok(response)
+    }
+}
+
+/// Make sure that all templates and webhook urls in the settings can actually be used.
+///
+/// This is done on startup and before new settings are applied on reload, so mistakes show up
+/// right away instead of when they're first used.
+pub fn validate(settings: &Settings) -> Result<()> {
+    #[cfg(not(feature = "sqlite"))]
+    if settings.daemon.state_backend == pueue_lib::settings::StateBackend::Sqlite {
+        bail!("The sqlite state backend isn't available, as pueue has been built without it.");
+    }
+
+    // Tasks can only be re-adopted, if their process can be told apart from a reused pid.
+    if settings.daemon.reattach_tasks && !cfg!(any(target_os = "linux", target_os = "macos")) {
+        bail!("`reattach_tasks` is only supported on Linux and macOS.");
+    }
+
+    if let Some(callback) = &settings.daemon.callback {
+        handlebars::Template::compile(callback).context("Invalid callback template.")?;
+    }
+    for hook in settings.daemon.hooks.iter() {
+        handlebars::Template::compile(hook).context("Invalid hook template.")?;
+    }
+
+    for (name, group) in &settings.daemon.groups {
+        if let Some(callback) = &group.callback {
+            handlebars::Template::compile(callback)
+                .wrap_err_with(|| format!("Invalid callback template for group {name}."))?;
+        }
+        for hook in group.hooks.iter() {
+            handlebars::Template::compile(hook)
+                .wrap_err_with(|| format!("Invalid hook template for group {name}."))?;
+        }
+
+        if let Some(shell_command) = &group.shell_command {
+            validate_shell_command(shell_command)
+                .wrap_err_with(|| format!("Invalid shell_command for group {name}."))?;
+        }
+    }
+
+    for webhook in &settings.daemon.webhooks {
+        let url = Url::parse(&webhook.url)
+            .wrap_err_with(|| format!("Invalid webhook url {}.", webhook.url))?;
+        if !matches!(url.scheme(), "http" | "https") || !url.has_host() {
+            bail!(
+                "Invalid webhook url {}. Only http and https urls with a host are supported.",
+                webhook.url
+            );
+        }
+    }
+
+    if let Some(shell_command) = &settings.daemon.shell_command {
+        validate_shell_command(shell_command).context("Invalid shell_command.")?;
+    }
+
+    Ok(())
+}
+
+/// Compare two configuration sections and return the keys of all values that differ.
+fn changed_keys<T: Serialize>(section: &str, old: &T, new: &T) -> Result<Vec<String>> {
+    let (Value::Object(old), Value::Object(new)) =
+        (serde_json::to_value(old)?, serde_json::to_value(new)?)
+    else {
+        bail!("Configuration section {section} isn't a map.");
+    };
+
+    let mut keys: Vec<String> = old
+        .keys()
+        .chain(new.keys())
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| format!("{section}.{key}"))
+        .collect();
+    keys.sort();
+    keys.dedup();
+
+    Ok(keys)
+}
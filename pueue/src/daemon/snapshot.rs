@@ -0,0 +1,178 @@
+//! Snapshots keep a copy of all tasks that are affected by destructive operations, such as
+//! `reset`, `clean`, `remove` or the removal of a group.
+//!
+//! Each snapshot is a JSON file in the `snapshots` folder inside the `pueue_directory`.
+//! Only the most recent `daemon.max_snapshots` snapshots are kept.
+//!
+//! Snapshot ids are never reused, even if the most recent snapshot has been restored and thereby
+//! removed. The next id is persisted in a `next_id` file next to the snapshots.
+use std::{collections::BTreeMap, fs::read_dir, path::PathBuf};
+
+use chrono::{DateTime, Local};
+use pueue_lib::{
+    Settings, Task,
+    message::{SnapshotInfo, TaskExport},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{daemon::internal_state::state::LockedState, internal_prelude::*};
+
+/// A copy of the tasks and groups that have been affected by a destructive operation.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Snapshot {
+    pub id: usize,
+    pub created_at: DateTime<Local>,
+    /// The operation that caused the snapshot, e.g. `reset`.
+    pub operation: String,
+    pub export: TaskExport,
+}
+
+impl From<&Snapshot> for SnapshotInfo {
+    fn from(snapshot: &Snapshot) -> Self {
+        SnapshotInfo {
+            id: snapshot.id,
+            created_at: snapshot.created_at,
+            operation: snapshot.operation.clone(),
+            task_ids: snapshot.export.tasks.iter().map(|task| task.id).collect(),
+            groups: snapshot.export.groups.keys().cloned().collect(),
+        }
+    }
+}
+
+/// The folder that contains all snapshots.
+pub fn snapshot_dir(settings: &Settings) -> PathBuf {
+    settings.shared.pueue_directory().join("snapshots")
+}
+
+/// Take a snapshot of the given tasks and the configuration of their groups.
+/// Additional `groups` that should be kept, even if no task belongs to them, can be passed.
+///
+/// Errors are only logged, as a failing snapshot shouldn't prevent the operation itself.
+pub fn take_snapshot(
+    settings: &Settings,
+    state: &LockedState,
+    operation: &str,
+    tasks: Vec<Task>,
+    groups: &[String],
+) {
+    if settings.daemon.max_snapshots == 0 || (tasks.is_empty() && groups.is_empty()) {
+        return;
+    }
+
+    let mut export = TaskExport {
+        tasks,
+        groups: BTreeMap::new(),
+        logs: BTreeMap::new(),
+    };
+    let group_names = export
+        .tasks
+        .iter()
+        .map(|task| &task.group)
+        .chain(groups.iter());
+    for name in group_names {
+        if let Some(group) = state.groups().get(name) {
+            export.groups.insert(name.clone(), group.clone());
+        }
+    }
+
+    if let Err(err) = write_snapshot(settings, operation, export) {
+        error!("Failed to take snapshot before {operation}: {err:?}");
+    }
+}
+
+fn write_snapshot(settings: &Settings, operation: &str, export: TaskExport) -> Result<()> {
+    let dir = snapshot_dir(settings);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create snapshot directory at {dir:?}"))?;
+
+    let mut snapshots = read_snapshots(settings)?;
+    // Fall back to the most recent snapshot, in case the counter is missing or outdated.
+    let id = read_next_id(settings)?.max(
+        snapshots
+            .last()
+            .map(|snapshot| snapshot.id + 1)
+            .unwrap_or(0),
+    );
+    let next_id_path = next_id_path(settings);
+    std::fs::write(&next_id_path, (id + 1).to_string())
+        .with_context(|| format!("Failed to write next snapshot id to {next_id_path:?}"))?;
+
+    let snapshot = Snapshot {
+        id,
+        created_at: Local::now(),
+        operation: operation.to_string(),
+        export,
+    };
+    let path = snapshot_path(settings, snapshot.id);
+    std::fs::write(&path, serde_json::to_string(&snapshot)?)
+        .with_context(|| format!("Failed to write snapshot to {path:?}"))?;
+    debug!("Snapshot {} taken at: {path:?}", snapshot.id);
+
+    // Remove the oldest snapshots.
+    snapshots.push(snapshot);
+    let excess = snapshots
+        .len()
+        .saturating_sub(settings.daemon.max_snapshots);
+    for snapshot in snapshots.drain(..excess) {
+        remove_snapshot(settings, snapshot.id)?;
+    }
+
+    Ok(())
+}
+
+/// Read the id that should be given to the next snapshot.
+fn read_next_id(settings: &Settings) -> Result<usize> {
+    let path = next_id_path(settings);
+    if !path.exists() {
+        return Ok(0);
+    }
+
+    let content = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read next snapshot id from {path:?}"))?;
+    match content.trim().parse() {
+        Ok(id) => Ok(id),
+        Err(err) => {
+            warn!("Ignoring broken next snapshot id at {path:?}: {err}");
+            Ok(0)
+        }
+    }
+}
+
+fn next_id_path(settings: &Settings) -> PathBuf {
+    snapshot_dir(settings).join("next_id")
+}
+
+/// Read all snapshots, from the oldest to the most recent one.
+pub fn read_snapshots(settings: &Settings) -> Result<Vec<Snapshot>> {
+    let dir = snapshot_dir(settings);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let content = std::fs::read_to_string(&path)?;
+        match serde_json::from_str::<Snapshot>(&content) {
+            Ok(snapshot) => snapshots.push(snapshot),
+            Err(err) => warn!("Skipping broken snapshot at {path:?}: {err}"),
+        }
+    }
+    snapshots.sort_by_key(|snapshot| snapshot.id);
+
+    Ok(snapshots)
+}
+
+/// Remove a single snapshot.
+pub fn remove_snapshot(settings: &Settings, id: usize) -> Result<()> {
+    let path = snapshot_path(settings, id);
+    std::fs::remove_file(&path).with_context(|| format!("Failed to remove snapshot {path:?}"))
+}
+
+fn snapshot_path(settings: &Settings, id: usize) -> PathBuf {
+    snapshot_dir(settings).join(format!("{id}.json"))
+}
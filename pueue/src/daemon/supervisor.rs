@@ -0,0 +1,151 @@
+//! Helper for supervised tasks, which survive a restart of the daemon.
+//!
+//! If `reattach_tasks` is enabled, every task is wrapped in a small supervisor process.
+//! The pid of that process (which is also the id of the task's process group) and its identity
+//! are written to the `task_supervisor` directory, while the supervisor itself writes the exit
+//! status of the task to the same directory once the task finishes.
+//!
+//! A restarted daemon uses those files to re-adopt still running tasks or to recover the results
+//! of tasks that finished while no daemon was around.
+use std::{
+    fs::{read_to_string, remove_file},
+    path::{Path, PathBuf},
+};
+
+use pueue_lib::TaskResult;
+
+use crate::{
+    daemon::process_handler::finish::result_from_exit_code,
+    internal_prelude::*,
+    process_helper::{AdoptedChild, process_identity},
+};
+
+/// The outcome of trying to re-adopt a previously running task.
+pub enum Adoption {
+    /// The task is still running and can be managed by this daemon.
+    Running(AdoptedChild),
+    /// The task finished while no daemon was around.
+    Finished(TaskResult),
+}
+
+/// The directory in which all supervisor files are stored.
+pub fn supervisor_directory(pueue_dir: &Path) -> PathBuf {
+    pueue_dir.join("task_supervisor")
+}
+
+/// The file that contains the pid and the identity of a task's supervisor.
+pub fn get_pid_path(task_id: usize, pueue_dir: &Path) -> PathBuf {
+    supervisor_directory(pueue_dir).join(format!("{task_id}.pid"))
+}
+
+/// The file to which the supervisor writes the exit status of a task.
+pub fn get_exit_code_path(task_id: usize, pueue_dir: &Path) -> PathBuf {
+    supervisor_directory(pueue_dir).join(format!("{task_id}.exit"))
+}
+
+/// Remember the pid of a newly spawned supervisor.
+///
+/// The pid is stored together with the identity of the process, so a restarted daemon doesn't
+/// mistake an unrelated process for the supervisor, once the pid has been reused.
+pub fn write_pid_file(task_id: usize, pid: u32, pueue_dir: &Path) -> Result<()> {
+    let Some(identity) = process_identity(pid) else {
+        bail!("Failed to determine the identity of supervisor {pid}");
+    };
+
+    let path = get_pid_path(task_id, pueue_dir);
+    std::fs::write(&path, format!("{pid}\n{identity}"))
+        .wrap_err_with(|| format!("Failed to write supervisor pid file at {path:?}"))
+}
+
+/// Remove all supervisor files of a task.
+/// This is done once a task finished or before it's spawned again.
+pub fn cleanup_supervisor_files(task_id: usize, pueue_dir: &Path) {
+    for path in [
+        get_pid_path(task_id, pueue_dir),
+        get_exit_code_path(task_id, pueue_dir),
+    ] {
+        if path.exists() {
+            if let Err(err) = remove_file(&path) {
+                warn!("Failed to remove supervisor file {path:?}: {err}");
+            }
+        }
+    }
+}
+
+/// Try to re-adopt a task that has been running when the previous daemon shut down.
+///
+/// Tasks are only re-adopted, if the process with the recorded pid still has the recorded
+/// identity. Otherwise, the supervisor is gone and its pid might already belong to another process.
+pub fn adopt(task_id: usize, pueue_dir: &Path) -> Adoption {
+    let pid_path = get_pid_path(task_id, pueue_dir);
+    let content = read_to_string(&pid_path).unwrap_or_default();
+    let mut lines = content.lines();
+    let pid = lines.next().and_then(|pid| pid.trim().parse::<u32>().ok());
+    let identity = lines.next().map(|identity| identity.trim().to_string());
+
+    // Without a pid, the task hasn't been supervised and we have no chance to find it again.
+    let Some(pid) = pid else {
+        info!("Couldn't find a supervisor for task {task_id}");
+        return Adoption::Finished(TaskResult::Killed);
+    };
+
+    // Pid files of previous versions don't contain an identity. Those processes cannot be
+    // safely told apart from other processes, so they aren't touched.
+    let Some(identity) = identity else {
+        warn!("Refusing to re-adopt task {task_id}, as the identity of pid {pid} is unknown");
+        return Adoption::Finished(TaskResult::Killed);
+    };
+
+    let child = AdoptedChild {
+        pid,
+        identity,
+        exit_code_path: get_exit_code_path(task_id, pueue_dir),
+    };
+
+    if child.is_alive() {
+        info!("Re-adopting task {task_id} with pid {pid}");
+        return Adoption::Running(child);
+    }
+
+    // The supervisor is gone. Check if it managed to record the exit code.
+    let result = match child.try_wait() {
+        Some(exit_code) => result_from_exit_code(exit_code),
+        None => TaskResult::Killed,
+    };
+    info!("Task {task_id} finished while the daemon was down with result: {result:?}");
+
+    Adoption::Finished(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    use super::*;
+
+    /// Pids whose process has a different identity than the recorded one must not be adopted.
+    #[test]
+    fn refuse_reused_pid() -> Result<()> {
+        let tempdir = TempDir::new()?;
+        std::fs::create_dir_all(supervisor_directory(tempdir.path()))?;
+
+        // Our own process is definitely alive, but it's not the recorded process.
+        let pid = std::process::id();
+        std::fs::write(
+            get_pid_path(0, tempdir.path()),
+            format!("{pid}\nsomething-else"),
+        )?;
+        std::fs::write(get_exit_code_path(0, tempdir.path()), "exit:3")?;
+        match adopt(0, tempdir.path()) {
+            Adoption::Running(_) => bail!("A process with another identity has been adopted"),
+            Adoption::Finished(result) => assert_eq!(result, TaskResult::Failed(3)),
+        }
+
+        // With the correct identity, the process is adopted.
+        write_pid_file(0, pid, tempdir.path())?;
+        assert!(matches!(adopt(0, tempdir.path()), Adoption::Running(_)));
+
+        Ok(())
+    }
+}
@@ -1,48 +1,65 @@
-use std::{collections::BTreeMap, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    time::{Duration, Instant},
+};
 
 use chrono::prelude::*;
 use pueue_lib::{Group, GroupStatus, Settings, TaskResult, TaskStatus, message::*};
 
 use crate::{
     daemon::{
+        archive::archive_tasks,
         callbacks::{check_callbacks, spawn_callback},
-        internal_state::{SharedState, children::Children, state::LockedState},
-        network::socket::socket_cleanup,
+        internal_state::{SharedState, state::LockedState},
+        network::{message_handler::clean::clean_tasks, socket::socket_cleanup},
         pid::cleanup_pid_file,
-        process_handler::{finish::handle_finished_tasks, spawn::spawn_new},
+        process_handler::{
+            finish::handle_finished_tasks, keep_tasks_on_shutdown, kill::kill_task,
+            spawn::spawn_new,
+        },
+        settings::SharedSettings,
     },
     internal_prelude::*,
     ok_or_shutdown,
 };
 
+/// How often the cleanup policy is applied.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(2);
+
 /// Main task handling loop.
 /// In here a few things happen:
 ///
 /// - Handle finished tasks, i.e. cleanup processes, update statuses.
 /// - Callback handling logic. This is rather uncritical.
 /// - Enqueue any stashed processes which are ready for being queued.
+/// - Kill tasks that exceeded their timeout.
+/// - Remove finished tasks according to the cleanup policy.
 /// - Ensure tasks with dependencies have no failed ancestors
 /// - Handle shutdown logic (graceful & not graceful).
 /// - If the client requested a reset: reset the state if all children have been killed and handled.
 /// - Check whether we can spawn new tasks.
 ///
 /// We also wait for 300ms to prevent this loop from running hot.
-pub async fn run(state: SharedState, settings: Settings) -> Result<()> {
+pub async fn run(state: SharedState, settings: SharedSettings) -> Result<()> {
     // Initialize the subprocess management structure.
     {
+        // Pools might already contain tasks that have been re-adopted during state restoration.
         let mut state = state.lock().unwrap();
-        let mut pools = BTreeMap::new();
-        for group in state.groups().keys() {
-            pools.insert(group.clone(), BTreeMap::new());
+        let groups: Vec<String> = state.groups().keys().cloned().collect();
+        for group in groups {
+            state.children.0.entry(group).or_default();
         }
-        state.children = Children(pools);
     }
 
+    let mut last_cleanup: Option<Instant> = None;
+
     loop {
         'mutex_block: {
+            // Work on a snapshot of the settings, as they might be reloaded at any time.
+            let settings = settings.current();
             let mut state = state.lock().unwrap();
 
-            check_callbacks(&mut state);
+            check_callbacks(&settings, &mut state);
             handle_finished_tasks(&settings, &mut state);
 
             // Check if we're in shutdown.
@@ -55,6 +72,11 @@ pub async fn run(state: SharedState, settings: Settings) -> Result<()> {
             // If we aren't in shutdown mode, do the usual stuff
             handle_group_resets(&settings, &mut state);
             enqueue_delayed_tasks(&settings, &mut state);
+            kill_timed_out_tasks(&mut state);
+            if last_cleanup.is_none_or(|last| last.elapsed() >= CLEANUP_INTERVAL) {
+                apply_cleanup_policy(&settings, &mut state);
+                last_cleanup = Some(Instant::now());
+            }
             check_failed_dependencies(&settings, &mut state);
             spawn_new(&settings, &mut state);
         }
@@ -68,7 +90,12 @@ pub async fn run(state: SharedState, settings: Settings) -> Result<()> {
 /// Once they're, we do some cleanup and exit.
 fn handle_shutdown(settings: &Settings, state: &mut LockedState) {
     // There are still active tasks. Continue waiting until they're killed and cleaned up.
-    if state.children.has_active_tasks() {
+    // Tasks that survive the shutdown don't need to be waited for.
+    let keep_tasks = state
+        .shutdown
+        .as_ref()
+        .is_some_and(|shutdown| keep_tasks_on_shutdown(settings, shutdown));
+    if !keep_tasks && state.children.has_active_tasks() {
         return;
     }
 
@@ -112,10 +139,12 @@ fn handle_group_resets(_settings: &Settings, state: &mut LockedState) {
         }
 
         // Remove all tasks that belong to the group to reset
-        state.tasks_mut().retain(|_id, task| &task.group != name);
+        for task_id in state.task_ids_in_group(name) {
+            state.remove_task(task_id);
+        }
 
         // Restart the group, now that it's devoid of tasks.
-        if let Some(group) = state.groups_mut().get_mut(name) {
+        if let Some(group) = state.group_mut(name) {
             group.status = GroupStatus::Running;
         }
     }
@@ -124,29 +153,136 @@ fn handle_group_resets(_settings: &Settings, state: &mut LockedState) {
 /// As time passes, some delayed tasks may need to be enqueued.
 /// Gather all stashed tasks and enqueue them if it is after the task's enqueue_at
 fn enqueue_delayed_tasks(settings: &Settings, state: &mut LockedState) {
-    let mut changed = false;
-    for (_, task) in state.tasks_mut().iter_mut() {
-        if let TaskStatus::Stashed {
-            enqueue_at: Some(time),
-        } = task.status
-        {
-            if time <= Local::now() {
-                info!("Enqueuing delayed task : {}", task.id);
+    let now = Local::now();
+    let due: Vec<usize> = state
+        .tasks()
+        .values()
+        .filter(|task| {
+            matches!(task.status, TaskStatus::Stashed { enqueue_at: Some(time) } if time <= now)
+        })
+        .map(|task| task.id)
+        .collect();
 
-                task.status = TaskStatus::Queued {
-                    enqueued_at: Local::now(),
-                };
-                changed = true;
-            }
-        }
+    for task_id in &due {
+        info!("Enqueuing delayed task : {task_id}");
+        state.change_status(
+            *task_id,
+            TaskStatus::Queued {
+                enqueued_at: Local::now(),
+            },
+        );
     }
     // Save the state if a task has been enqueued
-    if changed {
+    if !due.is_empty() {
+        ok_or_shutdown!(settings, state, state.save(settings));
+    }
+}
+
+/// Kill all running tasks that have been running for longer than their timeout.
+/// Paused tasks are left alone, as they've been explicitly halted, and the time they spent
+/// paused doesn't count against their timeout.
+/// Each task is only killed once, as it might take a moment until it actually exits.
+fn kill_timed_out_tasks(state: &mut LockedState) {
+    let now = Local::now();
+    let alive: BTreeSet<usize> = state
+        .tasks()
+        .values()
+        .filter(|task| task.is_running())
+        .map(|task| task.id)
+        .collect();
+    state.timeouts.retain(|id| alive.contains(&id));
+
+    let timed_out: Vec<usize> = state
+        .tasks()
+        .values()
+        .filter(|task| {
+            let (TaskStatus::Running { start, .. }, Some(timeout)) = (&task.status, task.timeout)
+            else {
+                return false;
+            };
+            !state.timeouts.is_killed(task.id)
+                && state.timeouts.runtime(task.id, *start, now).num_seconds()
+                    >= i64::try_from(timeout).unwrap_or(i64::MAX)
+        })
+        .map(|task| task.id)
+        .collect();
+
+    for task_id in timed_out {
+        info!("Killing task {task_id}, as it exceeded its timeout");
+        state.timeouts.mark_killed(task_id);
+        kill_task(state, task_id);
+    }
+}
+
+/// Remove finished tasks that are too old or exceed the maximum amount of tasks per group.
+/// Successful and failed tasks are handled separately, as failed tasks may be kept longer.
+fn apply_cleanup_policy(settings: &Settings, state: &mut LockedState) {
+    let policy = &settings.daemon.cleanup;
+    if policy.max_age_secs.is_none()
+        && policy.failed_max_age_secs.is_none()
+        && policy.max_tasks_per_group.is_none()
+        && policy.failed_max_tasks_per_group.is_none()
+    {
+        return;
+    }
+
+    let now = Local::now();
+    let mut expired = BTreeSet::new();
+    // The finished tasks by group and by whether they failed.
+    let mut finished_by_group = BTreeMap::new();
+    for task in state.tasks().values() {
+        let TaskStatus::Done { end, result, .. } = &task.status else {
+            continue;
+        };
+
+        let failed = *result != TaskResult::Success;
+        let max_age = if failed {
+            policy.failed_max_age_secs.or(policy.max_age_secs)
+        } else {
+            policy.max_age_secs
+        };
+        if max_age.is_some_and(|max_age| {
+            (now - *end).num_seconds() >= i64::try_from(max_age).unwrap_or(i64::MAX)
+        }) {
+            expired.insert(task.id);
+        }
+
+        finished_by_group
+            .entry((&task.group, failed))
+            .or_insert_with(Vec::new)
+            .push((*end, task.id));
+    }
+
+    for ((_, failed), finished) in finished_by_group.iter_mut() {
+        let max_tasks = if *failed {
+            policy
+                .failed_max_tasks_per_group
+                .or(policy.max_tasks_per_group)
+        } else {
+            policy.max_tasks_per_group
+        };
+        let Some(max_tasks) = max_tasks else {
+            continue;
+        };
+
+        // Keep the most recently finished tasks.
+        finished.sort_unstable_by(|a, b| b.cmp(a));
+        expired.extend(finished.iter().skip(max_tasks).map(|(_, id)| *id));
+    }
+
+    if expired.is_empty() {
+        return;
+    }
+
+    let removed = clean_tasks(settings, state, |task| expired.contains(&task.id));
+    if !removed.is_empty() {
+        info!("Cleaned up {} finished tasks", removed.len());
         ok_or_shutdown!(settings, state, state.save(settings));
+        archive_tasks(settings, state, &removed);
     }
 }
 
-/// Ensure that no `Queued` tasks have any failed dependencies.
+/// Ensure that no `Queued` tasks have any dependencies whose condition can no longer be met.
 /// Otherwise set their status to `Done` and result to `DependencyFailed`.
 fn check_failed_dependencies(settings: &Settings, state: &mut LockedState) {
     // Get id's of all tasks with failed dependencies
@@ -158,13 +294,15 @@ fn check_failed_dependencies(settings: &Settings, state: &mut LockedState) {
         })
         .filter_map(|(id, task)| {
             // At this point we got all queued tasks with dependencies.
-            // Go through all dependencies and ensure they didn't fail.
+            // Go through all dependencies and ensure that their conditions can still be met.
             let failed = task
                 .dependencies
                 .iter()
                 .flat_map(|id| state.tasks().get(id))
-                .filter(|task| task.failed())
-                .map(|task| task.id)
+                .filter(|dependency| {
+                    task.dependency_condition(dependency.id).is_met(dependency) == Some(false)
+                })
+                .map(|dependency| dependency.id)
                 .next();
 
             failed.map(|f| (*id, f))
@@ -193,7 +331,7 @@ fn check_failed_dependencies(settings: &Settings, state: &mut LockedState) {
 
         // Update the task and return a clone to build the callback.
         let task = {
-            let task = state.tasks_mut().get_mut(&id).unwrap();
+            let task = state.task_mut(id).unwrap();
             // We know that this must be true, but we have to check anyway.
             let TaskStatus::Queued { enqueued_at } = task.status else {
                 continue;
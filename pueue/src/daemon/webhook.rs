@@ -0,0 +1,108 @@
+//! Native notifications via HTTP.
+//!
+//! In contrast to callbacks, webhooks don't go through a shell. The payload is serialized as JSON,
+//! which is why commands or outputs with quotes don't break anything.
+use std::{thread, time::Duration};
+
+use pueue_lib::{Settings, Task, settings::Webhook};
+use serde_json::{Map, Value};
+
+use crate::{
+    daemon::{callbacks::callback_parameters, internal_state::state::LockedState},
+    internal_prelude::*,
+};
+
+/// The delay before the first retry of a failed request.
+/// The delay is doubled with every further retry.
+const INITIAL_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Notify all webhooks that're interested in a finished task.
+///
+/// Requests are sent from a separate thread, so slow or unreachable endpoints don't block the
+/// daemon.
+pub fn notify_webhooks(settings: &Settings, state: &LockedState, task: &Task) {
+    let webhooks: Vec<Webhook> = settings
+        .daemon
+        .webhooks
+        .iter()
+        .filter(|webhook| webhook.groups.is_empty() || webhook.groups.contains(&task.group))
+        .cloned()
+        .collect();
+    if webhooks.is_empty() {
+        return;
+    }
+
+    let payload = match build_payload(settings, state, task) {
+        Ok(payload) => payload,
+        Err(err) => {
+            error!(
+                "Failed to build webhook payload for task {}: {err:?}",
+                task.id
+            );
+            return;
+        }
+    };
+
+    for webhook in webhooks {
+        let payload = payload.clone();
+        let task_id = task.id;
+        thread::spawn(move || send(&webhook, &payload, task_id));
+    }
+}
+
+/// The payload contains all variables of the callback template and the full task.
+///
+/// The environment variables of the task are left out, as they're the captured environment of
+/// the client and may contain credentials, which shouldn't be sent to a remote endpoint.
+fn build_payload(settings: &Settings, state: &LockedState, task: &Task) -> Result<Value> {
+    let mut payload: Map<String, Value> = callback_parameters(settings, state, task)
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), Value::String(value)))
+        .collect();
+    let mut task = serde_json::to_value(task)?;
+    if let Value::Object(task) = &mut task {
+        task.remove("envs");
+    }
+    payload.insert("task".into(), task);
+
+    Ok(Value::Object(payload))
+}
+
+/// Send the payload to a single webhook.
+/// Failed requests are retried with an exponential backoff.
+fn send(webhook: &Webhook, payload: &Value, task_id: usize) {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(webhook.timeout))
+        .build();
+
+    let mut delay = INITIAL_RETRY_DELAY;
+    for attempt in 0..=webhook.retries {
+        if attempt > 0 {
+            thread::sleep(delay);
+            delay *= 2;
+        }
+
+        let mut request = agent.post(&webhook.url);
+        for (name, value) in &webhook.headers {
+            request = request.set(name, value);
+        }
+
+        match request.send_json(payload) {
+            Ok(_) => {
+                debug!("Notified webhook {} about task {task_id}", webhook.url);
+                return;
+            }
+            Err(err) => warn!(
+                "Failed to notify webhook {} about task {task_id} (attempt {}): {err}",
+                webhook.url,
+                attempt + 1
+            ),
+        }
+    }
+
+    error!(
+        "Giving up on webhook {} for task {task_id} after {} attempts",
+        webhook.url,
+        webhook.retries + 1
+    );
+}
@@ -1,6 +1,17 @@
-use libproc::libproc::{proc_pid, task_info};
+use libproc::libproc::{bsd_info::BSDInfo, proc_pid, task_info};
 
 /// Check, whether a specific process exists or not
 pub fn process_exists(pid: u32) -> bool {
     proc_pid::pidinfo::<task_info::TaskInfo>(pid.try_into().unwrap(), 0).is_ok()
 }
+
+/// Get a value that identifies a specific process, even if its pid is reused later on.
+/// The start time of a process is given as wall clock time, which makes it unique.
+pub fn process_identity(pid: u32) -> Option<String> {
+    let info = proc_pid::pidinfo::<BSDInfo>(pid.try_into().ok()?, 0).ok()?;
+
+    Some(format!(
+        "{}.{}",
+        info.pbi_start_tvsec, info.pbi_start_tvusec
+    ))
+}
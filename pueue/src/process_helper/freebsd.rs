@@ -5,6 +5,12 @@ pub fn process_exists(pid: u32) -> bool {
     return Path::new(&format!("/proc/{}", pid)).is_dir();
 }
 
+/// Get a value that identifies a specific process, even if its pid is reused later on.
+/// This isn't supported on this platform yet, which is why tasks cannot be re-adopted.
+pub fn process_identity(_pid: u32) -> Option<String> {
+    None
+}
+
 #[cfg(test)]
 pub mod tests {
     /// Get all processes in a process group
@@ -10,3 +10,15 @@ pub fn process_exists(pid: u32) -> bool {
         },
     }
 }
+
+/// Get a value that identifies a specific process, even if its pid is reused later on.
+///
+/// It consists of the id of the current boot and the start time of the process, as the start time
+/// is only given in clock ticks since boot.
+pub fn process_identity(pid: u32) -> Option<String> {
+    let pid = pid.try_into().ok()?;
+    let stat = process::Process::new(pid).ok()?.stat().ok()?;
+    let boot_id = std::fs::read_to_string("/proc/sys/kernel/random/boot_id").ok()?;
+
+    Some(format!("{}:{}", boot_id.trim(), stat.starttime))
+}
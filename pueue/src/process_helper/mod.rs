@@ -14,7 +14,7 @@ use crate::internal_prelude::*;
 #[cfg(unix)]
 mod unix;
 #[cfg(unix)]
-use command_group::Signal;
+pub use command_group::Signal;
 
 #[cfg(unix)]
 pub use self::unix::*;
@@ -59,6 +59,29 @@ pub fn signal_from_internal(signal: InternalSignal) -> Signal {
     }
 }
 
+/// Make sure that a user provided shell command can be used to run tasks.
+///
+/// Rendering a shell command happens right before a task is spawned, which is why invalid
+/// templates should be rejected as early as possible.
+pub fn validate_shell_command(shell_command: &[String]) -> Result<()> {
+    if shell_command.is_empty() {
+        bail!("The shell command must contain at least the executable.");
+    }
+
+    let mut handlebars = handlebars::Handlebars::new();
+    handlebars.set_strict_mode(true);
+
+    let mut parameters = HashMap::new();
+    parameters.insert("pueue_command_string", "");
+    for part in shell_command {
+        handlebars
+            .render_template(part, &parameters)
+            .wrap_err_with(|| format!("Failed to render shell command part: {part}"))?;
+    }
+
+    Ok(())
+}
+
 /// Take a platform specific shell command and insert the actual task command via templating.
 pub fn compile_shell_command(settings: &Settings, command: &str) -> Command {
     let shell_command = get_shell_command(settings);
@@ -2,11 +2,22 @@
 // As soon as it's obvious that this is code is intended to be exposed to library users, we
 // have to go ahead and replace any `anyhow` usage by proper error handling via our own Error
 // type.
+use std::{
+    ffi::{CStr, CString},
+    fs::read_to_string,
+    os::unix::{ffi::OsStrExt, process::CommandExt},
+    path::{Path, PathBuf},
+    process::Command,
+};
+
 use color_eyre::Result;
 use command_group::{GroupChild, Signal, UnixChildExt};
 use pueue_lib::Settings;
 
-use crate::internal_prelude::*;
+use crate::{
+    internal_prelude::*,
+    process_helper::{process_exists, process_identity},
+};
 
 pub fn get_shell_command(settings: &Settings) -> Vec<String> {
     let Some(ref shell_command) = settings.daemon.shell_command else {
@@ -26,6 +37,209 @@ pub fn send_signal_to_child(child: &mut GroupChild, signal: Signal) -> Result<()
     Ok(())
 }
 
+/// Wrap a compiled command into a small supervisor process.
+///
+/// The supervisor is forked right before the command is executed and stays the leader of the
+/// task's process group. It ignores `SIGHUP`, `SIGINT`, `SIGQUIT` and `SIGTERM`, waits for the
+/// command and writes the command's wait status to `exit_code_path`. That way, the result of a
+/// task can be recovered, even if the daemon that spawned the task is no longer around.
+///
+/// The command itself starts with the default signal handlers. Once it finishes, the supervisor
+/// exits the same way, i.e. it re-raises the signal that killed the command. The daemon thereby
+/// sees the same result as for unsupervised tasks.
+pub fn supervise_command(command: &mut Command, exit_code_path: &Path) -> std::io::Result<()> {
+    let exit_code_path = CString::new(exit_code_path.as_os_str().as_bytes())?;
+
+    // SAFETY: The closure runs between `fork` and `exec` and only does async-signal-safe syscalls
+    // without allocating any memory.
+    unsafe {
+        command.pre_exec(move || supervise(&exit_code_path));
+    }
+
+    Ok(())
+}
+
+/// The signals the supervisor doesn't react to, so it's always able to record the result of the
+/// command. They're delivered to the whole process group, so the command receives them anyway.
+const IGNORED_SIGNALS: [libc::c_int; 4] =
+    [libc::SIGHUP, libc::SIGINT, libc::SIGQUIT, libc::SIGTERM];
+
+/// The body of the supervisor process.
+///
+/// This returns in the forked command process, which then continues to `exec` the command.
+/// The supervisor itself never returns.
+unsafe fn supervise(exit_code_path: &CStr) -> std::io::Result<()> {
+    // SAFETY: See `supervise_command`.
+    unsafe {
+        let pid = libc::fork();
+        if pid == -1 {
+            return Err(std::io::Error::last_os_error());
+        } else if pid == 0 {
+            return Ok(());
+        }
+
+        // The spawning daemon waits until all descriptors that are closed by `exec` are gone.
+        // The supervisor doesn't need any of them.
+        close_descriptors();
+        for signal in IGNORED_SIGNALS {
+            libc::signal(signal, libc::SIG_IGN);
+        }
+
+        let mut status = 0;
+        while libc::waitpid(pid, &mut status, 0) == -1 {
+            if std::io::Error::last_os_error().raw_os_error() != Some(libc::EINTR) {
+                libc::_exit(127);
+            }
+        }
+
+        if libc::WIFSIGNALED(status) {
+            let signal = libc::WTERMSIG(status);
+            write_status(exit_code_path, b"signal:", signal);
+
+            // Die by the same signal, without dumping the core of the supervisor.
+            let no_core = libc::rlimit {
+                rlim_cur: 0,
+                rlim_max: 0,
+            };
+            libc::setrlimit(libc::RLIMIT_CORE, &no_core);
+            libc::signal(signal, libc::SIG_DFL);
+            let mut mask = std::mem::zeroed();
+            libc::sigemptyset(&mut mask);
+            libc::sigaddset(&mut mask, signal);
+            libc::sigprocmask(libc::SIG_UNBLOCK, &mask, std::ptr::null_mut());
+            libc::kill(libc::getpid(), signal);
+            libc::_exit(128 + signal);
+        }
+
+        let exit_code = libc::WEXITSTATUS(status);
+        write_status(exit_code_path, b"exit:", exit_code);
+        libc::_exit(exit_code);
+    }
+}
+
+/// Close all file descriptors except for stdin, stdout and stderr.
+unsafe fn close_descriptors() {
+    // SAFETY: See `supervise_command`.
+    unsafe {
+        #[cfg(target_os = "linux")]
+        if libc::syscall(libc::SYS_close_range, 3, libc::c_uint::MAX, 0) == 0 {
+            return;
+        }
+
+        let max_fd = match libc::sysconf(libc::_SC_OPEN_MAX) {
+            -1 => 1024,
+            max => max.min(65536) as libc::c_int,
+        };
+        for fd in 3..max_fd {
+            libc::close(fd);
+        }
+    }
+}
+
+/// Write `{prefix}{value}` to the file at the given path without allocating any memory.
+unsafe fn write_status(path: &CStr, prefix: &[u8], value: libc::c_int) {
+    let mut buffer = [0u8; 32];
+    buffer[..prefix.len()].copy_from_slice(prefix);
+    let mut length = prefix.len();
+
+    // Write the digits in reverse order and flip them afterwards.
+    let digits_start = length;
+    let mut value = value.unsigned_abs();
+    loop {
+        buffer[length] = b'0' + (value % 10) as u8;
+        length += 1;
+        value /= 10;
+        if value == 0 {
+            break;
+        }
+    }
+    buffer[digits_start..length].reverse();
+
+    // SAFETY: See `supervise_command`.
+    unsafe {
+        let fd = libc::open(
+            path.as_ptr(),
+            libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC | libc::O_CLOEXEC,
+            0o644,
+        );
+        if fd == -1 {
+            return;
+        }
+        libc::write(fd, buffer.as_ptr().cast(), length);
+        libc::close(fd);
+    }
+}
+
+/// Parse the status that has been written by the supervisor.
+///
+/// Returns the exit code of the command or `None`, if it has been killed by a signal.
+pub fn parse_supervisor_status(status: &str) -> Option<Option<i32>> {
+    let status = status.trim();
+    if let Some(exit_code) = status.strip_prefix("exit:") {
+        return exit_code.parse().ok().map(Some);
+    } else if status.starts_with("signal:") {
+        return Some(None);
+    }
+
+    // Supervisors of previous versions only wrote the plain exit code.
+    status.parse().ok().map(Some)
+}
+
+/// A supervised process group that has been spawned by a previous daemon instance.
+///
+/// As this process isn't a child of the current daemon, we cannot wait for it.
+/// Instead, we check whether it's still alive and read its exit code from the file that's written
+/// by the supervisor.
+#[derive(Debug)]
+pub struct AdoptedChild {
+    /// The pid of the supervisor, which is also the id of the task's process group.
+    pub pid: u32,
+    /// The identity of the supervisor process, see [`process_identity`].
+    /// This protects against signaling unrelated processes, once the pid has been reused.
+    pub identity: String,
+    pub exit_code_path: PathBuf,
+}
+
+impl AdoptedChild {
+    /// Check whether the supervisor is still the process that has been adopted.
+    pub fn is_alive(&self) -> bool {
+        process_exists(self.pid)
+            && process_identity(self.pid).is_some_and(|identity| identity == self.identity)
+    }
+
+    /// Returns `None` as long as the process is alive.
+    /// Once it's gone, the exit code is returned. The exit code is `None`, if the command has been
+    /// killed by a signal or if the supervisor didn't get the chance to write it.
+    pub fn try_wait(&self) -> Option<Option<i32>> {
+        if self.is_alive() {
+            return None;
+        }
+
+        let exit_code = read_to_string(&self.exit_code_path)
+            .ok()
+            .and_then(|status| parse_supervisor_status(&status))
+            .flatten();
+
+        Some(exit_code)
+    }
+
+    /// Send a signal to the whole process group of the adopted process.
+    /// Fails with `ESRCH`, if the adopted process is gone, even if its pid has been reused.
+    pub fn signal(&self, signal: Signal) -> std::io::Result<()> {
+        if !self.is_alive() {
+            return Err(std::io::Error::from_raw_os_error(libc::ESRCH));
+        }
+
+        // SAFETY: `killpg` doesn't touch any memory, it's only a syscall with plain integers.
+        let result = unsafe { libc::killpg(self.pid as libc::pid_t, signal as libc::c_int) };
+        if result == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+}
+
 /// This is a helper function to safely kill a child process group.
 /// Its purpose is to properly kill all processes and prevent any dangling processes.
 pub fn kill_child(task_id: usize, child: &mut GroupChild) -> std::io::Result<()> {
@@ -252,4 +466,40 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    /// Ensure the supervisor records the exit code of a command and exits with it.
+    fn test_supervised_command_exit_code() -> Result<()> {
+        let tempdir = tempfile::TempDir::new()?;
+        let exit_code_path = tempdir.path().join("0.exit");
+
+        let settings = Settings::default();
+        let mut command = compile_shell_command(&settings, "exit 3");
+        supervise_command(&mut command, &exit_code_path)?;
+        let status = command.group_spawn()?.wait()?;
+
+        assert_eq!(status.code(), Some(3));
+        assert_eq!(read_to_string(&exit_code_path)?, "exit:3");
+
+        Ok(())
+    }
+
+    #[test]
+    /// Ensure that a command that's killed by a signal is recorded as such, and that the command
+    /// isn't immune to `SIGHUP`, even though the supervisor is.
+    fn test_supervised_command_killed_by_signal() -> Result<()> {
+        let tempdir = tempfile::TempDir::new()?;
+        let exit_code_path = tempdir.path().join("0.exit");
+
+        let mut command = Command::new("sh");
+        command.arg("-c").arg("kill -HUP $$; sleep 5");
+        supervise_command(&mut command, &exit_code_path)?;
+        let status = command.group_spawn()?.wait()?;
+
+        assert_eq!(status.code(), None);
+        assert_eq!(read_to_string(&exit_code_path)?, "signal:1");
+        assert_eq!(parse_supervisor_status("signal:1"), Some(None));
+
+        Ok(())
+    }
 }
@@ -0,0 +1,30 @@
+use pueue_lib::task::DependencyCondition;
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Make sure that dependencies with conditions can be specified via `--after`.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn dependency_conditions() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+    run_client_command(shared, &["add", "--after", "0:failure", "1", "--", "ls"])?;
+
+    let state = get_state(shared).await?;
+    let task = &state.tasks[&2];
+    assert_eq!(task.dependencies, vec![0, 1]);
+    assert_eq!(task.dependency_condition(0), DependencyCondition::Failure);
+    assert_eq!(task.dependency_condition(1), DependencyCondition::Success);
+
+    // Unknown conditions are rejected by the client.
+    let output = run_client_command(shared, &["add", "--after", "0:maybe", "--", "ls"])?;
+    assert!(
+        !output.status.success(),
+        "Unknown conditions should be rejected"
+    );
+    assert!(String::from_utf8_lossy(&output.stderr).contains("unknown condition 'maybe'"));
+
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use pueue_lib::{Task, TaskResult, TaskStatus, settings::Shared};
+use serde_json::Value;
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Get the roll-up of a single array via `pueue array status --json`.
+fn array_status(shared: &Shared, name: &str) -> Result<Value> {
+    let output = run_client_command(shared, &["array", "status", "--json", name])?;
+    let mut summaries: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+    Ok(summaries.remove(0))
+}
+
+/// An array creates one task per parameter combination, which can be controlled as a unit.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn array_sweep() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let output = run_client_command(
+        shared,
+        &[
+            "array",
+            "add",
+            "--name",
+            "sweep",
+            "-p",
+            "x=1..3",
+            "-p",
+            "y=a,b",
+            "--",
+            "test",
+            "{{x}}{{y}}",
+            "!=",
+            "2b",
+        ],
+    )?;
+    assert!(
+        output.status.success(),
+        "Adding the array failed: {output:?}"
+    );
+    assert_eq!(
+        "New array 'sweep' added with 6 tasks (ids 0-5).",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    for id in 0..6 {
+        wait_for_task_condition(shared, id, Task::is_done).await?;
+    }
+
+    let state = get_state(shared).await?;
+    let task = &state.tasks[&3];
+    assert_eq!(task.command, "test 2b != 2b");
+    assert_eq!(task.label, Some("sweep".to_string()));
+    assert_eq!(task.array, Some("sweep".to_string()));
+    assert_eq!(task.envs["PUEUE_ARRAY_INDEX"], "3");
+
+    let summary = array_status(shared, "sweep")?;
+    assert_eq!(summary["total"], 6);
+    assert_eq!(summary["succeeded"], 5);
+    assert_eq!(summary["failed"], 1);
+
+    // Only the failed member is restarted.
+    run_client_command(shared, &["array", "restart", "sweep"])?;
+    wait_for_task_condition(shared, 3, |task| !task.is_done()).await?;
+    let task = wait_for_task_condition(shared, 3, Task::is_done).await?;
+    assert!(task.failed(), "The restarted task should fail again");
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks.len(), 6, "Members are restarted in place");
+
+    Ok(())
+}
+
+/// Killing an array kills its running members and stashes the queued ones.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn array_kill() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(
+        shared,
+        &[
+            "array",
+            "add",
+            "-p",
+            "n=1..3",
+            "--",
+            "sleep 60 && echo {{n}}",
+        ],
+    )?;
+    wait_for_task_condition(shared, 0, Task::is_running).await?;
+
+    let output = run_client_command(shared, &["array", "kill", "array-0"])?;
+    assert!(
+        output.status.success(),
+        "Killing the array failed: {output:?}"
+    );
+
+    let task = wait_for_task_condition(shared, 0, Task::is_done).await?;
+    assert!(matches!(
+        task.status,
+        TaskStatus::Done {
+            result: TaskResult::Killed,
+            ..
+        }
+    ));
+
+    let state = get_state(shared).await?;
+    assert!(state.tasks[&1].is_stashed());
+    assert!(state.tasks[&2].is_stashed());
+
+    // Unknown arrays are reported.
+    let output = run_client_command(shared, &["array", "kill", "unknown"])?;
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("There's no array with the name 'unknown'")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,43 @@
+use pueue_lib::{Task, message::CallbackRun};
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Make sure that the recorded callback runs can be listed, including their output.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn list_callbacks() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.callback = Some("echo 'callback for {{id}}'".into());
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // Nothing has been run yet.
+    let output = run_client_command(shared, &["callbacks"])?;
+    assert_eq!(
+        "No callbacks have been run yet.",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    run_client_command(shared, &["add", "ls"])?;
+    wait_for_task_condition(shared, 0, Task::is_done).await?;
+
+    // Wait for the callback to finish.
+    let mut runs: Vec<CallbackRun> = Vec::new();
+    for _ in 0..100 {
+        let output = run_client_command(shared, &["callbacks", "--json"])?;
+        runs = serde_json::from_slice(&output.stdout)?;
+        if !runs.is_empty() {
+            break;
+        }
+        sleep_ms(50).await;
+    }
+    assert_eq!(runs.len(), 1, "The callback should have been recorded");
+    assert_eq!(runs[0].output, "callback for 0\n");
+
+    let output = run_client_command(shared, &["callbacks", "--output"])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Output of run 0 (callback):\ncallback for 0"));
+
+    Ok(())
+}
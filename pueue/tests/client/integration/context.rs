@@ -0,0 +1,165 @@
+use pueue_lib::state::State;
+use serde_json::Value;
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Store the contexts of a daemon's client in its temporary directory.
+fn use_temporary_contexts_file(daemon: &mut PueueDaemon) -> Result<()> {
+    daemon.settings.client.contexts_file = Some(daemon.tempdir.path().join("contexts.yml"));
+    daemon
+        .settings
+        .save(&Some(daemon.tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+
+    Ok(())
+}
+
+/// Read the contexts file of a daemon's client.
+fn read_contexts(daemon: &PueueDaemon) -> Result<Value> {
+    let content = std::fs::read_to_string(daemon.tempdir.path().join("contexts.yml"))?;
+    Ok(serde_yaml::from_str(&content)?)
+}
+
+/// Contexts are written to the contexts file and relative paths are stored as absolute paths.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn contexts_file_round_trip() -> Result<()> {
+    let mut daemon = daemon().await?;
+    use_temporary_contexts_file(&mut daemon)?;
+    let shared = &daemon.settings.shared;
+
+    // The client is called from within the temporary directory.
+    let output = run_client_command(
+        shared,
+        &[
+            "context",
+            "add",
+            "remote",
+            "--host",
+            "10.0.0.2",
+            "--port",
+            "6925",
+            "--cert",
+            "remote.cert",
+            "--secret",
+            "remote_secret",
+        ],
+    )?;
+    assert!(output.status.success(), "Adding failed: {output:?}");
+
+    let contexts = read_contexts(&daemon)?;
+    let remote = &contexts["contexts"]["remote"];
+    assert_eq!(remote["host"], "10.0.0.2");
+    assert_eq!(remote["port"], "6925");
+    let tempdir = daemon.tempdir.path();
+    assert_eq!(
+        remote["daemon_cert"],
+        tempdir.join("remote.cert").to_string_lossy().as_ref()
+    );
+    assert_eq!(
+        remote["shared_secret_path"],
+        tempdir.join("remote_secret").to_string_lossy().as_ref()
+    );
+    assert_eq!(contexts["current"], Value::Null);
+
+    let output = run_client_command(shared, &["context", "list"])?;
+    assert_eq!(
+        "remote  10.0.0.2:6925",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    Ok(())
+}
+
+/// Using no context or removing the current context falls back to the configuration file.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn use_and_remove_clear_current() -> Result<()> {
+    let mut daemon = daemon().await?;
+    use_temporary_contexts_file(&mut daemon)?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(shared, &["context", "add", "first", "--host", "10.0.0.1"])?;
+    run_client_command(
+        shared,
+        &["context", "add", "second", "--host", "10.0.0.2", "--use"],
+    )?;
+    assert_eq!(read_contexts(&daemon)?["current"], "second");
+
+    run_client_command(shared, &["context", "use"])?;
+    assert_eq!(read_contexts(&daemon)?["current"], Value::Null);
+
+    run_client_command(shared, &["context", "use", "first"])?;
+    assert_eq!(read_contexts(&daemon)?["current"], "first");
+
+    // Unknown contexts cannot be used.
+    let output = run_client_command(shared, &["context", "use", "third"])?;
+    assert!(
+        !output.status.success(),
+        "Unknown contexts must be rejected"
+    );
+    assert_eq!(read_contexts(&daemon)?["current"], "first");
+
+    run_client_command(shared, &["context", "remove", "first"])?;
+    let contexts = read_contexts(&daemon)?;
+    assert_eq!(contexts["current"], Value::Null);
+    assert_eq!(contexts["contexts"]["first"], Value::Null);
+
+    Ok(())
+}
+
+/// Commands are sent to the daemon of the current context, unless `--context` is given.
+/// `--all-contexts` shows the tasks of all daemons in a single table.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn context_overrides_current() -> Result<()> {
+    let mut local = daemon().await?;
+    use_temporary_contexts_file(&mut local)?;
+    let remote = daemon().await?;
+    run_client_command(
+        &remote.settings.shared,
+        &["add", "--stashed", "remote task"],
+    )?;
+
+    let shared = &local.settings.shared;
+    for (name, daemon_shared) in [("local", shared), ("remote", &remote.settings.shared)] {
+        let output = run_client_command(
+            shared,
+            &[
+                "context",
+                "add",
+                name,
+                "--unix-socket",
+                daemon_shared.unix_socket_path().to_str().unwrap(),
+                "--secret",
+                daemon_shared.shared_secret_path().to_str().unwrap(),
+            ],
+        )?;
+        assert!(output.status.success(), "Adding failed: {output:?}");
+    }
+    run_client_command(shared, &["context", "use", "remote"])?;
+
+    // The current context is used by default.
+    let output = run_client_command(shared, &["status", "--json"])?;
+    let state: State = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(state.tasks[&0].command, "remote task");
+
+    // `--context` overrides the current context.
+    let output = run_client_command(shared, &["--context", "local", "status", "--json"])?;
+    let state: State = serde_json::from_slice(&output.stdout)?;
+    assert!(
+        state.tasks.is_empty(),
+        "Expected the local daemon: {state:?}"
+    );
+
+    let output = run_client_command(shared, &["status", "--all-contexts", "columns=id,command"])?;
+    assert!(output.status.success(), "Status failed: {output:?}");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.contains("Context"),
+        "Missing context column: {stdout}"
+    );
+    assert!(
+        stdout.contains("remote") && stdout.contains("remote task"),
+        "Missing remote task: {stdout}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use pueue_lib::{TaskStatus, task::DependencyCondition};
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Tasks that are exported from one daemon are recreated on another one, including their
+/// groups, logs and dependencies.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn export_and_import() -> Result<()> {
+    let source = daemon().await?;
+    let shared = &source.settings.shared;
+
+    run_client_command(shared, &["group", "add", "remote", "--parallel", "3"])?;
+    run_client_command(shared, &["add", "echo exported"])?;
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    run_client_command(shared, &["add", "--stashed", "--label", "build", "ls"])?;
+    run_client_command(
+        shared,
+        &[
+            "add",
+            "--group",
+            "remote",
+            "--after",
+            "1:failure",
+            "--",
+            "ls",
+        ],
+    )?;
+
+    let path = source.tempdir.path().join("export.json");
+    let output = run_client_command(
+        shared,
+        &[
+            "export",
+            "--all",
+            "--logs",
+            "--output",
+            path.to_str().unwrap(),
+        ],
+    )?;
+    assert!(output.status.success(), "Export failed: {output:?}");
+
+    // Import the tasks into another daemon, which already has a task.
+    let target = daemon().await?;
+    let shared = &target.settings.shared;
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+
+    let output = run_client_command(shared, &["import", path.to_str().unwrap()])?;
+    assert!(output.status.success(), "Import failed: {output:?}");
+    assert_eq!(
+        "Imported 3 tasks:\n  0 -> 1\n  1 -> 2\n  2 -> 3",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    let state = get_state(shared).await?;
+    assert!(state.tasks[&1].is_done(), "Finished tasks stay finished");
+    assert!(get_task_log(shared, 1, None).await?.contains("exported"));
+
+    let build = &state.tasks[&2];
+    assert_eq!(build.label, Some("build".to_string()));
+    assert!(matches!(build.status, TaskStatus::Stashed { .. }));
+
+    // Unfinished tasks are stashed and their dependencies point to the new ids.
+    let dependant = &state.tasks[&3];
+    assert!(matches!(dependant.status, TaskStatus::Stashed { .. }));
+    assert_eq!(dependant.group, "remote");
+    assert_eq!(dependant.dependencies, vec![2]);
+    assert_eq!(
+        dependant.dependency_condition(2),
+        DependencyCondition::Failure
+    );
+    assert_eq!(state.groups["remote"].parallel_tasks, 3);
+
+    Ok(())
+}
+
+/// Dependencies on tasks that aren't part of the export are dropped and reported.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn import_reports_dropped_dependencies() -> Result<()> {
+    let source = daemon().await?;
+    let shared = &source.settings.shared;
+
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+    run_client_command(shared, &["add", "--after", "0", "1", "--", "ls"])?;
+
+    // Only export the dependant task and one of its dependencies.
+    let path = source.tempdir.path().join("export.json");
+    let output = run_client_command(
+        shared,
+        &["export", "1", "2", "--output", path.to_str().unwrap()],
+    )?;
+    assert!(output.status.success(), "Export failed: {output:?}");
+
+    let target = daemon().await?;
+    let shared = &target.settings.shared;
+    let output = run_client_command(shared, &["import", path.to_str().unwrap()])?;
+    assert!(output.status.success(), "Import failed: {output:?}");
+    assert_eq!(
+        "Imported 2 tasks:\n  1 -> 0\n  2 -> 1\nDropped dependencies of task 2 on tasks that weren't exported: 0",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&1].dependencies, vec![0]);
+
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use pueue_lib::{Task, settings::Shared};
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Create a small diamond shaped graph, in which one branch fails.
+async fn create_graph(shared: &Shared) -> Result<()> {
+    run_client_command(shared, &["add", "--label", "build", "ls"])?;
+    run_client_command(shared, &["add", "--after", "0", "--", "false"])?;
+    run_client_command(shared, &["add", "--after", "0", "--", "ls"])?;
+    run_client_command(shared, &["add", "--after", "1", "2", "--", "ls"])?;
+    run_client_command(
+        shared,
+        &["add", "--stashed", "--after", "1:failure", "--", "ls"],
+    )?;
+
+    for id in 0..4 {
+        wait_for_task_condition(shared, id, Task::is_done).await?;
+    }
+
+    Ok(())
+}
+
+/// The graph is printed as a tree, starting at tasks without dependencies.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn graph_tree() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+    create_graph(shared).await?;
+
+    let output = run_client_command(shared, &["graph"])?;
+    let expected = "\
+0 build [Success]
+├─ 1 false [Failed (1)]
+│  ├─ 3 ls [Dependency failed]
+│  └─ 4 ls [Stashed] (on failure)
+└─ 2 ls [Success]
+   └─ 3 ls [Dependency failed] (see above)
+";
+    assert_eq!(expected, String::from_utf8_lossy(&output.stdout));
+
+    // Selecting a task only shows its dependencies and dependants.
+    let output = run_client_command(shared, &["graph", "2"])?;
+    let expected = "\
+0 build [Success]
+└─ 2 ls [Success]
+   └─ 3 ls [Dependency failed]
+";
+    assert_eq!(expected, String::from_utf8_lossy(&output.stdout));
+
+    Ok(())
+}
+
+/// The graph can be exported in the DOT and Mermaid formats.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn graph_dot_and_mermaid() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+    create_graph(shared).await?;
+
+    let output = run_client_command(shared, &["graph", "--format", "dot", "4"])?;
+    let expected = r##"digraph pueue {
+    rankdir=LR;
+    node [shape=box, style="rounded,filled"];
+    0 [label="0: build\nSuccess", fillcolor="#a6e3a1"];
+    1 [label="1: false\nFailed (1)", fillcolor="#f38ba8"];
+    4 [label="4: ls\nStashed", fillcolor="#f9e2af"];
+    0 -> 1;
+    1 -> 4 [label="failure"];
+}
+"##;
+    assert_eq!(expected, String::from_utf8_lossy(&output.stdout));
+
+    let output = run_client_command(shared, &["graph", "--format", "mermaid", "4"])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.starts_with("flowchart LR\n"));
+    assert!(stdout.contains("    task1[\"1: false<br/>Failed (1)\"]:::failed\n"));
+    assert!(stdout.contains("    task1 -->|failure| task4\n"));
+
+    Ok(())
+}
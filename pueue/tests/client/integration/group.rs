@@ -73,3 +73,62 @@ async fn json() -> Result<()> {
 
     Ok(())
 }
+
+/// Edit the task defaults of a group and make sure they're applied to new tasks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn edit() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+    let working_directory = daemon.tempdir.path().join("group_dir");
+    std::fs::create_dir(&working_directory)?;
+
+    let output = run_client_command(
+        shared,
+        &[
+            "group",
+            "edit",
+            "test_2",
+            "--env",
+            "GROUP_ENV=edited",
+            "--working-directory",
+            working_directory.to_str().unwrap(),
+            "--shell-command",
+            "bash",
+            "-c",
+            "echo wrapped; {{ pueue_command_string }}",
+        ],
+    )?;
+    assert!(output.status.success(), "Failed to edit group: {output:?}");
+
+    let state = get_state(shared).await?;
+    let group = &state.groups["test_2"];
+    assert_eq!(group.envs.get("GROUP_ENV"), Some(&"edited".to_string()));
+    assert_eq!(group.working_directory, Some(working_directory.clone()));
+
+    // New tasks use the group's working directory, if none is specified.
+    run_client_command(shared, &["add", "--group", "test_2", "echo $GROUP_ENV"])?;
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_eq!(task.path, working_directory);
+    assert_eq!(get_task_log(shared, 0, None).await?, "wrapped\nedited\n");
+
+    // Remove all defaults again.
+    run_client_command(
+        shared,
+        &[
+            "group",
+            "edit",
+            "test_2",
+            "--unset-env",
+            "GROUP_ENV",
+            "--clear-working-directory",
+            "--clear-shell-command",
+        ],
+    )?;
+    let state = get_state(shared).await?;
+    let group = &state.groups["test_2"];
+    assert!(group.envs.is_empty());
+    assert_eq!(group.working_directory, None);
+    assert_eq!(group.shell_command, None);
+
+    Ok(())
+}
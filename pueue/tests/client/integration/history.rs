@@ -0,0 +1,44 @@
+use pueue_lib::Task;
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Cleaned and removed tasks are archived and can be queried via `pueue history`.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn history_of_cleaned_tasks() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.archive.enabled = true;
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(shared, &["add", "echo first"])?;
+    run_client_command(shared, &["add", "false"])?;
+    run_client_command(shared, &["add", "echo third"])?;
+    for id in 0..3 {
+        wait_for_task_condition(shared, id, Task::is_done).await?;
+    }
+
+    // Remove one task explicitly and clean the rest.
+    run_client_command(shared, &["remove", "2"])?;
+    run_client_command(shared, &["clean"])?;
+    let state = get_state(shared).await?;
+    assert!(state.tasks.is_empty());
+
+    let output = run_client_command(shared, &["history", "--json"])?;
+    let tasks: Vec<Task> = serde_json::from_slice(&output.stdout)?;
+    let commands: Vec<&str> = tasks.iter().map(|task| task.command.as_str()).collect();
+    assert_eq!(commands, vec!["echo third", "echo first", "false"]);
+
+    // The status query syntax can be used to filter the archive.
+    let output = run_client_command(shared, &["history", "--json", "status=failed"])?;
+    let tasks: Vec<Task> = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0].command, "false");
+
+    let output = run_client_command(shared, &["history", "columns=id,command"])?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("echo first"), "Got: {stdout}");
+
+    Ok(())
+}
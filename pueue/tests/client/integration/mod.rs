@@ -1,10 +1,20 @@
+mod add;
+mod array;
+mod callbacks;
 mod completions;
 mod configuration;
+mod context;
 mod edit;
 mod env;
+mod export;
 mod follow;
+mod graph;
 mod group;
+mod history;
 mod log;
 mod restart;
+mod snapshot;
 mod status;
+mod submit;
+mod template;
 mod wait;
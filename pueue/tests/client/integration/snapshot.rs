@@ -0,0 +1,91 @@
+use pueue_lib::TaskStatus;
+use serde_json::Value;
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// A reset can be reverted via `pueue undo`, which restores all tasks as stashed tasks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn undo_reset() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(shared, &["add", "--stashed", "--label", "build", "ls"])?;
+    run_client_command(shared, &["add", "--after", "0", "--", "echo test"])?;
+
+    let output = run_client_command(shared, &["reset", "--force"])?;
+    assert!(output.status.success(), "Reset failed: {output:?}");
+    wait_for_task_absence(shared, 0).await?;
+    wait_for_task_absence(shared, 1).await?;
+
+    let output = run_client_command(shared, &["snapshot"])?;
+    assert!(String::from_utf8_lossy(&output.stdout).contains("reset"));
+
+    let output = run_client_command(shared, &["undo"])?;
+    assert!(output.status.success(), "Undo failed: {output:?}");
+    assert_eq!(
+        "Restored 2 tasks as stashed tasks:\n  0 -> 0\n  1 -> 1",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks[&0].label, Some("build".to_string()));
+    assert_eq!(state.tasks[&1].dependencies, vec![0]);
+    for task in state.tasks.values() {
+        assert!(matches!(task.status, TaskStatus::Stashed { .. }));
+    }
+
+    // Each snapshot can only be restored once.
+    let output = run_client_command(shared, &["undo"])?;
+    assert!(!output.status.success(), "Nothing should be left to undo");
+
+    Ok(())
+}
+
+/// Removed tasks can be restored from a specific snapshot.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn restore_removed_tasks() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+    run_client_command(shared, &["remove", "0"])?;
+    run_client_command(shared, &["remove", "1"])?;
+
+    let output = run_client_command(shared, &["snapshot", "--json"])?;
+    let snapshots: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(snapshots.len(), 2);
+
+    // Restore the first removal.
+    let output = run_client_command(shared, &["snapshot", "restore", "0"])?;
+    assert!(output.status.success(), "Restore failed: {output:?}");
+    assert_eq!(
+        "Restored 1 tasks as stashed tasks:\n  0 -> 0",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.tasks.len(), 1);
+
+    Ok(())
+}
+
+/// Snapshot ids aren't reused after the most recent snapshot has been restored.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn snapshot_ids_are_not_reused() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    run_client_command(shared, &["add", "--stashed", "ls"])?;
+    run_client_command(shared, &["remove", "0"])?;
+    let output = run_client_command(shared, &["undo"])?;
+    assert!(output.status.success(), "Undo failed: {output:?}");
+
+    run_client_command(shared, &["remove", "0"])?;
+    let output = run_client_command(shared, &["snapshot", "--json"])?;
+    let snapshots: Vec<Value> = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(snapshots.len(), 1);
+    assert_eq!(snapshots[0]["id"], 1);
+
+    Ok(())
+}
@@ -0,0 +1,100 @@
+use pueue_lib::task::DependencyCondition;
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// A workflow file is read by the client and all of its tasks are added.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn submit_workflow() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let workflow = r#"
+tasks:
+  build:
+    command: echo build
+    stashed: true
+  test:
+    command: echo "$TEST_VAR"
+    after: [build]
+    group: test_2
+    priority: 3
+    env:
+      TEST_VAR: from workflow
+  cleanup:
+    command: echo cleanup
+    label: always
+    after: ["test:completion"]
+    working_directory: subdir
+"#;
+    let path = daemon.tempdir.path().join("workflow.yml");
+    std::fs::write(&path, workflow)?;
+    std::fs::create_dir(daemon.tempdir.path().join("subdir"))?;
+
+    let output = run_client_command(shared, &["submit", path.to_str().unwrap()])?;
+    assert!(output.status.success(), "Submit failed: {output:?}");
+    assert_eq!(
+        "Workflow added (3 tasks):\n  0: build\n  1: test\n  2: cleanup",
+        String::from_utf8_lossy(&output.stdout).trim()
+    );
+
+    let state = get_state(shared).await?;
+    let test = &state.tasks[&1];
+    assert_eq!(test.dependencies, vec![0]);
+    assert_eq!(test.group, "test_2");
+    assert_eq!(test.priority, 3);
+    assert_eq!(test.envs["TEST_VAR"], "from workflow");
+
+    let cleanup = &state.tasks[&2];
+    assert_eq!(cleanup.label, Some("always".to_string()));
+    assert_eq!(
+        cleanup.dependency_condition(1),
+        DependencyCondition::Completion
+    );
+    assert_eq!(
+        cleanup.path,
+        daemon.tempdir.path().canonicalize()?.join("subdir")
+    );
+
+    // Only known conditions are split off, so task names may contain colons.
+    let workflow = r#"
+tasks:
+  "build:x86":
+    command: ls
+  test:
+    command: ls
+    after: ["build:x86"]
+  cleanup:
+    command: ls
+    after: ["build:x86:failure"]
+"#;
+    std::fs::write(&path, workflow)?;
+    let output = run_client_command(shared, &["submit", path.to_str().unwrap()])?;
+    assert!(output.status.success(), "Submit failed: {output:?}");
+
+    let state = get_state(shared).await?;
+    assert_eq!(
+        state.tasks[&5].dependency_condition(3),
+        DependencyCondition::Success
+    );
+    assert_eq!(
+        state.tasks[&4].dependency_condition(3),
+        DependencyCondition::Failure
+    );
+
+    // Cycles are rejected by the daemon.
+    let workflow = r#"
+tasks:
+  a:
+    command: ls
+    after: [b]
+  b:
+    command: ls
+    after: [a]
+"#;
+    std::fs::write(&path, workflow)?;
+    let output = run_client_command(shared, &["submit", path.to_str().unwrap()])?;
+    assert!(!output.status.success(), "Cycles should be rejected");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("dependency cycle"));
+
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use pueue_lib::settings::Template;
+
+use crate::{client::helper::*, internal_prelude::*};
+
+/// Tasks can be created from templates, whose parameters are validated by the client.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn add_from_template() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let mut template = Template {
+        command: "echo deploying to {{env}} in {{region}}".into(),
+        group: Some("test_2".into()),
+        priority: Some(2),
+        label: Some("deploy-{{env}}".into()),
+        timeout: Some(600),
+        ..Default::default()
+    };
+    template.defaults.insert("region".into(), "us".into());
+    template.env.insert("DEPLOY_ENV".into(), "{{env}}".into());
+    settings.client.templates.insert("deploy".into(), template);
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    let output = run_client_command(
+        shared,
+        &["add", "--template", "deploy", "env=prod", "region=eu"],
+    )?;
+    assert!(output.status.success(), "Adding failed: {output:?}");
+
+    // Explicit options take precedence over the template.
+    run_client_command(
+        shared,
+        &["add", "--template", "deploy", "--priority", "5", "env=dev"],
+    )?;
+
+    let state = get_state(shared).await?;
+    let task = &state.tasks[&0];
+    assert_eq!(task.command, "echo deploying to prod in eu");
+    assert_eq!(task.group, "test_2");
+    assert_eq!(task.priority, 2);
+    assert_eq!(task.label, Some("deploy-prod".to_string()));
+    assert_eq!(task.envs["DEPLOY_ENV"], "prod");
+    assert_eq!(task.timeout, Some(600));
+
+    let task = &state.tasks[&1];
+    assert_eq!(task.command, "echo deploying to dev in us");
+    assert_eq!(task.priority, 5);
+
+    // Missing parameters are rejected.
+    let output = run_client_command(shared, &["add", "--template", "deploy"])?;
+    assert!(!output.status.success(), "Missing parameters should fail");
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Template 'deploy' requires the parameter(s) [\"env\"]")
+    );
+
+    Ok(())
+}
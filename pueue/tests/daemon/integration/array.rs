@@ -0,0 +1,96 @@
+use std::{collections::HashMap, env::vars};
+
+use pueue_lib::{message::*, settings::Shared, state::PUEUE_DEFAULT_GROUP, task::*};
+
+use crate::{helper::*, internal_prelude::*};
+
+fn create_array_message(shared: &Shared, name: &str) -> AddArrayRequest {
+    AddArrayRequest {
+        name: Some(name.to_string()),
+        commands: vec!["echo 0".into(), "echo 1".into()],
+        path: shared.pueue_directory(),
+        path_is_fallback: false,
+        envs: HashMap::from_iter(vars()),
+        group: PUEUE_DEFAULT_GROUP.into(),
+        stashed: true,
+        priority: None,
+        timeout: None,
+    }
+}
+
+/// All members of an array are added at once and array names have to be unique.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_add_array() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let response = send_request(shared, create_array_message(shared, "sweep")).await?;
+    assert_eq!(
+        response,
+        Response::AddedArray(AddedArrayResponse {
+            name: "sweep".into(),
+            task_ids: vec![0, 1],
+        })
+    );
+
+    let state = get_state(shared).await?;
+    for (id, task) in &state.tasks {
+        assert_eq!(task.array, Some("sweep".to_string()));
+        assert_eq!(task.envs["PUEUE_ARRAY_INDEX"], id.to_string());
+        assert!(task.is_stashed());
+    }
+
+    // The name is already taken.
+    assert_failure(send_request(shared, create_array_message(shared, "sweep")).await?);
+
+    Ok(())
+}
+
+/// Killing an array stashes its queued members and kills its running members at once.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_kill_array() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut message = create_array_message(shared, "sweep");
+    message.commands = vec!["sleep 60".into(), "sleep 60".into()];
+    message.stashed = false;
+    assert_success(send_request(shared, message).await?);
+    // Only one member may run at a time, the other one stays queued.
+    wait_for_task_condition(shared, 0, Task::is_running).await?;
+
+    assert_success(send_request(shared, Request::KillArray("sweep".into())).await?);
+
+    let task = wait_for_task_condition(shared, 0, Task::is_done).await?;
+    assert!(task.failed());
+    let state = get_state(shared).await?;
+    assert!(state.tasks[&1].is_stashed());
+
+    // Unknown arrays are rejected.
+    assert_failure(send_request(shared, Request::KillArray("unknown".into())).await?);
+
+    Ok(())
+}
+
+/// Restarting an array only restarts its failed members in place.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_restart_array() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut message = create_array_message(shared, "sweep");
+    message.commands = vec!["true".into(), "false".into()];
+    message.stashed = false;
+    assert_success(send_request(shared, message).await?);
+    wait_for_task_condition(shared, 0, Task::is_done).await?;
+    wait_for_task_condition(shared, 1, Task::is_done).await?;
+
+    assert_success(send_request(shared, Request::RestartArray("sweep".into())).await?);
+
+    let state = get_state(shared).await?;
+    assert!(state.tasks[&0].is_done());
+    assert!(!state.tasks[&1].is_done());
+    assert_eq!(state.tasks.len(), 2);
+
+    Ok(())
+}
@@ -1,5 +1,11 @@
 use std::fs::read_to_string;
 
+use pueue_lib::{
+    message::{CallbackRun, Request, Response},
+    settings::{GroupSettings, Shared},
+    state::Hooks,
+};
+
 use crate::{helper::*, internal_prelude::*};
 
 /// Make sure that callback commands are executed while variables are
@@ -39,3 +45,146 @@ async fn test_callback_variables() -> Result<()> {
 
     Ok(())
 }
+
+/// Make sure that hooks are executed for their respective events and that a group's hooks replace
+/// the global hooks.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_hooks() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let tempdir_path = tempdir.path().to_path_buf();
+    let path = tempdir_path.to_string_lossy().to_string();
+
+    // Each hook writes into its own file, as hooks run concurrently.
+    settings.daemon.hooks = Hooks {
+        on_start: Some(format!("echo '{{{{id}}}}' >> {path}/start")),
+        on_success: Some(format!("echo '{{{{id}}}}' >> {path}/success")),
+        on_failure: Some(format!("echo '{{{{id}}}}' >> {path}/global_failure")),
+        on_group_empty: None,
+        on_daemon_start: Some(format!("echo 'started' > {path}/daemon_start")),
+    };
+    let mut deploy = GroupSettings::default();
+    deploy.hooks.on_failure = Some(format!(
+        "echo '{{{{id}}}} {{{{result}}}}' >> {path}/failure"
+    ));
+    deploy.hooks.on_group_empty = Some(format!(
+        "echo '{{{{group}}}} {{{{queued_count}}}}' >> {path}/group_empty"
+    ));
+    settings.daemon.groups.insert("deploy".into(), deploy);
+    settings
+        .save(&Some(tempdir_path.join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // One successful task in the default group and a failing one in the deploy group.
+    assert_success(add_task(shared, "ls").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_success(add_task_to_group(shared, "failing", "deploy").await?);
+    wait_for_task_condition(shared, 1, |task| task.is_done()).await?;
+
+    // Give the hooks some time to be executed.
+    sleep_ms(1000).await;
+
+    assert_eq!(
+        read_to_string(tempdir_path.join("daemon_start"))?,
+        "started\n"
+    );
+    assert_eq!(read_to_string(tempdir_path.join("start"))?, "0\n1\n");
+    assert_eq!(read_to_string(tempdir_path.join("success"))?, "0\n");
+    assert_eq!(read_to_string(tempdir_path.join("failure"))?, "1 Failed\n");
+    // The group's hook replaces the global failure hook.
+    assert!(!tempdir_path.join("global_failure").exists());
+    // The default group has no `on_group_empty` hook.
+    assert_eq!(
+        read_to_string(tempdir_path.join("group_empty"))?,
+        "deploy 0\n"
+    );
+
+    Ok(())
+}
+
+/// Wait until the daemon recorded a given amount of callback runs.
+async fn wait_for_callback_runs(shared: &Shared, count: usize) -> Result<Vec<CallbackRun>> {
+    // Wait up to 5s for the callbacks to finish.
+    for _ in 0..100 {
+        let response = send_request(shared, Request::Callbacks).await?;
+        let Response::Callbacks(response) = response else {
+            bail!("Didn't get a callbacks response: {response:#?}");
+        };
+        if response.runs.len() >= count {
+            return Ok(response.runs);
+        }
+
+        sleep_ms(50).await;
+    }
+
+    bail!("The daemon didn't record {count} callback runs in time.")
+}
+
+/// Make sure that the outcome and output of callbacks are recorded.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_callback_runs_are_recorded() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.callback = Some("echo 'task {{id}}'; echo 'error' >&2; exit 3".into());
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "ls").await?);
+
+    let runs = wait_for_callback_runs(shared, 1).await?;
+    let run = &runs[0];
+    assert_eq!(run.task_id, Some(0));
+    assert_eq!(run.trigger, "callback");
+    assert_eq!(run.exit_code, Some(3));
+    assert!(!run.timed_out);
+    assert_eq!(run.output, "task 0\nerror\n");
+
+    Ok(())
+}
+
+/// Make sure that callbacks are killed once they exceed the callback timeout.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_callback_timeout() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.callback = Some("sleep 60".into());
+    settings.daemon.callback_timeout = Some(1);
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "ls").await?);
+
+    let runs = wait_for_callback_runs(shared, 1).await?;
+    let run = &runs[0];
+    assert!(run.timed_out);
+    assert_eq!(run.exit_code, None);
+
+    Ok(())
+}
+
+/// Huge timeouts never expire and only the end of large outputs is recorded.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_callback_huge_timeout_and_output() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.callback = Some("head -c 20000 /dev/zero | tr '\\0' a; echo end".into());
+    settings.daemon.callback_timeout = Some(u64::MAX);
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "ls").await?);
+
+    let runs = wait_for_callback_runs(shared, 1).await?;
+    let run = &runs[0];
+    assert!(!run.timed_out);
+    assert_eq!(run.exit_code, Some(0));
+    assert_eq!(run.output.len(), 16 * 1024);
+    assert!(run.output.ends_with("aaaend\n"), "Unexpected output end");
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use pueue_lib::{message::*, task::Task};
+use pueue_lib::{message::*, settings::Shared, task::Task};
 
 use crate::{helper::*, internal_prelude::*};
 
@@ -137,3 +137,76 @@ async fn test_clean_successful_only_in_selected_group() -> Result<()> {
 
     Ok(())
 }
+
+/// Wait until the daemon's state only contains the expected task ids.
+async fn wait_for_task_ids(shared: &Shared, expected: &[usize]) -> Result<()> {
+    let mut ids = Vec::new();
+    for _ in 0..100 {
+        let state = get_state(shared).await?;
+        ids = state.tasks.keys().copied().collect();
+        if ids == expected {
+            return Ok(());
+        }
+        sleep_ms(100).await;
+    }
+
+    bail!("Expected the tasks {expected:?} to remain, found {ids:?}");
+}
+
+/// The cleanup policy removes successful tasks right away, while failed tasks are kept longer.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_cleanup_policy_by_age() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.cleanup.max_age_secs = Some(0);
+    settings.daemon.cleanup.failed_max_age_secs = Some(3600);
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "ls").await?);
+    assert_success(add_task(shared, "failing").await?);
+    assert_success(add_task(shared, "sleep 60").await?);
+
+    // The successful task is removed, the failed and the running task are kept.
+    wait_for_task_ids(shared, &[1, 2]).await?;
+
+    Ok(())
+}
+
+/// The cleanup policy only keeps the most recently finished tasks of each group.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_cleanup_policy_by_count() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.cleanup.max_tasks_per_group = Some(1);
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    for _ in 0..3 {
+        assert_success(add_task(shared, "ls").await?);
+    }
+    assert_success(add_task_to_group(shared, "ls", "test_2").await?);
+
+    wait_for_task_ids(shared, &[2, 3]).await?;
+
+    Ok(())
+}
+
+/// Failed tasks are counted separately and may be kept in larger numbers.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_cleanup_policy_by_count_keeps_failed() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.cleanup.max_tasks_per_group = Some(1);
+    settings.daemon.cleanup.failed_max_tasks_per_group = Some(2);
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    for command in ["failing", "ls", "failing", "ls", "failing"] {
+        assert_success(add_task(shared, command).await?);
+    }
+
+    wait_for_task_ids(shared, &[2, 3, 4]).await?;
+
+    Ok(())
+}
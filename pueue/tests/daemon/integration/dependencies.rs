@@ -1,5 +1,8 @@
+use std::collections::BTreeMap;
+
 use pueue_lib::{
-    message::{KillRequest, TaskSelection},
+    message::{EnqueueRequest, KillRequest, TaskSelection},
+    settings::Shared,
     task::*,
 };
 
@@ -73,3 +76,94 @@ async fn test_failing_dependency() -> Result<()> {
 
     Ok(())
 }
+
+/// Add a task whose single dependency has a specific condition.
+async fn add_task_with_condition(
+    shared: &Shared,
+    command: &str,
+    dependency: usize,
+    condition: DependencyCondition,
+) -> Result<()> {
+    let mut message = create_add_message(shared, command);
+    message.dependency_conditions = BTreeMap::from([(dependency, condition)]);
+    assert_success(send_request(shared, message).await?);
+
+    Ok(())
+}
+
+/// Make sure that dependency conditions decide whether a task runs or fails.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_dependency_conditions() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // Task 0 fails, task 1 succeeds.
+    // Both are stashed, so all dependants are added before they finish.
+    assert_success(create_stashed_task(shared, "false", None).await?);
+    assert_success(create_stashed_task(shared, "true", None).await?);
+
+    add_task_with_condition(shared, "ls", 0, DependencyCondition::Success).await?;
+    add_task_with_condition(shared, "ls", 0, DependencyCondition::Failure).await?;
+    add_task_with_condition(shared, "ls", 0, DependencyCondition::Completion).await?;
+    add_task_with_condition(shared, "ls", 1, DependencyCondition::Failure).await?;
+    add_task_with_condition(shared, "ls", 1, DependencyCondition::Completion).await?;
+
+    // Default conditions aren't stored.
+    let state = get_state(shared).await?;
+    assert!(state.tasks[&2].dependency_conditions.is_empty());
+    assert_eq!(
+        state.tasks[&3].dependency_condition(0),
+        DependencyCondition::Failure
+    );
+
+    send_request(
+        shared,
+        EnqueueRequest {
+            tasks: TaskSelection::TaskIds(vec![0, 1]),
+            enqueue_at: None,
+        },
+    )
+    .await?;
+
+    let expected = [
+        (2, TaskResult::DependencyFailed),
+        (3, TaskResult::Success),
+        (4, TaskResult::Success),
+        (5, TaskResult::DependencyFailed),
+        (6, TaskResult::Success),
+    ];
+    for (id, expected_result) in expected {
+        let task = wait_for_task_condition(shared, id, Task::is_done).await?;
+        let TaskStatus::Done { result, .. } = task.status else {
+            unreachable!();
+        };
+        assert_eq!(result, expected_result, "Unexpected result for task {id}");
+    }
+
+    Ok(())
+}
+
+/// Older clients send plain dependencies, which have to succeed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_legacy_dependencies() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(create_stashed_task(shared, "ls", None).await?);
+
+    let mut message = create_add_message(shared, "ls");
+    message.dependencies = vec![0];
+    assert_success(send_request(shared, message).await?);
+
+    let state = get_state(shared).await?;
+    let task = &state.tasks[&1];
+    assert_eq!(task.dependencies, vec![0]);
+    assert_eq!(task.dependency_condition(0), DependencyCondition::Success);
+
+    // Unknown dependencies are rejected, just like with the new field.
+    let mut message = create_add_message(shared, "ls");
+    message.dependencies = vec![5];
+    assert_failure(send_request(shared, message).await?);
+
+    Ok(())
+}
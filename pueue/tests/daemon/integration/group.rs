@@ -1,4 +1,6 @@
-use pueue_lib::{Task, message::*};
+use std::collections::HashMap;
+
+use pueue_lib::{Task, message::*, settings::GroupSettings};
 
 use crate::{helper::*, internal_prelude::*};
 
@@ -79,3 +81,104 @@ async fn test_cannot_delete_group_with_tasks() -> Result<()> {
 
     Ok(())
 }
+
+/// Groups that're declared in the configuration file are created on startup.
+/// Their settings are applied to all tasks of that group.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_groups_from_config() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.groups.insert(
+        "declared".into(),
+        GroupSettings {
+            parallel_tasks: 3,
+            priority: Some(5),
+            envs: HashMap::from([("GROUP_ENV".into(), "from_group".into())]),
+            ..Default::default()
+        },
+    );
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    let state = get_state(shared).await?;
+    let group = state.groups.get("declared").expect("Group should exist");
+    assert_eq!(group.parallel_tasks, 3);
+    assert_eq!(group.priority, Some(5));
+
+    // Tasks in that group get the group's default priority and environment variables.
+    assert_success(add_task_to_group(shared, "echo $GROUP_ENV", "declared").await?);
+    let task = wait_for_task_condition(shared, 0, Task::is_done).await?;
+    assert_eq!(task.priority, 5);
+    let log = get_task_log(shared, 0, None).await?;
+    assert_eq!(log, "from_group\n");
+
+    Ok(())
+}
+
+/// Groups that're added to the configuration file are created when reloading the configuration.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_groups_reconciled_on_reload() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut settings = daemon.settings.clone();
+    settings.daemon.groups.insert(
+        "test_2".into(),
+        GroupSettings {
+            parallel_tasks: 4,
+            ..Default::default()
+        },
+    );
+    settings
+        .daemon
+        .groups
+        .insert("reloaded".into(), GroupSettings::default());
+    settings.save(&Some(daemon.tempdir.path().join("pueue.yml")))?;
+
+    let response = send_request(shared, Request::ReloadConfig).await?;
+    assert_success(response);
+
+    let state = get_state(shared).await?;
+    assert_eq!(state.groups["test_2"].parallel_tasks, 4);
+    assert_eq!(state.groups["reloaded"].parallel_tasks, 1);
+
+    // The new group should be fully usable.
+    assert_success(add_task_to_group(shared, "ls", "reloaded").await?);
+    wait_for_task_condition(shared, 0, Task::is_done).await?;
+
+    // Runtime changes are kept on reload, as long as the group's declaration doesn't change.
+    let message = ParallelRequest {
+        parallel_tasks: 2,
+        group: "reloaded".into(),
+    };
+    assert_success(send_request(shared, message).await?);
+    assert_success(send_request(shared, Request::ReloadConfig).await?);
+    let state = get_state(shared).await?;
+    assert_eq!(state.groups["reloaded"].parallel_tasks, 2);
+
+    Ok(())
+}
+
+/// Shell commands that cannot be rendered are rejected when editing a group.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_edit_invalid_shell_command() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let message = GroupRequest::Edit(GroupEditRequest {
+        name: "test_2".into(),
+        shell_command: Some(vec!["bash".into(), "-c".into(), "{{ unknown }}".into()]),
+        ..Default::default()
+    });
+    assert_failure(send_request(shared, message).await?);
+
+    // Editing a non-existing group fails as well.
+    let message = GroupRequest::Edit(GroupEditRequest {
+        name: "doesnt_exist".into(),
+        ..Default::default()
+    });
+    assert_failure(send_request(shared, message).await?);
+
+    Ok(())
+}
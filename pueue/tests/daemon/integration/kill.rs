@@ -1,3 +1,4 @@
+use assert_matches::assert_matches;
 use pretty_assertions::assert_eq;
 use pueue_lib::{GroupStatus, message::*, task::*};
 use rstest::rstest;
@@ -170,3 +171,72 @@ async fn test_kill_tasks_without_pause(#[case] kill_message: KillRequest) -> Res
 
     Ok(())
 }
+
+/// Tasks that run for longer than their timeout are killed.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_kill_on_timeout() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut message = create_add_message(shared, "sleep 60");
+    message.timeout = Some(1);
+    assert_success(send_request(shared, message).await?);
+    // A task that finishes in time isn't affected by its timeout.
+    let mut message = create_add_message(shared, "ls");
+    message.timeout = Some(60);
+    assert_success(send_request(shared, message).await?);
+
+    let task = wait_for_task_condition(shared, 0, Task::is_done).await?;
+    assert_matches!(
+        task.status,
+        TaskStatus::Done {
+            result: TaskResult::Killed,
+            ..
+        },
+        "The task should have been killed due to its timeout"
+    );
+
+    let task = wait_for_task_condition(shared, 1, Task::is_done).await?;
+    assert_matches!(
+        task.status,
+        TaskStatus::Done {
+            result: TaskResult::Success,
+            ..
+        }
+    );
+
+    Ok(())
+}
+
+/// Time a task spends paused doesn't count against its timeout.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_timeout_excludes_paused_time() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut message = create_add_message(shared, "sleep 2");
+    message.timeout = Some(3);
+    assert_success(send_request(shared, message).await?);
+    wait_for_task_condition(shared, 0, Task::is_running).await?;
+
+    // Keep the task paused for longer than its timeout.
+    assert_success(pause_tasks(shared, TaskSelection::TaskIds(vec![0])).await?);
+    wait_for_task_condition(shared, 0, |task| {
+        matches!(task.status, TaskStatus::Paused { .. })
+    })
+    .await?;
+    sleep_ms(3500).await;
+    assert_success(start_tasks(shared, TaskSelection::TaskIds(vec![0])).await?);
+
+    let task = wait_for_task_condition(shared, 0, Task::is_done).await?;
+    assert_matches!(
+        task.status,
+        TaskStatus::Done {
+            result: TaskResult::Success,
+            ..
+        },
+        "The paused time shouldn't have counted against the timeout"
+    );
+
+    Ok(())
+}
@@ -1,5 +1,6 @@
 mod add;
 mod aliases;
+mod array;
 mod callback;
 mod clean;
 mod dependencies;
@@ -11,6 +12,8 @@ mod log;
 mod parallel_tasks;
 mod pause;
 mod priority;
+/// Tests for reloading the configuration file.
+mod reload;
 mod remove;
 mod reset;
 mod restart;
@@ -22,5 +25,9 @@ mod socket_permissions;
 mod spawn;
 mod start;
 mod stashed;
+/// Tests for adding whole workflows of tasks at once.
+mod submit;
+/// Tests for notifying webhooks via HTTP.
+mod webhook;
 /// Test that the worker pool environment variables are properly injected.
 mod worker_environment_variables;
@@ -0,0 +1,105 @@
+use std::fs::write;
+
+use pueue_lib::{message::*, task::Task};
+use rstest::rstest;
+
+use crate::{helper::*, internal_prelude::*};
+
+/// Reloading the configuration should apply changes to the `daemon` section right away and
+/// report changes to the `shared` section that require a restart.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reload_config() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // Change some settings in the configuration file.
+    let mut settings = daemon.settings.clone();
+    settings.daemon.shell_command = Some(vec![
+        "sh".into(),
+        "-c".into(),
+        "echo reloaded; {{ pueue_command_string }}".into(),
+    ]);
+    settings.shared.port = "51234".into();
+    settings.save(&Some(daemon.tempdir.path().join("pueue.yml")))?;
+
+    let response = send_request(shared, Request::ReloadConfig).await?;
+    assert_eq!(
+        response,
+        Response::ConfigReloaded(ConfigReloadResponse {
+            applied: vec!["daemon.shell_command".into()],
+            requires_restart: vec!["shared.port".into()],
+        })
+    );
+
+    // New tasks should now be started with the new shell command.
+    assert_success(add_and_start_task(shared, "echo task").await?);
+    wait_for_task_condition(shared, 0, Task::is_done).await?;
+    let log = get_task_log(shared, 0, None).await?;
+    assert_eq!(log, "reloaded\ntask\n");
+
+    Ok(())
+}
+
+/// Settings that are only used during startup are reported as requiring a restart and their old
+/// values are kept until then.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reload_keeps_startup_settings() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut settings = daemon.settings.clone();
+    settings.daemon.reattach_tasks = !settings.daemon.reattach_tasks;
+    settings.daemon.compress_state_file = !settings.daemon.compress_state_file;
+    settings.daemon.callback_log_lines += 1;
+    settings.save(&Some(daemon.tempdir.path().join("pueue.yml")))?;
+
+    let response = send_request(shared, Request::ReloadConfig).await?;
+    assert_eq!(
+        response,
+        Response::ConfigReloaded(ConfigReloadResponse {
+            applied: vec!["daemon.callback_log_lines".into()],
+            requires_restart: vec![
+                "daemon.compress_state_file".into(),
+                "daemon.reattach_tasks".into(),
+            ],
+        })
+    );
+
+    // The old values are still in use, so they're reported once more.
+    let response = send_request(shared, Request::ReloadConfig).await?;
+    assert_eq!(
+        response,
+        Response::ConfigReloaded(ConfigReloadResponse {
+            applied: Vec::new(),
+            requires_restart: vec![
+                "daemon.compress_state_file".into(),
+                "daemon.reattach_tasks".into(),
+            ],
+        })
+    );
+
+    Ok(())
+}
+
+/// An invalid configuration file must be rejected and the old settings must be kept.
+#[rstest]
+#[case::callback("daemon:\n  callback: \"{{ unclosed\"\n")]
+#[case::webhook_url("daemon:\n  webhooks:\n    - url: \"http//localhost:8080\"\n")]
+#[case::webhook_scheme("daemon:\n  webhooks:\n    - url: \"ftp://localhost\"\n")]
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_reload_invalid_config(#[case] config: &str) -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    write(daemon.tempdir.path().join("pueue.yml"), config)?;
+
+    let response = send_request(shared, Request::ReloadConfig).await?;
+    assert_failure(response);
+
+    // The daemon should still work as before.
+    assert_success(add_and_start_task(shared, "ls").await?);
+    let task = wait_for_task_condition(shared, 0, Task::is_done).await?;
+    assert!(!task.failed());
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
-use pueue_lib::{GroupStatus, message::TaskSelection};
+#[cfg(feature = "sqlite")]
+use pueue_lib::settings::StateBackend;
+use pueue_lib::{GroupStatus, TaskResult, TaskStatus, message::TaskSelection};
 use rstest::rstest;
 
 use crate::{helper::*, internal_prelude::*};
@@ -75,3 +77,122 @@ async fn test_start_paused(#[case] compress: bool) -> Result<()> {
     child.kill()?;
     Ok(())
 }
+
+/// The state should survive a restart with the SQLite backend and should be migrated back,
+/// once the backend is switched to JSON again.
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn test_restore_sqlite_backend() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.state_backend = StateBackend::Sqlite;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+    assert_success(create_stashed_task(shared, "ls", None).await?);
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+    assert!(tempdir.path().join("state.sqlite").exists());
+
+    // The task is restored from the database.
+    let mut child = standalone_daemon(&settings.shared).await?;
+    assert_eq!(get_task(shared, 0).await?.command, "ls");
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+
+    // Switch back to JSON. The state is migrated and the database is removed.
+    settings.daemon.state_backend = StateBackend::Json;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+    let mut child = standalone_daemon(&settings.shared).await?;
+    assert_eq!(get_task(shared, 0).await?.command, "ls");
+    assert!(!tempdir.path().join("state.sqlite").exists());
+
+    child.kill()?;
+    Ok(())
+}
+
+/// Running tasks should survive a graceful restart of the daemon, if `reattach_tasks` is set.
+/// The restarted daemon re-adopts the task and picks up its exit code once it finishes.
+#[cfg(unix)]
+#[tokio::test]
+async fn test_reattach_running_task() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.reattach_tasks = true;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+
+    assert_success(add_task(shared, "sleep 3 && exit 3").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    // Shut down the daemon, while the task is still running.
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+
+    // Boot it up again. The task should still be running.
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let task = get_task(shared, 0).await?;
+    assert!(task.is_running(), "Task should still be running: {task:?}");
+
+    // The daemon should pick up the task's exit code.
+    let task = wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert!(
+        matches!(
+            task.status,
+            TaskStatus::Done {
+                result: TaskResult::Failed(3),
+                ..
+            }
+        ),
+        "Task should have failed with exit code 3: {task:?}"
+    );
+
+    child.kill()?;
+    Ok(())
+}
+
+/// Tasks that finish while no daemon is around should get their actual result on restore.
+#[cfg(unix)]
+#[tokio::test]
+async fn test_reattach_task_finished_during_restart() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    settings.daemon.reattach_tasks = true;
+    settings
+        .save(&Some(tempdir.path().join("pueue.yml")))
+        .context("Couldn't write pueue config to temporary directory")?;
+
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let shared = &settings.shared;
+
+    assert_success(add_task(shared, "sleep 1").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_running()).await?;
+
+    assert_success(shutdown_daemon(shared).await?);
+    wait_for_shutdown(&mut child).await?;
+
+    // Give the task enough time to finish while the daemon is down.
+    sleep_ms(1500).await;
+
+    let mut child = standalone_daemon(&settings.shared).await?;
+    let task = get_task(shared, 0).await?;
+    assert!(
+        matches!(
+            task.status,
+            TaskStatus::Done {
+                result: TaskResult::Success,
+                ..
+            }
+        ),
+        "Task should have finished successfully: {task:?}"
+    );
+
+    child.kill()?;
+    Ok(())
+}
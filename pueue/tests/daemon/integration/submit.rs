@@ -0,0 +1,95 @@
+use std::collections::BTreeMap;
+
+use assert_matches::assert_matches;
+use pueue_lib::{
+    Response,
+    message::{SubmitRequest, SubmittedTask},
+    task::*,
+};
+
+use crate::{helper::*, internal_prelude::*};
+
+/// Create a submitted task that depends on the given local names.
+fn submitted_task(command: &str, dependencies: &[&str]) -> SubmittedTask {
+    SubmittedTask {
+        command: command.into(),
+        path: std::env::temp_dir(),
+        group: PUEUE_DEFAULT_GROUP.into(),
+        dependencies: dependencies
+            .iter()
+            .map(|name| (name.to_string(), DependencyCondition::Success))
+            .collect(),
+        ..Default::default()
+    }
+}
+
+/// A diamond shaped workflow is added in one go and its dependencies are resolved to task ids.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_submit_workflow() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    // Add a task beforehand, so the workflow's ids don't start at 0.
+    assert_success(add_task(shared, "ls").await?);
+
+    let mut tasks = BTreeMap::new();
+    tasks.insert("build".to_string(), submitted_task("echo build", &[]));
+    tasks.insert("lint".to_string(), submitted_task("echo lint", &["build"]));
+    tasks.insert("test".to_string(), submitted_task("echo test", &["build"]));
+    tasks.insert(
+        "deploy".to_string(),
+        submitted_task("echo deploy", &["lint", "test"]),
+    );
+
+    let response = send_request(shared, SubmitRequest { tasks }).await?;
+    let Response::Submitted(response) = response else {
+        bail!("Expected a submitted response, got {response:?}");
+    };
+
+    let ids = response.task_ids;
+    assert_eq!(ids["build"], 1);
+    assert_eq!(ids["lint"], 2);
+    assert_eq!(ids["test"], 3);
+    assert_eq!(ids["deploy"], 4);
+
+    let state = get_state(shared).await?;
+    let deploy = &state.tasks[&4];
+    assert_eq!(deploy.dependencies, vec![2, 3]);
+    assert_eq!(deploy.label, Some("deploy".to_string()));
+
+    // The whole workflow runs through.
+    let task = wait_for_task_condition(shared, 4, Task::is_done).await?;
+    assert_matches!(
+        task.status,
+        TaskStatus::Done {
+            result: TaskResult::Success,
+            ..
+        },
+        "The last task of the workflow should succeed"
+    );
+
+    Ok(())
+}
+
+/// Workflows with cycles or unknown dependencies are rejected as a whole.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_submit_invalid_workflow() -> Result<()> {
+    let daemon = daemon().await?;
+    let shared = &daemon.settings.shared;
+
+    let mut tasks = BTreeMap::new();
+    tasks.insert("independent".to_string(), submitted_task("ls", &[]));
+    tasks.insert("a".to_string(), submitted_task("ls", &["b"]));
+    tasks.insert("b".to_string(), submitted_task("ls", &["a"]));
+    assert_failure(send_request(shared, SubmitRequest { tasks }).await?);
+
+    let mut tasks = BTreeMap::new();
+    tasks.insert("a".to_string(), submitted_task("ls", &["missing"]));
+    assert_failure(send_request(shared, SubmitRequest { tasks }).await?);
+
+    // Nothing has been added.
+    let state = get_state(shared).await?;
+    assert!(state.tasks.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,146 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::TcpListener,
+    sync::mpsc::{Receiver, channel},
+    thread,
+};
+
+use pueue_lib::settings::Webhook;
+use serde_json::Value;
+
+use crate::{helper::*, internal_prelude::*};
+
+/// A minimal HTTP server that stands in for a real webhook endpoint.
+///
+/// It answers the incoming requests with the given status codes in order and sends the body of
+/// every request through the returned channel.
+fn http_stand_in(status_codes: Vec<u16>) -> Result<(String, Receiver<String>)> {
+    let listener = TcpListener::bind("127.0.0.1:0")?;
+    let url = format!("http://{}/hook", listener.local_addr()?);
+    let (sender, receiver) = channel();
+
+    thread::spawn(move || {
+        for status_code in status_codes {
+            let Ok((mut stream, _)) = listener.accept() else {
+                return;
+            };
+
+            // Read the headers to find out how large the body is.
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line == "\r\n" {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            sender.send(String::from_utf8(body).unwrap()).unwrap();
+
+            let response = format!(
+                "HTTP/1.1 {status_code} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+
+    Ok((url, receiver))
+}
+
+/// Wait for the body of the next request.
+async fn next_request(receiver: &mut Receiver<String>) -> Result<String> {
+    // Wait up to 5s for the request to arrive.
+    let sleep = 50;
+    for _ in 0..100 {
+        if let Ok(body) = receiver.try_recv() {
+            return Ok(body);
+        }
+        sleep_ms(sleep).await;
+    }
+
+    bail!("Didn't receive a webhook request in time")
+}
+
+/// Make sure that finished tasks are reported to webhooks with the callback variables and the
+/// full task.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_webhook_payload() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let (url, mut receiver) = http_stand_in(vec![200])?;
+    settings.daemon.webhooks = vec![Webhook::new(url)];
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    // Quotes would break a `curl` call inside of a callback template.
+    assert_success(add_task(shared, "echo \"quoted 'output'\"").await?);
+
+    let body = next_request(&mut receiver).await?;
+    let payload: Value = serde_json::from_str(&body)?;
+    assert_eq!(payload["id"], "0");
+    assert_eq!(payload["result"], "Success");
+    assert_eq!(payload["exit_code"], "0");
+    assert_eq!(payload["group"], PUEUE_DEFAULT_GROUP);
+    assert_eq!(payload["output"], "quoted 'output'");
+    assert_eq!(payload["task"]["command"], "echo \"quoted 'output'\"");
+    // The captured environment may contain credentials.
+    assert_eq!(payload["task"].get("envs"), None);
+
+    Ok(())
+}
+
+/// Make sure that failed requests are retried.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_webhook_retry() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let (url, mut receiver) = http_stand_in(vec![500, 200])?;
+    let mut webhook = Webhook::new(url);
+    webhook.retries = 1;
+    settings.daemon.webhooks = vec![webhook];
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "ls").await?);
+
+    // The first request fails, the second one is the retry.
+    let first = next_request(&mut receiver).await?;
+    let second = next_request(&mut receiver).await?;
+    assert_eq!(first, second);
+
+    Ok(())
+}
+
+/// Webhooks that're restricted to some groups don't get notified about other groups.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_webhook_groups() -> Result<()> {
+    let (mut settings, tempdir) = daemon_base_setup()?;
+    let (url, mut receiver) = http_stand_in(vec![200])?;
+    let mut webhook = Webhook::new(url);
+    webhook.groups = vec!["test_2".into()];
+    settings.daemon.webhooks = vec![webhook];
+    settings.save(&Some(tempdir.path().join("pueue.yml")))?;
+
+    let daemon = daemon_with_settings(settings, tempdir).await?;
+    let shared = &daemon.settings.shared;
+
+    assert_success(add_task(shared, "ls").await?);
+    wait_for_task_condition(shared, 0, |task| task.is_done()).await?;
+    assert_success(add_task_to_group(shared, "ls", "test_2").await?);
+
+    let body = next_request(&mut receiver).await?;
+    let payload: Value = serde_json::from_str(&body)?;
+    assert_eq!(payload["id"], "1");
+
+    Ok(())
+}
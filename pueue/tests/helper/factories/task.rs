@@ -1,4 +1,4 @@
-use pueue_lib::{message::*, settings::*};
+use pueue_lib::{message::*, settings::*, task::DependencyCondition};
 
 use crate::helper::*;
 
@@ -40,7 +40,10 @@ pub async fn add_task_with_dependencies(
     dependencies: Vec<usize>,
 ) -> Result<Response> {
     let mut message = create_add_message(shared, command);
-    message.dependencies = dependencies;
+    message.dependency_conditions = dependencies
+        .into_iter()
+        .map(|id| (id, DependencyCondition::Success))
+        .collect();
 
     send_request(shared, message)
         .await
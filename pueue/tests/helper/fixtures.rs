@@ -110,13 +110,17 @@ pub async fn standalone_daemon(shared: &Shared) -> Result<Child> {
     let mut envs = HashMap::new();
     envs.insert("PUEUED_TEST_ENV_VARIABLE", "Test");
 
+    // The output is written to a file, as nobody reads it.
+    // A full pipe would otherwise block the daemon as soon as it logs enough.
+    let log_file = File::create(shared.pueue_directory().join("daemon.log"))?;
+
     let child = Command::cargo_bin("pueued")?
         .arg("--config")
         .arg(shared.pueue_directory().join("pueue.yml").to_str().unwrap())
         .arg("-vvv")
         .envs(envs)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
+        .stdout(Stdio::from(log_file.try_clone()?))
+        .stderr(Stdio::from(log_file))
         .spawn()?;
 
     let sleep = 50;
@@ -1,4 +1,7 @@
-use std::{collections::HashMap, env::vars};
+use std::{
+    collections::{BTreeMap, HashMap},
+    env::vars,
+};
 
 use chrono::{DateTime, Local};
 use pueue_lib::{
@@ -15,14 +18,17 @@ pub fn create_add_message<C: ToString>(shared: &Shared, command: C) -> AddReques
     AddRequest {
         command: command.to_string(),
         path: shared.pueue_directory(),
+        path_is_fallback: false,
         envs: HashMap::from_iter(vars()),
         start_immediately: false,
         stashed: false,
         group: PUEUE_DEFAULT_GROUP.to_string(),
         enqueue_at: None,
         dependencies: Vec::new(),
+        dependency_conditions: BTreeMap::new(),
         priority: None,
         label: None,
+        timeout: None,
     }
 }
 
@@ -1,10 +1,10 @@
 //! This contains the the [`Request`] and [`Response`]  enums and
 //! all their structs used to communicate with the daemon or client.
-use std::path::PathBuf;
+use std::{collections::BTreeMap, path::PathBuf};
 
 use serde::{Deserialize, Serialize};
 
-use crate::task::Task;
+use crate::{state::Group, task::Task};
 
 pub mod request;
 pub mod response;
@@ -44,3 +44,15 @@ impl EditableTask {
         task.priority = self.priority;
     }
 }
+
+/// A portable set of tasks, which can be moved from one daemon to another.
+/// It's created via a [`Request::Export`] and recreated via a [`Request::Import`].
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TaskExport {
+    pub tasks: Vec<Task>,
+    /// The configuration of all groups the exported tasks belong to.
+    pub groups: BTreeMap<String, Group>,
+    /// The log output of the exported tasks by their task id, if logs have been exported.
+    #[serde(default)]
+    pub logs: BTreeMap<usize, String>,
+}
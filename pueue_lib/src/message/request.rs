@@ -1,10 +1,16 @@
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
 
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString, VariantNames};
 
-use crate::message::EditableTask;
+use crate::{
+    message::{EditableTask, TaskExport},
+    task::DependencyCondition,
+};
 
 /// Macro to simplify creating [From] implementations for each variant-contained
 /// Request; e.g. `impl_into_request!(AddRequest, Request::Add)` to make it possible
@@ -25,6 +31,15 @@ macro_rules! impl_into_request {
 pub enum Request {
     /// Add a new task to the daemon.
     Add(AddRequest),
+    /// Add many similar tasks at once, which are managed as a single array.
+    AddArray(AddArrayRequest),
+    /// Stash the queued members of a task array and kill its running members.
+    KillArray(String),
+    /// Restart the failed members of a task array in place.
+    RestartArray(String),
+    /// Add a whole graph of tasks at once.
+    /// Either all tasks are added or none of them.
+    Submit(SubmitRequest),
     /// Remove non-running/paused tasks.
     Remove(Vec<usize>),
     /// Switch two enqueued/stashed tasks.
@@ -77,6 +92,18 @@ pub enum Request {
     Clean(CleanRequest),
     /// Initiate shutdown on the daemon.
     DaemonShutdown(ShutdownRequest),
+    /// Re-read the daemon's configuration file and apply all changes that can be applied live.
+    ReloadConfig,
+    /// Request the most recent runs of callbacks and hooks.
+    Callbacks,
+    /// Request all tasks of the archive.
+    History,
+    /// Export a [`TaskSelection`] including the involved groups.
+    Export(ExportRequest),
+    /// Recreate previously exported tasks.
+    Import(ImportRequest),
+    /// List or restore snapshots of tasks that have been taken before destructive operations.
+    Snapshot(SnapshotRequest),
 }
 
 /// This enum is used to express a selection of tasks.
@@ -93,14 +120,29 @@ pub enum TaskSelection {
 pub struct AddRequest {
     pub command: String,
     pub path: PathBuf,
+    /// Whether `path` is only the client's current directory, as no working directory has been
+    /// specified. The group's default working directory is used instead, if it has one.
+    #[serde(default)]
+    pub path_is_fallback: bool,
     pub envs: HashMap<String, String>,
     pub start_immediately: bool,
     pub stashed: bool,
     pub group: String,
     pub enqueue_at: Option<DateTime<Local>>,
+    /// Deprecated: Use `dependency_conditions` instead.
+    ///
+    /// This is only kept for older clients. The daemon adds these ids to `dependency_conditions`
+    /// with the [DependencyCondition::Success] condition.
+    #[serde(default)]
     pub dependencies: Vec<usize>,
+    /// The ids of all dependencies and the condition under which each of them is fulfilled.
+    #[serde(default)]
+    pub dependency_conditions: BTreeMap<usize, DependencyCondition>,
     pub priority: Option<i32>,
     pub label: Option<String>,
+    /// Kill the task once it has been running for this many seconds.
+    #[serde(default)]
+    pub timeout: Option<u64>,
 }
 
 /// We use a custom `Debug` implementation for [AddRequest], as the `envs` field just has
@@ -113,18 +155,109 @@ impl std::fmt::Debug for AddRequest {
         f.debug_struct("Task")
             .field("command", &self.command)
             .field("path", &self.path)
+            .field("path_is_fallback", &self.path_is_fallback)
             .field("envs", &"hidden")
             .field("start_immediately", &self.start_immediately)
             .field("stashed", &self.stashed)
             .field("group", &self.group)
             .field("enqueue_at", &self.enqueue_at)
             .field("dependencies", &self.dependencies)
+            .field("dependency_conditions", &self.dependency_conditions)
             .field("label", &self.label)
+            .field("timeout", &self.timeout)
             .finish()
     }
 }
 impl_into_request!(AddRequest, Request::Add);
 
+/// Add one task per command, all of which share the same settings.
+///
+/// All tasks become members of a task array, which can be controlled as a unit.
+/// Each member gets its index inside the array via the `PUEUE_ARRAY_INDEX` environment variable.
+#[derive(PartialEq, Eq, Clone, Default, Deserialize, Serialize)]
+pub struct AddArrayRequest {
+    /// The name of the array, which is also used as the label of its members.
+    /// The daemon picks a name, if none is given.
+    pub name: Option<String>,
+    /// The commands of the members, in the order of their index.
+    pub commands: Vec<String>,
+    pub path: PathBuf,
+    /// See [AddRequest::path_is_fallback].
+    #[serde(default)]
+    pub path_is_fallback: bool,
+    pub envs: HashMap<String, String>,
+    pub group: String,
+    pub stashed: bool,
+    pub priority: Option<i32>,
+    pub timeout: Option<u64>,
+}
+
+/// We use a custom `Debug` implementation for [AddArrayRequest], as the `envs` field just has
+/// too much info in it and makes the log output much too verbose.
+impl std::fmt::Debug for AddArrayRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AddArrayRequest")
+            .field("name", &self.name)
+            .field("commands", &self.commands)
+            .field("path", &self.path)
+            .field("path_is_fallback", &self.path_is_fallback)
+            .field("envs", &"hidden")
+            .field("group", &self.group)
+            .field("stashed", &self.stashed)
+            .field("priority", &self.priority)
+            .field("timeout", &self.timeout)
+            .finish()
+    }
+}
+impl_into_request!(AddArrayRequest, Request::AddArray);
+
+/// A graph of tasks that're identified by local names.
+///
+/// The daemon makes sure that the graph doesn't contain cycles and adds all tasks in one go.
+/// The ids are assigned in topological order, so dependencies always have a smaller id than
+/// their dependants.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SubmitRequest {
+    /// All tasks of the graph by their local name.
+    pub tasks: BTreeMap<String, SubmittedTask>,
+}
+impl_into_request!(SubmitRequest, Request::Submit);
+
+/// A single task of a [SubmitRequest].
+#[derive(PartialEq, Eq, Clone, Default, Deserialize, Serialize)]
+pub struct SubmittedTask {
+    pub command: String,
+    pub path: PathBuf,
+    /// See [AddRequest::path_is_fallback].
+    #[serde(default)]
+    pub path_is_fallback: bool,
+    pub envs: HashMap<String, String>,
+    pub group: String,
+    pub stashed: bool,
+    pub priority: Option<i32>,
+    pub label: Option<String>,
+    /// The local names of this task's dependencies and their conditions.
+    pub dependencies: BTreeMap<String, DependencyCondition>,
+}
+
+/// We use a custom `Debug` implementation for [SubmittedTask], as the `envs` field just has
+/// too much info in it and makes the log output much too verbose.
+impl std::fmt::Debug for SubmittedTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubmittedTask")
+            .field("command", &self.command)
+            .field("path", &self.path)
+            .field("path_is_fallback", &self.path_is_fallback)
+            .field("envs", &"hidden")
+            .field("group", &self.group)
+            .field("stashed", &self.stashed)
+            .field("priority", &self.priority)
+            .field("label", &self.label)
+            .field("dependencies", &self.dependencies)
+            .finish()
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub struct SwitchRequest {
     pub task_id_1: usize,
@@ -240,9 +373,28 @@ pub enum GroupRequest {
     },
     Remove(String),
     List,
+    /// Change the defaults that're applied to all tasks of a group.
+    Edit(GroupEditRequest),
 }
 impl_into_request!(GroupRequest, Request::Group);
 
+/// Change the task defaults of an existing group.
+/// Only the specified properties are changed.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct GroupEditRequest {
+    pub name: String,
+    /// Environment variables that should be set for all tasks of the group.
+    pub set_envs: HashMap<String, String>,
+    /// Environment variables that should no longer be set for tasks of the group.
+    pub unset_envs: Vec<String>,
+    pub working_directory: Option<PathBuf>,
+    /// Remove the group's default working directory.
+    pub clear_working_directory: bool,
+    pub shell_command: Option<Vec<String>>,
+    /// Remove the group's shell command, which means the daemon's shell command is used again.
+    pub clear_shell_command: bool,
+}
+
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub enum ResetTarget {
     // Reset all groups
@@ -308,3 +460,29 @@ pub struct ParallelRequest {
     pub group: String,
 }
 impl_into_request!(ParallelRequest, Request::Parallel);
+
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct ExportRequest {
+    pub tasks: TaskSelection,
+    /// Whether the log output of the tasks should be exported as well.
+    pub logs: bool,
+}
+impl_into_request!(ExportRequest, Request::Export);
+
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct ImportRequest {
+    pub export: TaskExport,
+    /// Whether unfinished tasks should be enqueued right away.
+    /// By default, they're stashed.
+    pub enqueue: bool,
+}
+impl_into_request!(ImportRequest, Request::Import);
+
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub enum SnapshotRequest {
+    List,
+    /// Restore the tasks of a snapshot as stashed tasks and remove the snapshot.
+    /// The most recent snapshot is restored, if no id is given.
+    Restore(Option<usize>),
+}
+impl_into_request!(SnapshotRequest, Request::Snapshot);
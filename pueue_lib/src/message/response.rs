@@ -4,7 +4,7 @@ use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    message::EditableTask,
+    message::{EditableTask, TaskExport},
     state::{Group, State},
     task::Task,
 };
@@ -52,6 +52,27 @@ macro_rules! impl_into_response {
 pub enum Response {
     AddedTask(AddedTaskResponse),
 
+    /// The name and the member ids of an array that has been added.
+    AddedArray(AddedArrayResponse),
+
+    /// All tasks of the archive.
+    History(HistoryResponse),
+
+    /// The ids of all tasks that have been added by a [`super::Request::Submit`].
+    Submitted(SubmittedResponse),
+
+    /// The exported tasks.
+    /// This is the response to [`super::Request::Export`]
+    Export(Box<TaskExport>),
+
+    /// The ids of all tasks that have been added by a [`super::Request::Import`] or by
+    /// restoring a snapshot.
+    Imported(ImportedResponse),
+
+    /// All snapshots, from the oldest to the most recent one.
+    /// This is the response to [`super::SnapshotRequest::List`]
+    Snapshots(SnapshotsResponse),
+
     /// The daemon locked the tasks and responds with the tasks' details.
     Edit(Vec<EditableTask>),
 
@@ -66,6 +87,14 @@ pub enum Response {
     /// The next chunk of output, that's send to the client.
     Stream(StreamResponse),
 
+    /// The daemon reloaded its configuration.
+    /// This is the response to [`super::Request::ReloadConfig`]
+    ConfigReloaded(ConfigReloadResponse),
+
+    /// The most recent runs of callbacks and hooks.
+    /// This is the response to [`super::Request::Callbacks`]
+    Callbacks(CallbacksResponse),
+
     Success(String),
     Failure(String),
 
@@ -76,7 +105,15 @@ pub enum Response {
 
 impl Response {
     pub fn success(&self) -> bool {
-        matches!(&self, Self::AddedTask(_) | Self::Success(_))
+        matches!(
+            &self,
+            Self::AddedTask(_)
+                | Self::AddedArray(_)
+                | Self::Submitted(_)
+                | Self::Imported(_)
+                | Self::ConfigReloaded(_)
+                | Self::Success(_)
+        )
     }
 }
 
@@ -88,6 +125,59 @@ pub struct AddedTaskResponse {
 }
 impl_into_response!(AddedTaskResponse, Response::AddedTask);
 
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct AddedArrayResponse {
+    pub name: String,
+    /// The ids of the array's members, in the order of their index.
+    pub task_ids: Vec<usize>,
+}
+impl_into_response!(AddedArrayResponse, Response::AddedArray);
+
+/// The tasks of the archive, from the oldest to the most recently archived one.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HistoryResponse {
+    pub tasks: Vec<Task>,
+}
+impl_into_response!(HistoryResponse, Response::History);
+
+/// The ids that have been assigned to the tasks of a submitted graph.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SubmittedResponse {
+    /// The task ids by the local names of the tasks.
+    pub task_ids: BTreeMap<String, usize>,
+}
+impl_into_response!(SubmittedResponse, Response::Submitted);
+
+/// The ids that have been assigned to imported tasks.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ImportedResponse {
+    /// The new task ids by the ids the tasks had when they were exported.
+    pub task_ids: BTreeMap<usize, usize>,
+    /// Dependencies on tasks that weren't part of the export and which have thereby been dropped.
+    /// The dropped dependencies are listed by the id the dependant task had when it was exported.
+    #[serde(default)]
+    pub dropped_dependencies: BTreeMap<usize, Vec<usize>>,
+}
+impl_into_response!(ImportedResponse, Response::Imported);
+
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SnapshotsResponse {
+    pub snapshots: Vec<SnapshotInfo>,
+}
+impl_into_response!(SnapshotsResponse, Response::Snapshots);
+
+/// A summary of a snapshot of tasks, which has been taken before a destructive operation.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct SnapshotInfo {
+    pub id: usize,
+    pub created_at: DateTime<Local>,
+    /// The operation that caused the snapshot, e.g. `reset`.
+    pub operation: String,
+    /// The ids the tasks had when the snapshot was taken.
+    pub task_ids: Vec<usize>,
+    pub groups: Vec<String>,
+}
+
 /// Helper struct for sending tasks and their log output to the client.
 #[derive(PartialEq, Eq, Clone, Deserialize, Serialize)]
 pub struct TaskLogResponse {
@@ -125,3 +215,44 @@ pub struct StreamResponse {
     pub logs: BTreeMap<usize, String>,
 }
 impl_into_response!(StreamResponse, Response::Stream);
+
+/// The result of a configuration reload.
+///
+/// Keys are represented by their path in the configuration file, e.g. `daemon.callback`.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct ConfigReloadResponse {
+    /// Keys that changed and which are now used by the daemon.
+    pub applied: Vec<String>,
+    /// Keys that changed, but which cannot be changed while the daemon is running.
+    /// The daemon continues to use the old values until it's restarted.
+    pub requires_restart: Vec<String>,
+}
+impl_into_response!(ConfigReloadResponse, Response::ConfigReloaded);
+
+/// The most recent runs of callbacks and hooks, ordered from oldest to newest.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct CallbacksResponse {
+    pub runs: Vec<CallbackRun>,
+}
+impl_into_response!(CallbacksResponse, Response::Callbacks);
+
+/// A single, finished run of a callback or hook.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct CallbackRun {
+    /// The task that triggered the run.
+    /// This is `None` for hooks that aren't bound to a task, such as `on_group_empty`.
+    pub task_id: Option<usize>,
+    /// What triggered the run, i.e. `callback` or the name of a hook such as `on_failure`.
+    pub trigger: String,
+    /// The fully rendered command.
+    pub command: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    /// The exit code of the process.
+    /// This is `None`, if the process has been killed.
+    pub exit_code: Option<i32>,
+    /// Whether the process has been killed, as it ran longer than the `callback_timeout`.
+    pub timed_out: bool,
+    /// The combined stdout and stderr of the process.
+    pub output: String,
+}
@@ -26,3 +26,19 @@ pub(crate) fn default_status_datetime_format() -> String {
 pub(crate) fn default_callback_log_lines() -> usize {
     10
 }
+
+pub(crate) fn default_max_snapshots() -> usize {
+    20
+}
+
+pub(crate) fn default_parallel_tasks() -> usize {
+    1
+}
+
+pub(crate) fn default_webhook_retries() -> u32 {
+    3
+}
+
+pub(crate) fn default_webhook_timeout() -> u64 {
+    10
+}
@@ -1,15 +1,15 @@
 //! Pueue's configuration file representation.
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fs::{File, create_dir_all},
     io::{BufReader, prelude::*},
     path::{Path, PathBuf},
 };
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
 use shellexpand::tilde;
 
-use crate::{error::Error, internal_prelude::*, setting_defaults::*};
+use crate::{error::Error, internal_prelude::*, setting_defaults::*, state::Hooks};
 
 /// The environment variable that can be set to overwrite pueue's config path.
 pub const PUEUE_CONFIG_PATH_ENV: &str = "PUEUE_CONFIG_PATH";
@@ -94,6 +94,17 @@ pub enum EditMode {
     Files,
 }
 
+/// The storage backend the daemon uses to persist its state.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Deserialize, Serialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StateBackend {
+    /// A JSON snapshot (`state.json`) with an append-only journal of all changes since.
+    #[default]
+    Json,
+    /// An SQLite database (`state.sqlite`), in which tasks and groups are stored as rows.
+    Sqlite,
+}
+
 /// All settings which are used by the client
 #[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
 pub struct Client {
@@ -127,6 +138,17 @@ pub struct Client {
     /// The format that will be used to display datetime formats in `pueue status`.
     #[serde(default = "default_status_datetime_format")]
     pub status_datetime_format: String,
+    /// Named templates that can be instantiated via `pueue add --template`.
+    #[serde(default = "Default::default")]
+    pub templates: BTreeMap<String, Template>,
+    /// Named queries for `pueue status`, which can be used via `pueue status @name`.
+    #[serde(default = "Default::default")]
+    pub views: BTreeMap<String, String>,
+    /// Don't access this property directly, but rather use the getter with the same name.
+    ///
+    /// The location of the file in which `pueue context` stores the connection details of
+    /// daemons.
+    pub contexts_file: Option<PathBuf>,
 }
 
 /// All settings which are used by the daemon
@@ -146,14 +168,29 @@ pub struct Daemon {
     /// time. You can expect a ~10 compression ratio.
     #[serde(default = "Default::default")]
     pub compress_state_file: bool,
+    /// Where the daemon persists its state.
+    ///
+    /// The state is migrated automatically on the next start of the daemon when this is changed.
+    #[serde(default = "Default::default")]
+    pub state_backend: StateBackend,
     /// The callback that's called whenever a task finishes.
     pub callback: Option<String>,
+    /// Commands that're executed on specific events, such as a task failure.
+    #[serde(default = "Default::default")]
+    pub hooks: Hooks,
+    /// URLs that're notified via a JSON POST request whenever a task finishes.
+    #[serde(default = "Default::default")]
+    pub webhooks: Vec<Webhook>,
     /// Environment variables that can be will be injected into all executed processes.
     #[serde(default = "Default::default")]
     pub env_vars: HashMap<String, String>,
     /// The amount of log lines from stdout/stderr that are passed to the callback command.
     #[serde(default = "default_callback_log_lines")]
     pub callback_log_lines: usize,
+    /// The amount of seconds after which callbacks and hooks are killed.
+    /// They may run indefinitely, if this isn't set.
+    #[serde(default = "Default::default")]
+    pub callback_timeout: Option<u64>,
     /// The command that should be used for task and callback execution.
     /// The following are the only officially supported modi for Pueue.
     ///
@@ -171,6 +208,199 @@ pub struct Daemon {
     /// ];
     /// ```
     pub shell_command: Option<Vec<String>>,
+    /// If this is set to `true`, running tasks survive a restart of the daemon.
+    ///
+    /// Tasks are then spawned via a small supervisor process that records their exit status.
+    /// On shutdown, the daemon no longer kills its tasks and a restarted daemon re-adopts all
+    /// tasks that're still running.
+    ///
+    /// This is only supported on Linux and macOS.
+    #[serde(default = "Default::default")]
+    pub reattach_tasks: bool,
+    /// Groups that're declared in the configuration file.
+    ///
+    /// The daemon creates any missing groups from this list and updates the configuration of
+    /// existing ones on startup. On reload, only groups whose declaration changed are updated, so
+    /// runtime changes to other groups are kept.
+    /// Groups that aren't listed in here are left untouched.
+    #[serde(default = "Default::default", deserialize_with = "deserialize_groups")]
+    pub groups: BTreeMap<String, GroupSettings>,
+    /// Keep a record of cleaned and removed tasks in an archive.
+    #[serde(default = "Default::default")]
+    pub archive: Archive,
+    /// Automatically remove finished tasks from the state.
+    #[serde(default = "Default::default")]
+    pub cleanup: Cleanup,
+    /// The amount of snapshots that are kept of tasks affected by destructive operations, such as
+    /// `reset`, `clean` or `remove`. Older snapshots are removed.
+    /// Setting this to 0 disables snapshots.
+    #[serde(default = "default_max_snapshots")]
+    pub max_snapshots: usize,
+}
+
+/// The retention policy for finished tasks.
+///
+/// Tasks are removed in the same way as via `pueue clean`, so they're archived if the archive is
+/// enabled. Tasks that unfinished tasks depend on are always kept.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Cleanup {
+    /// Remove successful tasks that ended more than this many seconds ago.
+    #[serde(default = "Default::default")]
+    pub max_age_secs: Option<u64>,
+    /// A separate maximum age in seconds for failed tasks, e.g. to keep them around for longer.
+    /// Falls back to `max_age_secs`, if it isn't set.
+    #[serde(default = "Default::default")]
+    pub failed_max_age_secs: Option<u64>,
+    /// Only keep this many of the most recently finished successful tasks in each group.
+    #[serde(default = "Default::default")]
+    pub max_tasks_per_group: Option<usize>,
+    /// A separate limit for the failed tasks in each group, e.g. to keep more of them around.
+    /// Falls back to `max_tasks_per_group`, if it isn't set.
+    #[serde(default = "Default::default")]
+    pub failed_max_tasks_per_group: Option<usize>,
+}
+
+/// The archive is an append-only JSONL file in the `pueue_directory`.
+/// Finished tasks are written to it, whenever they're cleaned or removed from the state.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Archive {
+    /// Whether finished tasks should be archived at all.
+    #[serde(default = "Default::default")]
+    pub enabled: bool,
+    /// Prune archived tasks that finished more than this many days ago.
+    #[serde(default = "Default::default")]
+    pub max_age_days: Option<u64>,
+    /// Only keep this many of the most recently archived tasks.
+    #[serde(default = "Default::default")]
+    pub max_tasks: Option<usize>,
+}
+
+/// The declaration of a single group in the `daemon.groups` section.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct GroupSettings {
+    /// The amount of tasks that may run in parallel in this group. `0` means unlimited.
+    #[serde(default = "default_parallel_tasks")]
+    pub parallel_tasks: usize,
+    /// The priority of new tasks in this group, if no explicit priority is given.
+    #[serde(default = "Default::default")]
+    pub priority: Option<i32>,
+    /// Whether this group should be paused as soon as one of its tasks fails.
+    /// Falls back to `pause_group_on_failure` if not set.
+    #[serde(default = "Default::default")]
+    pub pause_on_failure: Option<bool>,
+    /// Environment variables that will be injected into all tasks of this group.
+    #[serde(default = "Default::default")]
+    pub envs: HashMap<String, String>,
+    /// The callback that's called whenever a task of this group finishes.
+    /// This replaces the global `callback` for this group.
+    #[serde(default = "Default::default")]
+    pub callback: Option<String>,
+    /// The working directory of new tasks in this group, if no explicit directory is given.
+    #[serde(default = "Default::default")]
+    pub working_directory: Option<PathBuf>,
+    /// The shell command that's used for tasks of this group.
+    /// This replaces the global `shell_command` for this group.
+    #[serde(default = "Default::default")]
+    pub shell_command: Option<Vec<String>>,
+    /// Hooks for events of this group.
+    /// These replace the global hooks of the same event for this group.
+    #[serde(default = "Default::default")]
+    pub hooks: Hooks,
+}
+
+/// Deserialize the `daemon.groups` section.
+///
+/// Old configuration files contain a `groups` section with the amount of parallel tasks of each
+/// group, e.g. `my_group: 2`. Groups have since been managed in the state, so those entries are
+/// ignored instead of (re-)creating groups and resetting their amount of parallel tasks.
+fn deserialize_groups<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, GroupSettings>, D::Error> {
+    let declarations = BTreeMap::<String, serde_yaml::Value>::deserialize(deserializer)?;
+
+    let mut groups = BTreeMap::new();
+    for (name, declaration) in declarations {
+        if declaration.is_number() {
+            continue;
+        }
+
+        let group = GroupSettings::deserialize(declaration).map_err(|err| {
+            serde::de::Error::custom(format!("Invalid declaration of group {name}: {err}"))
+        })?;
+        groups.insert(name, group);
+    }
+
+    Ok(groups)
+}
+
+impl Default for GroupSettings {
+    fn default() -> Self {
+        GroupSettings {
+            parallel_tasks: default_parallel_tasks(),
+            priority: None,
+            pause_on_failure: None,
+            envs: HashMap::new(),
+            callback: None,
+            working_directory: None,
+            shell_command: None,
+            hooks: Hooks::default(),
+        }
+    }
+}
+
+/// A URL that's notified via a JSON `POST` request whenever a task finishes.
+///
+/// The payload contains the same variables that're available in the callback template, as well as
+/// the full task.
+#[derive(PartialEq, Eq, Clone, Debug, Deserialize, Serialize)]
+pub struct Webhook {
+    /// The URL to which the payload is sent.
+    pub url: String,
+    /// Additional headers, e.g. for authentication.
+    #[serde(default = "Default::default")]
+    pub headers: BTreeMap<String, String>,
+    /// Only notify about tasks of these groups. Tasks of all groups are reported if this is empty.
+    #[serde(default = "Default::default")]
+    pub groups: Vec<String>,
+    /// How often a failed request is retried.
+    #[serde(default = "default_webhook_retries")]
+    pub retries: u32,
+    /// The timeout of a single request in seconds.
+    #[serde(default = "default_webhook_timeout")]
+    pub timeout: u64,
+}
+
+impl Webhook {
+    pub fn new(url: String) -> Self {
+        Webhook {
+            url,
+            headers: BTreeMap::new(),
+            groups: Vec::new(),
+            retries: default_webhook_retries(),
+            timeout: default_webhook_timeout(),
+        }
+    }
+}
+
+/// A named blueprint for tasks, which is instantiated via `pueue add --template NAME KEY=VALUE`.
+///
+/// The command, label, working directory and environment variable values can contain
+/// `{{parameter}}` placeholders. Parameters without a default have to be specified.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Template {
+    pub command: String,
+    /// Default values of parameters.
+    #[serde(default = "Default::default")]
+    pub defaults: BTreeMap<String, String>,
+    pub group: Option<String>,
+    pub priority: Option<i32>,
+    pub label: Option<String>,
+    /// Environment variables that're set on top of the current environment.
+    #[serde(default = "Default::default")]
+    pub env: BTreeMap<String, String>,
+    pub working_directory: Option<PathBuf>,
+    /// Kill the task once it has been running for this many seconds.
+    pub timeout: Option<u64>,
 }
 
 impl Default for Shared {
@@ -209,6 +439,9 @@ impl Default for Client {
             max_status_lines: None,
             status_time_format: default_status_time_format(),
             status_datetime_format: default_status_datetime_format(),
+            templates: BTreeMap::new(),
+            views: BTreeMap::new(),
+            contexts_file: None,
         }
     }
 }
@@ -219,10 +452,19 @@ impl Default for Daemon {
             pause_group_on_failure: false,
             pause_all_on_failure: false,
             callback: None,
+            hooks: Hooks::default(),
+            webhooks: Vec::new(),
             callback_log_lines: default_callback_log_lines(),
+            callback_timeout: None,
             compress_state_file: false,
+            state_backend: StateBackend::default(),
             shell_command: None,
             env_vars: HashMap::new(),
+            reattach_tasks: false,
+            groups: BTreeMap::new(),
+            archive: Archive::default(),
+            cleanup: Cleanup::default(),
+            max_snapshots: default_max_snapshots(),
         }
     }
 }
@@ -357,6 +599,19 @@ impl Shared {
     }
 }
 
+impl Client {
+    /// The location of the file with all contexts of `pueue context`.
+    pub fn contexts_file(&self) -> PathBuf {
+        if let Some(path) = &self.contexts_file {
+            expand_home(path)
+        } else if let Some(config_dir) = default_configuration_directory() {
+            config_dir.join("pueue_contexts.yml")
+        } else {
+            PathBuf::from("pueue_contexts.yml")
+        }
+    }
+}
+
 impl Settings {
     /// Try to read existing config files, while using default values for non-existing fields.
     /// If successful, this will return a full config as well as a boolean on whether we found an
@@ -1,6 +1,9 @@
 //! The representation of the pueue daemon's current [State].
 //! Contains all [`Task`]s and [`Group`]s of the daemon.
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
 
 use serde::{Deserialize, Serialize};
 
@@ -24,6 +27,86 @@ pub enum GroupStatus {
 pub struct Group {
     pub status: GroupStatus,
     pub parallel_tasks: usize,
+    /// The priority of new tasks in this group, if no explicit priority is given.
+    #[serde(default)]
+    pub priority: Option<i32>,
+    /// Whether this group should be paused as soon as one of its tasks fails.
+    /// If this isn't set, the daemon's `pause_group_on_failure` setting is used.
+    #[serde(default)]
+    pub pause_on_failure: Option<bool>,
+    /// Environment variables that're injected into all tasks of this group.
+    #[serde(default)]
+    pub envs: HashMap<String, String>,
+    /// The callback that's called instead of the daemon's callback for tasks of this group.
+    #[serde(default)]
+    pub callback: Option<String>,
+    /// The working directory of new tasks in this group, if no explicit directory is given.
+    #[serde(default)]
+    pub working_directory: Option<PathBuf>,
+    /// The shell command that's used instead of the daemon's `shell_command` for tasks of this
+    /// group.
+    #[serde(default)]
+    pub shell_command: Option<Vec<String>>,
+    /// Hooks that're used instead of the daemon's hooks for events of this group.
+    #[serde(default)]
+    pub hooks: Hooks,
+}
+
+/// Commands that're executed when specific events happen.
+///
+/// Hooks are handlebars templates, just like the daemon's `callback`.
+/// They can be set globally and per group. A group's hook replaces the global hook of the same
+/// event for this group.
+#[derive(PartialEq, Eq, Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Hooks {
+    /// Executed whenever a task is started.
+    #[serde(default)]
+    pub on_start: Option<String>,
+    /// Executed whenever a task finishes successfully.
+    #[serde(default)]
+    pub on_success: Option<String>,
+    /// Executed whenever a task finishes without success, e.g. if it failed or has been killed.
+    #[serde(default)]
+    pub on_failure: Option<String>,
+    /// Executed when the last queued or running task of a group finished.
+    #[serde(default)]
+    pub on_group_empty: Option<String>,
+    /// Executed once the daemon started.
+    /// The hooks of groups are executed once per group.
+    #[serde(default)]
+    pub on_daemon_start: Option<String>,
+}
+
+impl Hooks {
+    /// Iterate over all hooks that are set.
+    pub fn iter(&self) -> impl Iterator<Item = &String> {
+        [
+            &self.on_start,
+            &self.on_success,
+            &self.on_failure,
+            &self.on_group_empty,
+            &self.on_daemon_start,
+        ]
+        .into_iter()
+        .flatten()
+    }
+}
+
+impl Group {
+    /// Create a new group with the given status and amount of parallel tasks.
+    pub fn new(status: GroupStatus, parallel_tasks: usize) -> Group {
+        Group {
+            status,
+            parallel_tasks,
+            priority: None,
+            pause_on_failure: None,
+            envs: HashMap::new(),
+            callback: None,
+            working_directory: None,
+            shell_command: None,
+            hooks: Hooks::default(),
+        }
+    }
 }
 
 /// This is the full representation of the current state of the Pueue daemon.
@@ -1,9 +1,12 @@
 //! Everything regarding Pueue's [Task]s.
-use std::{collections::HashMap, path::PathBuf};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+};
 
 use chrono::prelude::*;
 use serde::{Deserialize, Serialize};
-use strum::Display;
+use strum::{Display, EnumString};
 
 /// This enum represents the status of the internal task handling of Pueue.
 /// They basically represent the internal task life-cycle.
@@ -49,9 +52,43 @@ pub enum TaskResult {
     /// Some kind of IO error. This should barely ever happen. Please check the daemon logs.
     Errored,
     /// A dependency of the task failed.
+    /// This is also used, if the condition of a dependency can no longer be met, e.g. a task that
+    /// should only run after a failure, but the dependency succeeded.
     DependencyFailed,
 }
 
+/// The condition under which a dependency is considered to be fulfilled.
+#[derive(
+    PartialEq, Eq, Clone, Copy, Debug, Default, Display, EnumString, Serialize, Deserialize,
+)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum DependencyCondition {
+    /// The dependency finished successfully.
+    #[default]
+    Success,
+    /// The dependency finished, but didn't succeed.
+    Failure,
+    /// The dependency finished, regardless of its result.
+    Completion,
+}
+
+impl DependencyCondition {
+    /// Check whether a dependency fulfills this condition.
+    ///
+    /// Returns `None` as long as the dependency didn't finish yet.
+    pub fn is_met(&self, dependency: &Task) -> Option<bool> {
+        if !dependency.is_done() {
+            return None;
+        }
+
+        Some(match self {
+            DependencyCondition::Success => !dependency.failed(),
+            DependencyCondition::Failure => dependency.failed(),
+            DependencyCondition::Completion => true,
+        })
+    }
+}
+
 /// Representation of a task.
 #[derive(PartialEq, Eq, Clone, Deserialize, Serialize)]
 pub struct Task {
@@ -63,8 +100,19 @@ pub struct Task {
     pub envs: HashMap<String, String>,
     pub group: String,
     pub dependencies: Vec<usize>,
+    /// The conditions of dependencies that shouldn't simply succeed.
+    /// Dependencies without an entry in here have to finish successfully.
+    #[serde(default)]
+    pub dependency_conditions: BTreeMap<usize, DependencyCondition>,
     pub priority: i32,
     pub label: Option<String>,
+    /// The task is killed once it has been running for this many seconds.
+    /// Time the task spends paused doesn't count towards this.
+    #[serde(default)]
+    pub timeout: Option<u64>,
+    /// The name of the task array this task is a member of.
+    #[serde(default)]
+    pub array: Option<String>,
     pub status: TaskStatus,
 }
 
@@ -89,12 +137,23 @@ impl Task {
             envs,
             group,
             dependencies,
+            dependency_conditions: BTreeMap::new(),
             priority,
             label,
+            timeout: None,
+            array: None,
             status: starting_status.clone(),
         }
     }
 
+    /// Get the condition under which a specific dependency is fulfilled.
+    pub fn dependency_condition(&self, dependency_id: usize) -> DependencyCondition {
+        self.dependency_conditions
+            .get(&dependency_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
     pub fn start_and_end(&self) -> (Option<DateTime<Local>>, Option<DateTime<Local>>) {
         match self.status {
             TaskStatus::Running { start, .. } => (Some(start), None),
@@ -165,9 +224,12 @@ impl std::fmt::Debug for Task {
             .field("envs", &"hidden")
             .field("group", &self.group)
             .field("dependencies", &self.dependencies)
+            .field("dependency_conditions", &self.dependency_conditions)
             .field("label", &self.label)
             .field("status", &self.status)
             .field("priority", &self.priority)
+            .field("timeout", &self.timeout)
+            .field("array", &self.array)
             .finish()
     }
 }